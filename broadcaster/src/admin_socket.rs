@@ -35,6 +35,7 @@ pub async fn admin_spawn_pubsub_listener(clients: ClientList) {
     pubsub.subscribe("vote_channel").await.expect("subscribe failed");
     pubsub.subscribe("rushee").await.expect("subscribe failed");
     pubsub.subscribe("question").await.expect("subscribe failed");
+    pubsub.subscribe("attendance").await.expect("subscribe failed");
 
     tokio::spawn(async move {
         let mut stream = pubsub.on_message();
@@ -81,6 +82,13 @@ pub async fn admin_spawn_pubsub_listener(clients: ClientList) {
                         });
                         broadcast_to_clients(&clients, msg.to_string());
                     }
+                    "attendance" => {
+                        let msg = serde_json::json!({
+                            "type": "head_count_update",
+                            "head_count": payload
+                        });
+                        broadcast_to_clients(&clients, msg.to_string());
+                    }
                     _ => {}
                 }
             }
@@ -185,6 +193,20 @@ async fn handle_socket(
         }
     }
 
+    // Initial head count snapshot for tonight's rush night
+    match conn.get::<_, Option<String>>("head_count").await {
+        Ok(data) => {
+            let msg = serde_json::json!({
+                "type": "head_count_update",
+                "head_count": data
+            });
+            let _ = tx.send(Message::Text(msg.to_string()));
+        }
+        Err(e) => {
+            println!("Redis error while fetching head_count: {}", e);
+        }
+    }
+
     // Task to send messages to client
     let send_task = tokio::spawn(async move {
         while let Some(msg) = rx.recv().await {
@@ -29,6 +29,7 @@ pub async fn spawn_pubsub_listener(clients: ClientList) {
     let mut pubsub = get_redis_pubsub().await;
     pubsub.subscribe("rushee").await.expect("subscribe failed");
     pubsub.subscribe("question").await.expect("subscribe failed");
+    pubsub.subscribe("pis").await.expect("subscribe failed");
 
     tokio::spawn(async move {
         let mut stream = pubsub.on_message();
@@ -44,6 +45,11 @@ pub async fn spawn_pubsub_listener(clients: ClientList) {
                         "type": "question_update",
                         "question": payload
                     }),
+                    // brothers pick out the PIS they're on from the names in the payload
+                    "pis" => serde_json::json!({
+                        "type": "pis_update",
+                        "pis": payload
+                    }),
                     _ => continue,
                 };
                 broadcast_to_clients(&clients, msg.to_string());
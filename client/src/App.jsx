@@ -20,6 +20,7 @@ import PIS from './pages/PIS';
 import MyError from './components/Error';
 import Admin from './pages/Admin';
 import Attendance from './pages/Attendance';
+import FaceAttendance from './pages/FaceAttendance';
 import BrotherPIS from './pages/BrotherPIS';
 import AddTimeslotPage from './pages/AddTimeslotPage';
 import AddPIS from './pages/AddPIS';
@@ -53,6 +54,7 @@ function App() {
           <Route path='/rushee/:gtid/:link' element={<RusheePage/>} />
           <Route path='/pis/:gtid' element={<PIS/>} />
           <Route path='/attendance' element={<Attendance/>} />
+          <Route path='/face-attendance' element={<FaceAttendance/>} />
           <Route path='/comments' element={<Comments />} />
           {/* <Route path='/brother/pis' element={<BrotherPIS/>} />
           <Route path='/brother/dashboard' element={<PISDashboard/>} /> */}
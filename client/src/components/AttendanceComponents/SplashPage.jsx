@@ -86,12 +86,30 @@ export default function SplashPage(props) {
                         className={`input-apple w-full text-apple-body ${error ? "border-red-500 focus:ring-red-500" : ""}`}
                         placeholder="Enter your GTID"
                     />
+
+                    <input
+                        type="text"
+                        value={props.accessCode}
+                        onChange={(e) => props.setAccessCode(e.target.value)}
+                        className="input-apple w-full text-apple-body"
+                        placeholder="Enter your access code"
+                    />
+
+                    {props.showTokenInput && (
+                        <input
+                            type="text"
+                            value={props.token}
+                            onChange={(e) => props.setToken(e.target.value)}
+                            className="input-apple w-full text-apple-body"
+                            placeholder="Enter the check-in code from the QR code"
+                        />
+                    )}
                     
                     <button
                         onClick={props.func}
-                        disabled={!inputValue.trim() || error}
+                        disabled={!inputValue.trim() || error || !props.accessCode.trim() || !props.token.trim()}
                         className={`w-full py-4 px-6 text-apple-headline font-light rounded-apple-xl transition-all duration-200 ${
-                            inputValue.trim() && !error
+                            inputValue.trim() && !error && props.accessCode.trim() && props.token.trim()
                                 ? 'bg-black text-white hover:bg-apple-gray-800'
                                 : 'bg-apple-gray-200 text-apple-gray-400 cursor-not-allowed'
                         }`}
@@ -49,7 +49,10 @@ export default function AddPIS() {
                 updatedPayload.time = new Date(updatedPayload.time).toISOString();
             }
 
-            const response = await axios[method](`${apiBase}/${endpoint}`, updatedPayload);
+            // the admin key is saved by the Admin page
+            const config = { headers: { "x-rush-key": localStorage.getItem("rushKey") || "" } };
+
+            const response = await axios[method](`${apiBase}/${endpoint}`, updatedPayload, config);
             setResults(JSON.stringify(response.data, null, 2));
         } catch (error) {
             setResults(error.response?.data || "An error occurred");
@@ -14,6 +14,10 @@ export default function Admin() {
     const [rushNightName, setRushNightName] = useState("");
     const [rushNightTime, setRushNightTime] = useState("");
     const [results, setResults] = useState("");
+    const [rushNights, setRushNights] = useState([]);
+    const [rushNightId, setRushNightId] = useState("");
+    // admin endpoints check the x-rush-key header, the key is only kept in this browser
+    const [adminKey, setAdminKey] = useState(localStorage.getItem("rushKey") || "");
     const [loading, setLoading] = useState(true)
 
     const navigate = useNavigate()
@@ -58,13 +62,35 @@ export default function Admin() {
             updatedPayload.time = new Date(updatedPayload.time).toISOString();
           }
       
-          const response = await axios[method](`${apiBase}/${endpoint}`, updatedPayload);
+          const config = { headers: { "x-rush-key": adminKey } };
+          const response = method === "get"
+            ? await axios.get(`${apiBase}/${endpoint}`, config)
+            : await axios[method](`${apiBase}/${endpoint}`, updatedPayload, config);
           setResults(JSON.stringify(response.data, null, 2));
         } catch (error) {
           setResults(error.response?.data || "An error occurred");
         }
       };
 
+    const updateAdminKey = (key) => {
+        setAdminKey(key);
+        localStorage.setItem("rushKey", key);
+    };
+
+    const loadRushNights = async () => {
+        try {
+            const response = await axios.get(`${apiBase}/rush-nights`);
+
+            if (response.data.status === "success") {
+                setRushNights(response.data.payload);
+            }
+
+            setResults(JSON.stringify(response.data, null, 2));
+        } catch (error) {
+            setResults(error.response?.data || "An error occurred");
+        }
+    };
+
     const exportPISSchedule = async () => {
         try {
             const api = import.meta.env.VITE_API_PREFIX;
@@ -141,6 +167,18 @@ export default function Admin() {
         <div className="container mx-auto p-4">
             <h1 className="text-2xl font-bold mb-4">Admin Panel</h1>
 
+            {/* Admin Key */}
+            <div className="mb-6">
+                <h2 className="text-xl font-semibold">Admin Key</h2>
+                <input
+                    type="password"
+                    placeholder="Admin key"
+                    className="border p-2 w-full mb-2"
+                    value={adminKey}
+                    onChange={(e) => updateAdminKey(e.target.value)}
+                />
+            </div>
+
             {/* Add PIS Question */}
             <div className="mb-6">
                 <h2 className="text-xl font-semibold">Add PIS Question</h2>
@@ -310,16 +348,29 @@ export default function Admin() {
             {/* Delete Rush Night */}
             <div className="mb-6">
                 <h2 className="text-xl font-semibold">Delete Rush Night</h2>
-                <input
-                    type="datetime-local"
+                <button
+                    onClick={loadRushNights}
+                    className="bg-gray-500 text-white px-4 py-2 mb-2"
+                >
+                    Load Rush Nights
+                </button>
+                <select
                     className="border p-2 w-full mb-2"
-                    value={rushNightTime}
-                    onChange={(e) => setRushNightTime(e.target.value)}
-                />
+                    value={rushNightId}
+                    onChange={(e) => setRushNightId(e.target.value)}
+                >
+                    <option value="">Pick a rush night</option>
+                    {rushNights.filter((night) => night._id).map((night) => (
+                        <option key={night._id.$oid} value={night._id.$oid}>
+                            {night.name} ({new Date(parseInt(night.time.$date.$numberLong)).toLocaleString()})
+                        </option>
+                    ))}
+                </select>
                 <button
                     onClick={() =>
-                        handleRequest("delete_rush_night", { time: rushNightTime })
+                        handleRequest(`delete-rush-night/${rushNightId}`, {})
                     }
+                    disabled={!rushNightId}
                     className="bg-red-500 text-white px-4 py-2"
                 >
                     Delete Rush Night
@@ -10,7 +10,7 @@ import { verifyUser } from "../js/verifications";
 import axios from "axios";
 import DisplayInfo from "../components/AttendanceComponents/DisplayInfo";
 import SuccessPage from "../components/AttendanceComponents/SuccessPage";
-import { useNavigate } from "react-router-dom";
+import { useNavigate, useSearchParams } from "react-router-dom";
 
 export default function Attendance() {
 
@@ -19,6 +19,11 @@ export default function Attendance() {
     const [loading, setLoading] = useState()
     const [rushee, setRushee] = useState()
 
+    // the QR code at the door links here with tonight's check-in code, it can also be typed in
+    const [searchParams] = useSearchParams()
+    const [token, setToken] = useState(searchParams.get("token") ?? "")
+    const [accessCode, setAccessCode] = useState("")
+
     const api = import.meta.env.VITE_API_PREFIX;
 
     const navigate = useNavigate()
@@ -128,7 +133,10 @@ export default function Attendance() {
 
         setLoading(true)
 
-        await axios.post(`${api}/rushee/update-attendance/${gtid}`)
+        await axios.post(`${api}/rushee/update-attendance/${gtid}`, {
+            token: token.trim(),
+            access_code: accessCode.trim(),
+        })
         .then((response) => {
 
             if (response.data.status == "success") {
@@ -181,6 +189,11 @@ export default function Attendance() {
                     func={handleSubmit}
                     gtid={gtid}
                     setGtid={setGtid}
+                    token={token}
+                    setToken={setToken}
+                    showTokenInput={!searchParams.get("token")}
+                    accessCode={accessCode}
+                    setAccessCode={setAccessCode}
                 /> : <div>
 
                     {page == 1 ? <DisplayInfo
@@ -48,12 +48,7 @@ export default function BrotherPIS() {
                                     rushee_first_name: response.data.payload[slot].rushee_first_name,
                                     rushee_last_name: response.data.payload[slot].rushee_last_name,
                                     rushee_gtid: response.data.payload[slot].rushee_gtid,
-                                    first_brother_first_name: response.data.payload[slot].first_brother_first_name,
-                                    first_brother_last_name: response.data.payload[slot].first_brother_last_name,
-                                    second_brother_first_name: response.data.payload[slot].second_brother_first_name,
-                                    second_brother_last_name: response.data.payload[slot].second_brother_last_name,
-                                    third_brother_first_name: response.data.payload[slot].third_brother_first_name,
-                                    third_brother_last_name: response.data.payload[slot].third_brother_last_name,
+                                    panel: response.data.payload[slot].panel || [],
                                 };
                             
                                 if (tempDays.has(day)) {
@@ -209,16 +204,16 @@ export default function BrotherPIS() {
                                                     {slot.rushee_first_name}{" "}
                                                     {slot.rushee_last_name}
                                                 </p>
-                                                <p>
-                                                    <strong>First Brother:</strong>{" "}
-                                                    {slot.first_brother_first_name || "None"}{" "}
-                                                    {slot.first_brother_last_name || "None"}
-                                                </p>
-                                                <p>
-                                                    <strong>Second Brother:</strong>{" "}
-                                                    {slot.second_brother_first_name || "None"}{" "}
-                                                    {slot.second_brother_last_name || "None"}
-                                                </p>
+                                                {slot.panel.length === 0 ? (
+                                                    <p>
+                                                        <strong>Brothers:</strong> None
+                                                    </p>
+                                                ) : slot.panel.map((brother, idx) => (
+                                                    <p key={idx}>
+                                                        <strong>Brother {idx + 1}:</strong>{" "}
+                                                        {brother.first_name} {brother.last_name}
+                                                    </p>
+                                                ))}
                                             </div>
                                         );
                                     })}
@@ -5,7 +5,7 @@ import * as tf from "@tensorflow/tfjs";
 import * as mobilenet from "@tensorflow-models/mobilenet";
 import Loader from "../components/Loader";
 import axios from "axios";
-import { useNavigate } from "react-router-dom";
+import { useNavigate, useSearchParams } from "react-router-dom";
 
 import { base64ToTensor } from "../js/image_processing";
 
@@ -23,6 +23,11 @@ export default function FaceAttendance() {
 
     const navigate = useNavigate()
 
+    // the kiosk is opened with the door check-in code from /admin/checkin-token?purpose=door
+    const [searchParams] = useSearchParams()
+    const token = searchParams.get("token")
+    const kioskName = searchParams.get("kiosk")
+
     const webcamRef = useRef()
 
     const api = import.meta.env.VITE_API_PREFIX;
@@ -66,7 +71,11 @@ export default function FaceAttendance() {
 
         }
 
-        await axios.get(`${api}/rushee/get-rushee-face`, vector)
+        await axios.post(`${api}/rushee/get-rushee-face`, {
+            embedding: vector,
+            token: token ?? "",
+            kiosk_name: kioskName,
+        })
             .then((response) => {
 
                 if (response.data.status === "success") {
@@ -343,24 +343,14 @@ export default function PIS() {
                                     <h3 className="text-apple-title2 font-normal text-black mb-4">Brother Information</h3>
                                     
                                     {/* Show current assignments if they exist */}
-                                    {rushee.pis_signup && (rushee.pis_signup.first_brother_first_name !== "none" || rushee.pis_signup.second_brother_first_name !== "none" || rushee.pis_signup.third_brother_first_name !== "none") && (
+                                    {rushee.pis_signup && rushee.pis_signup.panel && rushee.pis_signup.panel.length > 0 && (
                                         <div className="mb-6 p-4 bg-apple-gray-100 border border-apple-gray-200 rounded-apple">
                                             <h4 className="text-apple-body text-black font-normal mb-2">Currently Assigned:</h4>
-                                            {rushee.pis_signup.first_brother_first_name !== "none" && (
-                                                <p className="text-apple-body text-apple-gray-600 font-light">
-                                                    Brother 1: {rushee.pis_signup.first_brother_first_name} {rushee.pis_signup.first_brother_last_name}
+                                            {rushee.pis_signup.panel.map((brother, idx) => (
+                                                <p key={idx} className="text-apple-body text-apple-gray-600 font-light">
+                                                    Brother {idx + 1}: {brother.first_name} {brother.last_name}
                                                 </p>
-                                            )}
-                                            {rushee.pis_signup.second_brother_first_name !== "none" && (
-                                                <p className="text-apple-body text-apple-gray-600 font-light">
-                                                    Brother 2: {rushee.pis_signup.second_brother_first_name} {rushee.pis_signup.second_brother_last_name}
-                                                </p>
-                                            )}
-                                            {rushee.pis_signup.third_brother_first_name !== "none" && (
-                                                <p className="text-apple-body text-apple-gray-600 font-light">
-                                                    Brother 3: {rushee.pis_signup.third_brother_first_name} {rushee.pis_signup.third_brother_last_name}
-                                                </p>
-                                            )}
+                                            ))}
                                         </div>
                                     )}
                                     
@@ -578,15 +578,11 @@ export default function RusheeZoom() {
                                                 {dayjs(parseInt(rushee.pis_timeslot.$date.$numberLong)).format('ddd, DD MMM YYYY HH:mm:ss')}
                                             </span>
                                         </div>
-                                        <p className="text-apple-gray-600 font-light">
-                                            <span className="text-black font-normal">Brother 1:</span> {rushee.pis_signup.first_brother_first_name} {rushee.pis_signup.first_brother_last_name}
-                                        </p>
-                                        <p className="text-apple-gray-600 font-light">
-                                            <span className="text-black font-normal">Brother 2:</span> {rushee.pis_signup.second_brother_first_name} {rushee.pis_signup.second_brother_last_name}
-                                        </p>
-                                        <p className="text-apple-gray-600 font-light">
-                                            <span className="text-black font-normal">Brother 3:</span> {rushee.pis_signup.third_brother_first_name} {rushee.pis_signup.third_brother_last_name}
-                                        </p>
+                                        {(rushee.pis_signup.panel || []).map((brother, idx) => (
+                                            <p key={idx} className="text-apple-gray-600 font-light">
+                                                <span className="text-black font-normal">Brother {idx + 1}:</span> {brother.first_name} {brother.last_name}
+                                            </p>
+                                        ))}
                                     </div>
 
                                     {/* PIS Responses Section */}
@@ -0,0 +1,92 @@
+import os
+from datetime import timezone
+from zoneinfo import ZoneInfo
+
+from pymongo import MongoClient
+from tqdm import tqdm
+
+# Points older attendance entries and comments at their rush night's id
+# Anything recorded before rush nights had ids is matched to the rush night on the same local day
+# Copies that already have an id get any rush night fields they're missing (location, description, night_type)
+
+mongo_uri = os.environ["MONGO_URI"]
+chapter_timezone = ZoneInfo(os.environ.get("CHAPTER_TIMEZONE", "America/New_York"))
+
+client = MongoClient(mongo_uri)
+
+db = client["rush-app"]
+rushee_collection = db["rushees"]
+rush_night_collection = db["rush-nights"]
+
+
+# what the server fills in for rush nights saved before these fields existed
+NIGHT_DEFAULTS = {"location": "", "description": "", "night_type": "Open"}
+
+
+def local_day(time):
+    return time.replace(tzinfo=timezone.utc).astimezone(chapter_timezone).date()
+
+
+def fill_defaults(night):
+    missing = {field: value for field, value in NIGHT_DEFAULTS.items() if field not in night}
+    night.update(missing)
+    return len(missing) > 0
+
+
+rush_nights = {local_day(night["time"]): {**NIGHT_DEFAULTS, **night} for night in rush_night_collection.find({})}
+
+rushees = list(rushee_collection.find({}, {"gtid": 1, "attendance": 1, "comments": 1}))
+
+print("Loading...")
+
+repaired = 0
+unmatched = 0
+
+for rushee in tqdm(rushees, desc="Backfilling Night Ids", total=len(rushees)):
+    changed = False
+
+    attendance = rushee.get("attendance", [])
+
+    for i, record in enumerate(attendance):
+        if record.get("_id") is not None:
+            changed = fill_defaults(record) or changed
+            continue
+
+        night = rush_nights.get(local_day(record["time"]))
+
+        if night is None:
+            unmatched += 1
+            continue
+
+        # keep the check-in details, take everything else from the rush night
+        attendance[i] = {**record, **night}
+        changed = True
+
+    comments = rushee.get("comments", [])
+
+    for comment in comments:
+        if comment.get("night_id"):
+            changed = fill_defaults(comment["night"]) or changed
+            continue
+
+        night = rush_nights.get(local_day(comment["night"]["time"]))
+
+        if night is None:
+            unmatched += 1
+            continue
+
+        comment["night"] = night
+        comment["night_id"] = str(night["_id"])
+        changed = True
+
+    if not changed:
+        continue
+
+    rushee_collection.update_one(
+        {"gtid": rushee["gtid"]},
+        {"$set": {"attendance": attendance, "comments": comments}}
+    )
+
+    repaired += 1
+
+print(f"Backfilled night ids for {repaired} rushees, {unmatched} entries didn't match a rush night")
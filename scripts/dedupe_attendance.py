@@ -0,0 +1,68 @@
+import os
+from datetime import timezone
+from zoneinfo import ZoneInfo
+
+from pymongo import MongoClient
+from tqdm import tqdm
+
+# Collapses duplicate attendance entries (same rush night recorded more than once)
+# Keeps the earliest check-in for each night
+
+mongo_uri = os.environ["MONGO_URI"]
+chapter_timezone = ZoneInfo(os.environ.get("CHAPTER_TIMEZONE", "America/New_York"))
+
+client = MongoClient(mongo_uri)
+
+db = client["rush-app"]
+rushee_collection = db["rushees"]
+
+
+def night_key(record):
+    # newer records carry the rush night's id, older ones only have the night's time
+    if record.get("_id") is not None:
+        return str(record["_id"])
+
+    return record["time"].replace(tzinfo=timezone.utc).astimezone(chapter_timezone).date().isoformat()
+
+
+def check_in_time(record):
+    return record.get("checked_in_at") or record.get("time")
+
+
+rushees = list(rushee_collection.find({}, {"gtid": 1, "attendance": 1}))
+
+print("Loading...")
+
+repaired = 0
+removed = 0
+
+for rushee in tqdm(rushees, desc="Deduping Attendance", total=len(rushees)):
+
+    attendance = rushee.get("attendance", [])
+
+    kept = {}
+    order = []
+
+    for record in attendance:
+        key = night_key(record)
+
+        if key not in kept:
+            kept[key] = record
+            order.append(key)
+        elif check_in_time(record) < check_in_time(kept[key]):
+            kept[key] = record
+
+    deduped = [kept[key] for key in order]
+
+    if len(deduped) == len(attendance):
+        continue
+
+    rushee_collection.update_one(
+        {"gtid": rushee["gtid"]},
+        {"$set": {"attendance": deduped}}
+    )
+
+    repaired += 1
+    removed += len(attendance) - len(deduped)
+
+print(f"Removed {removed} duplicate attendance entries across {repaired} rushees")
@@ -0,0 +1,48 @@
+import os
+
+from pymongo import MongoClient
+from tqdm import tqdm
+
+# Moves PIS signups from the first/second/third brother name fields to the panel list
+# Seats that were never taken ("none" / "none") are dropped
+
+mongo_uri = os.environ["MONGO_URI"]
+
+client = MongoClient(mongo_uri)
+
+db = client["rush-app"]
+rushee_collection = db["rushees"]
+
+seats = ["first", "second", "third"]
+old_fields = [f"pis_signup.{seat}_brother_{part}_name" for seat in seats for part in ["first", "last"]]
+
+rushees = list(rushee_collection.find({"pis_signup.panel": {"$exists": False}}, {"gtid": 1, "pis_signup": 1}))
+
+print("Loading...")
+
+migrated = 0
+
+for rushee in tqdm(rushees, desc="Migrating PIS Panels", total=len(rushees)):
+    signup = rushee.get("pis_signup", {})
+    panel = []
+
+    for seat in seats:
+        first_name = signup.get(f"{seat}_brother_first_name", "none")
+        last_name = signup.get(f"{seat}_brother_last_name", "none")
+
+        if first_name == "none" and last_name == "none":
+            continue
+
+        panel.append({"first_name": first_name, "last_name": last_name})
+
+    rushee_collection.update_one(
+        {"gtid": rushee["gtid"]},
+        {
+            "$set": {"pis_signup.panel": panel},
+            "$unset": {field: "" for field in old_fields},
+        }
+    )
+
+    migrated += 1
+
+print(f"Migrated PIS panels for {migrated} rushees")
@@ -0,0 +1,52 @@
+import os
+
+from pymongo import MongoClient
+from tqdm import tqdm
+
+# Gives PIS questions from before the question bank had ids an order and a version,
+# then points rushees' PIS answers at the question they answered (matched on the wording)
+
+mongo_uri = os.environ["MONGO_URI"]
+
+client = MongoClient(mongo_uri)
+
+db = client["rush-app"]
+rushee_collection = db["rushees"]
+question_collection = db["pis-questions"]
+
+questions = list(question_collection.find({}))
+
+print("Loading...")
+
+for order, question in enumerate(tqdm(questions, desc="Migrating PIS Questions", total=len(questions))):
+    update = {}
+
+    for field, default in [("order", order), ("required", False), ("version", 1), ("options", []), ("history", []), ("archived", False)]:
+        if field not in question:
+            update[field] = default
+
+    if update:
+        question_collection.update_one({"_id": question["_id"]}, {"$set": update})
+
+questions_by_wording = {question["question"]: question for question in question_collection.find({})}
+
+rushees = list(rushee_collection.find({"pis.0": {"$exists": True}}, {"gtid": 1, "pis": 1}))
+
+linked = 0
+
+for rushee in tqdm(rushees, desc="Linking PIS Answers", total=len(rushees)):
+    responses = rushee["pis"]
+
+    for response in responses:
+        question = questions_by_wording.get(response["question"])
+
+        if question is None or "question_id" in response:
+            continue
+
+        response["question_id"] = str(question["_id"])
+        response["version"] = question.get("version", 1)
+        linked += 1
+
+    rushee_collection.update_one({"gtid": rushee["gtid"]}, {"$set": {"pis": responses}})
+
+print(f"Linked {linked} PIS answers to their questions")
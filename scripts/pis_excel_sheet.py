@@ -27,12 +27,7 @@ def fetch_pis_signups():
                 "rushee_first_name": 1,
                 "rushee_last_name": 1,
                 "rushee_gtid": 1,
-                "first_brother_first_name": 1,
-                "first_brother_last_name": 1,
-                "second_brother_first_name": 1,
-                "second_brother_last_name": 1,
-                "third_brother_first_name": 1,
-                "third_brother_last_name": 1,
+                "panel": 1,
             }
         }}
     ]
@@ -44,18 +39,20 @@ def create_excel(data):
     flattened_data = []
     for item in data:
         if "pis_signup" in item and item["pis_signup"]:
-            flattened_data.append({
+            row = {
                 "Rushee First Name": item["pis_signup"].get("rushee_first_name", ""),
                 "Rushee Last Name": item["pis_signup"].get("rushee_last_name", ""),
                 "Rushee GTID": item["pis_signup"].get("rushee_gtid", ""),
                 "PIS Time": format_datetime(item["pis_signup"].get("time", "")),
-                "First Brother First Name": item["pis_signup"].get("first_brother_first_name", ""),
-                "First Brother Last Name": item["pis_signup"].get("first_brother_last_name", ""),
-                "Second Brother First Name": item["pis_signup"].get("second_brother_first_name", ""),
-                "Second Brother Last Name": item["pis_signup"].get("second_brother_last_name", ""),
-                "Third Brother First Name": item["pis_signup"].get("third_brother_first_name", ""),
-                "Third Brother Last Name": item["pis_signup"].get("third_brother_last_name", ""),
-            })
+            }
+
+            # one set of columns per brother on the panel
+            for i, brother in enumerate(item["pis_signup"].get("panel", []), start=1):
+                row[f"Brother {i} First Name"] = brother.get("first_name", "")
+                row[f"Brother {i} Last Name"] = brother.get("last_name", "")
+                row[f"Brother {i} Role"] = brother.get("role", "")
+
+            flattened_data.append(row)
 
     # Create a DataFrame
     df = pd.DataFrame(flattened_data)
@@ -1,42 +1,128 @@
 use axum::{
-    extract::Path,
-    http::StatusCode,
-    response::{IntoResponse, Json},
+    extract::{Path, Query},
+    http::{header, HeaderMap, StatusCode},
+    response::{IntoResponse, Json, Response},
     routing::{get, post},
     Router,
 };
 use futures::stream::StreamExt;
-use mongodb::bson::doc;
+use mongodb::bson::{doc, oid::ObjectId, to_bson};
 use serde_json::{json, Value};
 
 use crate::{
-    middlewares::timeHelpers::{self, string_to_bson_datetime},
+    middlewares::{
+        assignment,
+        attendance,
+        auth::is_admin,
+        checkin::{checkin_qr_contents, issue_checkin_token, render_qr_svg, RUSHEE_TOKEN},
+        moderation::redact,
+        pis, questions,
+        rushee::{fetch_rushee, get_pseudonyms},
+        timeHelpers,
+        valid::{comment_match, find_comment, is_comment_on_night},
+        waitlist,
+    },
     models::{
-        misc::{IncomingBrotherName, IncomingRushNight, RushNight},
-        pis::{IncomingPISSignup, PISQuestion, PISTimeslot, PISTimeslotIncoming},
-        Rushee::StrippedRushee,
+        misc::{AuditLogEntry, CheckinTokenParams, DeanonymizeRequest, IncomingBrotherName, IncomingRushNight},
+        pis::{
+            IncomingPISQuestion, IncomingPISQuestionDelete, IncomingPISSignup, PISBrother, PISQuestion,
+            PISQuestionVersion, PISTimeslot, PISTimeslotIncoming,
+        },
+        Rushee::{BrotherPIS, IncomingModerationAction, ModerationStatus, StrippedRushee},
     },
 };
 
 use super::{db, rushee};
 
 /**
- * Add a PIS question
+ * Builds a question from an admin's input, checking its type and MC options
  */
-pub async fn add_pis_question(Json(payload): Json<PISQuestion>) -> Result<Json<Value>, StatusCode> {
-    let connection = db::get_pis_questions_client().await;
+fn build_pis_question(payload: IncomingPISQuestion, order: u32) -> Result<PISQuestion, std::io::Error> {
+    questions::check_question_type(&payload.question_type)?;
+
+    let question = payload.question.trim().to_string();
+    let options: Vec<String> = payload
+        .options
+        .unwrap_or_default()
+        .into_iter()
+        .map(|option| option.trim().to_string())
+        .filter(|option| !option.is_empty())
+        .collect();
+
+    if question.is_empty() {
+        return Err(std::io::Error::new(std::io::ErrorKind::InvalidInput, "the question can't be blank"));
+    }
 
-    let new_question = PISQuestion {
-        question: payload.question,
+    if !options.is_empty() && payload.question_type != "MC" {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::InvalidInput,
+            "only MC questions have options",
+        ));
+    }
+
+    Ok(PISQuestion {
+        id: Some(ObjectId::new()),
+        question: question,
         question_type: payload.question_type,
-    };
+        options: options,
+        order: payload.order.unwrap_or(order),
+        required: payload.required.unwrap_or(false),
+        version: 1,
+        history: Vec::new(),
+        archived: false,
+    })
+}
+
+/**
+ * Add a PIS question, it goes after the other questions unless it's given an order
+ */
+pub async fn add_pis_question(
+    headers: HeaderMap,
+    Json(payload): Json<IncomingPISQuestion>,
+) -> Result<Json<Value>, StatusCode> {
+    if !is_admin(&headers) {
+        return Err(StatusCode::UNAUTHORIZED);
+    }
+
+    let connection = db::get_pis_questions_client().await;
+
+    let order;
+
+    match questions::next_order().await {
+        Ok(x) => {
+            order = x;
+        }
+
+        Err(err) => {
+            return Ok(Json(json!({
+                "status": "error",
+                "message": err.to_string()
+            })))
+        }
+    }
+
+    let new_question;
+
+    match build_pis_question(payload, order) {
+        Ok(x) => {
+            new_question = x;
+        }
+
+        Err(err) => {
+            return Ok(Json(json!({
+                "status": "error",
+                "message": err.to_string()
+            })))
+        }
+    }
 
-    let result = connection.insert_one(new_question).await;
+    let result = connection.insert_one(&new_question).await;
 
     match result {
         Ok(_insert_result) => Ok(Json(json!({
             "status": "success",
-            "message": "successfully added pis question"
+            "message": "successfully added pis question",
+            "payload": new_question
         }))),
         Err(_err) => Ok(Json(json!({
             "status": "error",
@@ -46,22 +132,152 @@ pub async fn add_pis_question(Json(payload): Json<PISQuestion>) -> Result<Json<V
 }
 
 /**
- * Delete a PIS question
+ * Edit a PIS question. Changing its wording, type or options makes a new version,
+ * answers given to the old one keep the old wording. Order and required can change without a new version
+ */
+pub async fn edit_pis_question(
+    Path(id): Path<String>,
+    headers: HeaderMap,
+    Json(mut payload): Json<IncomingPISQuestion>,
+) -> Result<Json<Value>, StatusCode> {
+    if !is_admin(&headers) {
+        return Err(StatusCode::UNAUTHORIZED);
+    }
+
+    let current;
+
+    match questions::get_question(&id).await {
+        Ok(x) => {
+            current = x;
+        }
+
+        Err(err) => {
+            return Ok(Json(json!({
+                "status": "error",
+                "message": err.to_string()
+            })))
+        }
+    }
+
+    // anything the edit leaves out stays as it was
+    payload.required = payload.required.or(Some(current.required));
+
+    let edited;
+
+    match build_pis_question(payload, current.order) {
+        Ok(x) => {
+            edited = x;
+        }
+
+        Err(err) => {
+            return Ok(Json(json!({
+                "status": "error",
+                "message": err.to_string()
+            })))
+        }
+    }
+
+    let reworded = edited.question != current.question
+        || edited.question_type != current.question_type
+        || edited.options != current.options;
+
+    let mut history = current.history.clone();
+    let mut version = current.version;
+
+    if reworded {
+        history.push(PISQuestionVersion {
+            version: current.version,
+            question: current.question.clone(),
+            question_type: current.question_type.clone(),
+            options: current.options.clone(),
+            replaced_at: bson::DateTime::now(),
+        });
+
+        version += 1;
+    }
+
+    let updated = PISQuestion {
+        id: current.id,
+        version: version,
+        history: history,
+        archived: current.archived,
+        ..edited
+    };
+
+    let connection = db::get_pis_questions_client().await;
+
+    // only save if nobody else edited the question in the meantime
+    let filter = doc! {"_id": current.id, "version": current.version};
+
+    match connection.replace_one(filter, &updated).await {
+        Ok(replace_result) if replace_result.matched_count == 0 => Ok(Json(json!({
+            "status": "error",
+            "message": "the question was changed while you were editing it, try again"
+        }))),
+
+        Ok(_replace_result) => Ok(Json(json!({
+            "status": "success",
+            "message": if reworded { "saved a new version of the PIS question" } else { "updated PIS question" },
+            "payload": updated
+        }))),
+
+        Err(_err) => Ok(Json(json!({
+            "status": "error",
+            "message": "couldn't update the PIS question"
+        }))),
+    }
+}
+
+/**
+ * Delete a PIS question, by id or by its text and type. The question is only hidden
+ * so answers already given to it still show what was asked
  */
 pub async fn delete_pis_question(
-    Json(payload): Json<PISQuestion>,
+    headers: HeaderMap,
+    Json(payload): Json<IncomingPISQuestionDelete>,
 ) -> Result<Json<Value>, StatusCode> {
+    if !is_admin(&headers) {
+        return Err(StatusCode::UNAUTHORIZED);
+    }
+
     let connection = db::get_pis_questions_client().await;
 
-    let filter = doc! {"$and": [
-        doc! {"question": payload.question},
-        doc! {"question_type": payload.question_type}
-    ]};
+    let filter;
+
+    match (payload.id, payload.question, payload.question_type) {
+        (Some(id), _, _) => match ObjectId::parse_str(&id) {
+            Ok(oid) => {
+                filter = doc! {"_id": oid};
+            }
+
+            Err(_err) => {
+                return Ok(Json(json!({
+                    "status": "error",
+                    "message": "invalid question id"
+                })))
+            }
+        },
+
+        (None, Some(question), Some(question_type)) => {
+            filter = doc! {"question": question, "question_type": question_type, "archived": {"$ne": true}};
+        }
+
+        _ => {
+            return Ok(Json(json!({
+                "status": "error",
+                "message": "give either the question's id or its text and type"
+            })))
+        }
+    }
 
-    let result = connection.delete_one(filter).await;
+    let result = connection.update_one(filter, doc! {"$set": {"archived": true}}).await;
 
     match result {
-        Ok(_delete_result) => Ok(Json(json!({
+        Ok(update_result) if update_result.matched_count == 0 => Ok(Json(json!({
+            "status": "error",
+            "message": "PIS question does not exist"
+        }))),
+        Ok(_update_result) => Ok(Json(json!({
             "status": "success",
             "message": "successfully deleted PIS question"
         }))),
@@ -73,39 +289,141 @@ pub async fn delete_pis_question(
 }
 
 /**
- * Fetch all the PIS questions
+ * Fetch all the PIS questions in display order
  */
 pub async fn get_pis_questions() -> Result<Json<Value>, StatusCode> {
-    let connection = db::get_pis_questions_client().await;
-    let result = connection.find(doc! {}).await;
+    match questions::get_questions(false).await {
+        Ok(pis_questions) => Ok(Json(json!({
+            "status": "success",
+            "payload": pis_questions
+        }))),
 
-    match result {
-        Ok(mut cursor) => {
-            let mut pis_questions: Vec<PISQuestion> = Vec::new();
+        Err(err) => Ok(Json(json!({
+            "status": "error",
+            "message": err.to_string()
+        }))),
+    }
+}
 
-            while let Some(question) = cursor.next().await {
-                match question {
-                    Ok(doc) => pis_questions.push(doc),
-                    Err(err) => {
-                        return Ok(Json(json!({
-                            "status": "error",
-                            "message": "some error occurred"
-                        })))
-                    }
-                }
+/**
+ * Puts the PIS questions in the order of the given ids
+ */
+pub async fn reorder_pis_questions(
+    headers: HeaderMap,
+    Json(payload): Json<Vec<String>>,
+) -> Result<Json<Value>, StatusCode> {
+    if !is_admin(&headers) {
+        return Err(StatusCode::UNAUTHORIZED);
+    }
+
+    let mut oids = Vec::<ObjectId>::new();
+
+    for id in payload.iter() {
+        match ObjectId::parse_str(id) {
+            Ok(oid) => oids.push(oid),
+
+            Err(_err) => {
+                return Ok(Json(json!({
+                    "status": "error",
+                    "message": format!("{} isn't a valid question id", id)
+                })))
             }
+        }
+    }
 
-            Ok(Json(json!({
-                "status": "success",
-                "payload": pis_questions
+    let connection = db::get_pis_questions_client().await;
+
+    for (order, oid) in oids.iter().enumerate() {
+        let update = doc! {"$set": {"order": order as u32}};
+
+        if let Err(_err) = connection.update_one(doc! {"_id": oid}, update).await {
+            return Ok(Json(json!({
+                "status": "error",
+                "message": "couldn't reorder the PIS questions"
+            })));
+        }
+    }
+
+    Ok(Json(json!({
+        "status": "success",
+        "message": "successfully reordered PIS questions"
+    })))
+}
+
+/**
+ * Imports a list of PIS questions (the format of pis_questions.json) in order.
+ * Questions whose wording is already in the bank are left alone, so the same file can be imported again
+ */
+pub async fn import_pis_questions(
+    headers: HeaderMap,
+    Json(payload): Json<Vec<IncomingPISQuestion>>,
+) -> Result<Json<Value>, StatusCode> {
+    if !is_admin(&headers) {
+        return Err(StatusCode::UNAUTHORIZED);
+    }
+
+    let fetched = (questions::get_questions(false).await, questions::next_order().await);
+
+    let existing;
+    let first_order;
+
+    match fetched {
+        (Ok(x), Ok(y)) => {
+            existing = x;
+            first_order = y;
+        }
+
+        (Err(err), _) | (_, Err(err)) => {
+            return Ok(Json(json!({
+                "status": "error",
+                "message": err.to_string()
             })))
         }
+    }
 
-        Err(err) => Ok(Json(json!({
-            "status": "error",
-            "message": "some error occurred while fetching data"
-        }))),
+    let mut new_questions = Vec::<PISQuestion>::new();
+
+    for incoming in payload.into_iter() {
+        let already_added = existing
+            .iter()
+            .chain(new_questions.iter())
+            .any(|question| question.question == incoming.question.trim());
+
+        if already_added {
+            continue;
+        }
+
+        let order = first_order + new_questions.len() as u32;
+
+        match build_pis_question(incoming, order) {
+            Ok(question) => new_questions.push(question),
+
+            Err(err) => {
+                return Ok(Json(json!({
+                    "status": "error",
+                    "message": err.to_string()
+                })))
+            }
+        }
+    }
+
+    let imported = new_questions.len();
+
+    if !new_questions.is_empty() {
+        let connection = db::get_pis_questions_client().await;
+
+        if let Err(_err) = connection.insert_many(new_questions).await {
+            return Ok(Json(json!({
+                "status": "error",
+                "message": "failed to import the PIS questions"
+            })));
+        }
     }
+
+    Ok(Json(json!({
+        "status": "success",
+        "message": format!("imported {} PIS questions", imported)
+    })))
 }
 
 /**
@@ -117,7 +435,20 @@ pub async fn add_pis_timeslot(
 ) -> Result<Json<Value>, StatusCode> {
     let connection = db::get_pis_timeslots_client().await;
 
-    let time = timeHelpers::string_to_bson_datetime(&payload.time);
+    let time;
+
+    match timeHelpers::string_to_bson_datetime(&payload.time) {
+        Ok(x) => {
+            time = x;
+        }
+
+        Err(err) => {
+            return Ok(Json(json!({
+                "status": "error",
+                "message": err.to_string()
+            })))
+        }
+    }
 
     // check if timeslot exists
     let filter = doc! {"time": time};
@@ -125,16 +456,29 @@ pub async fn add_pis_timeslot(
 
     match result {
         Ok(find_result) => match find_result {
-            Some(timeslot) => {
-                let update_filter = doc! {"time": payload.time};
-                let update = doc! {"$set": doc! {
-                    "num_available": timeslot.num_available + payload.change
+            Some(_timeslot) => {
+                // $inc so seats booked or held in the meantime aren't overwritten, never below 0
+                let update_filter = doc! {"time": time, "num_available": {"$gte": -payload.change}};
+                let update = doc! {"$inc": doc! {
+                    "num_available": payload.change
                 }};
 
                 let update_result = connection.update_one(update_filter, update).await;
 
                 match update_result {
+                    Ok(x) if x.matched_count == 0 => {
+                        return Ok(Json(json!({
+                            "status": "error",
+                            "message": "not enough open seats in this timeslot"
+                        })))
+                    }
+
                     Ok(_x) => {
+                        // new seats go to rushees on the waitlist first
+                        if let Err(err) = waitlist::offer_open_seats(time).await {
+                            println!("Couldn't offer the new PIS seats: {}", err);
+                        }
+
                         return Ok(Json(json!({
                             "status": "success",
                             "message": "added to num_available timeslots"
@@ -193,22 +537,40 @@ pub async fn delete_pis_timeslot(
 ) -> Result<Json<Value>, StatusCode> {
     let connection = db::get_pis_timeslots_client().await;
 
-    let time = timeHelpers::string_to_bson_datetime(&payload.time);
+    let time;
+
+    match timeHelpers::string_to_bson_datetime(&payload.time) {
+        Ok(x) => {
+            time = x;
+        }
+
+        Err(err) => {
+            return Ok(Json(json!({
+                "status": "error",
+                "message": err.to_string()
+            })))
+        }
+    }
 
     // check if timeslot exists
-    let filter = doc! {"time": payload.time};
+    let filter = doc! {"time": time};
     let result = connection.find_one(filter).await;
 
     match result {
         Ok(find_result) => match find_result {
             Some(timeslot) => {
                 if timeslot.num_available < payload.change {
-                    // delete timeslot
-                    let delete_filter = doc! {"time": time};
+                    // delete timeslot, unless seats were given back since we looked
+                    let delete_filter = doc! {"time": time, "num_available": {"$lt": payload.change}};
 
                     let delete_result = connection.delete_one(delete_filter).await;
 
                     match delete_result {
+                        Ok(x) if x.deleted_count == 0 => Ok(Json(json!({
+                            "status": "error",
+                            "message": "the timeslot changed while deleting it, try again"
+                        }))),
+
                         Ok(_x) => Ok(Json(json!({
                             "status": "success",
                             "message": "successfully deleted timeslot"
@@ -220,14 +582,22 @@ pub async fn delete_pis_timeslot(
                         }))),
                     }
                 } else {
-                    let update_filter = doc! {"time": time};
-                    let update = doc! {"$set": doc! {
-                        "num_available": timeslot.num_available + payload.change
+                    // $inc so seats booked or held in the meantime aren't overwritten, never below 0
+                    let update_filter = doc! {"time": time, "num_available": {"$gte": payload.change}};
+                    let update = doc! {"$inc": doc! {
+                        "num_available": -payload.change
                     }};
 
                     let update_result = connection.update_one(update_filter, update).await;
 
                     match update_result {
+                        Ok(x) if x.matched_count == 0 => {
+                            return Ok(Json(json!({
+                                "status": "error",
+                                "message": "the timeslot changed while updating it, try again"
+                            })))
+                        }
+
                         Ok(_x) => {
                             return Ok(Json(json!({
                                 "status": "success",
@@ -300,228 +670,297 @@ pub async fn get_pis_timeslots() -> Result<Json<Value>, StatusCode> {
 }
 
 /**
- * Add Rush Night
+ * All the rush nights, in order
  */
-pub async fn add_rush_night(
-    Json(payload): Json<IncomingRushNight>,
-) -> Result<Json<Value>, StatusCode> {
-    let connection = db::get_rush_nights_client().await;
+pub async fn get_rush_nights() -> Result<Json<Value>, StatusCode> {
+    match attendance::get_rush_nights().await {
+        Ok(mut rush_nights) => {
+            rush_nights.sort_by_key(|night| night.time);
 
-    let new_rush_night = RushNight {
-        time: string_to_bson_datetime(&payload.time),
-        name: payload.name,
-    };
+            Ok(Json(json!({
+                "status": "success",
+                "payload": rush_nights
+            })))
+        }
 
-    let result = connection.insert_one(new_rush_night).await;
+        Err(_err) => Ok(Json(json!({
+            "status": "error",
+            "message": "couldn't fetch the rush nights"
+        }))),
+    }
+}
 
-    match result {
-        Ok(_insert_result) => Ok(Json(json!({
+/**
+ * A single rush night
+ */
+pub async fn get_rush_night(Path(id): Path<String>) -> Result<Json<Value>, StatusCode> {
+    match attendance::get_rush_night(&id).await {
+        Ok(night) => Ok(Json(json!({
             "status": "success",
-            "message": "successfully added rush night"
+            "payload": night
         }))),
 
-        Err(_err) => Ok(Json(json!({
+        Err(err) => Ok(Json(json!({
             "status": "error",
-            "message": "couldn't add rush night"
+            "message": err.to_string()
         }))),
     }
 }
 
 /**
- * Delete a Rush Night
- * Fix this later -> make it only date, right now the time is set to 12:00 PM, or should be
+ * Add Rush Night
  */
-pub async fn delete_rush_night(Json(payload): Json<RushNight>) -> Result<Json<Value>, StatusCode> {
+pub async fn add_rush_night(
+    headers: HeaderMap,
+    Json(payload): Json<IncomingRushNight>,
+) -> Result<Json<Value>, StatusCode> {
+    if !is_admin(&headers) {
+        return Err(StatusCode::UNAUTHORIZED);
+    }
+
     let connection = db::get_rush_nights_client().await;
 
-    let filter = doc! {"time": payload.time};
-    let result = connection.delete_one(filter).await;
+    let new_rush_night;
+
+    match attendance::build_rush_night(payload, Some(ObjectId::new())) {
+        Ok(x) => {
+            new_rush_night = x;
+        }
+
+        Err(err) => {
+            return Ok(Json(json!({
+                "status": "error",
+                "message": err.to_string()
+            })))
+        }
+    }
+
+    let result = connection.insert_one(&new_rush_night).await;
 
     match result {
-        Ok(_delete_result) => Ok(Json(json!({
+        Ok(_insert_result) => Ok(Json(json!({
             "status": "success",
-            "message": "successfully deleted rush night"
+            "message": "successfully added rush night",
+            "payload": new_rush_night
         }))),
 
         Err(_err) => Ok(Json(json!({
             "status": "error",
-            "message": "there was an issue while deleting the rush night"
+            "message": "couldn't add rush night"
         }))),
     }
 }
 
-pub async fn brother_pis_sign_up(
+/**
+ * Edit a Rush Night. Comments and attendance keep pointing at the same night
+ * and their copies of it are updated to match
+ */
+pub async fn update_rush_night(
+    Path(id): Path<String>,
+    headers: HeaderMap,
+    Json(payload): Json<IncomingRushNight>,
+) -> Result<Json<Value>, StatusCode> {
+    if !is_admin(&headers) {
+        return Err(StatusCode::UNAUTHORIZED);
+    }
+
+    let existing;
+
+    match attendance::get_rush_night(&id).await {
+        Ok(x) => {
+            existing = x;
+        }
+
+        Err(err) => {
+            return Ok(Json(json!({
+                "status": "error",
+                "message": err.to_string()
+            })))
+        }
+    }
+
+    let updated_night;
+
+    match attendance::build_rush_night(payload, existing.id) {
+        Ok(x) => {
+            updated_night = x;
+        }
+
+        Err(err) => {
+            return Ok(Json(json!({
+                "status": "error",
+                "message": err.to_string()
+            })))
+        }
+    }
+
+    let connection = db::get_rush_nights_client().await;
+
+    match connection
+        .replace_one(doc! {"_id": existing.id}, &updated_night)
+        .await
+    {
+        Ok(_replace_result) => {}
+
+        Err(_err) => {
+            return Ok(Json(json!({
+                "status": "error",
+                "message": "couldn't update rush night"
+            })))
+        }
+    }
+
+    match attendance::sync_night_references(&updated_night).await {
+        Ok(_) => Ok(Json(json!({
+            "status": "success",
+            "message": "successfully updated rush night",
+            "payload": updated_night
+        }))),
+
+        Err(err) => Ok(Json(json!({
+            "status": "error",
+            "message": format!("the rush night was updated but {}", err)
+        }))),
+    }
+}
+
+/**
+ * Delete a Rush Night. Nights that rushees attended or were commented on can't be deleted
+ */
+pub async fn delete_rush_night(
+    Path(id): Path<String>,
+    headers: HeaderMap,
+) -> Result<Json<Value>, StatusCode> {
+    if !is_admin(&headers) {
+        return Err(StatusCode::UNAUTHORIZED);
+    }
+
+    let night_oid;
+
+    match ObjectId::parse_str(&id) {
+        Ok(x) => {
+            night_oid = x;
+        }
+
+        Err(_err) => {
+            return Ok(Json(json!({
+                "status": "error",
+                "message": "invalid rush night id"
+            })))
+        }
+    }
+
+    match attendance::count_night_references(night_oid).await {
+        Ok(0) => {}
+
+        Ok(count) => {
+            return Ok(Json(json!({
+                "status": "error",
+                "message": format!("{} rushees have attendance or comments for this rush night", count)
+            })))
+        }
+
+        Err(err) => {
+            return Ok(Json(json!({
+                "status": "error",
+                "message": err.to_string()
+            })))
+        }
+    }
+
+    let connection = db::get_rush_nights_client().await;
+
+    let result = connection.delete_one(doc! {"_id": night_oid}).await;
+
+    match result {
+        Ok(delete_result) if delete_result.deleted_count == 0 => Ok(Json(json!({
+            "status": "error",
+            "message": "rush night does not exist"
+        }))),
+
+        Ok(_delete_result) => Ok(Json(json!({
+            "status": "success",
+            "message": "successfully deleted rush night"
+        }))),
+
+        Err(_err) => Ok(Json(json!({
+            "status": "error",
+            "message": "there was an issue while deleting the rush night"
+        }))),
+    }
+}
+
+pub async fn brother_pis_sign_up(
     Path(id): Path<String>,
     Json(payload): Json<IncomingPISSignup>,
 ) -> Result<Json<Value>, StatusCode> {
-    let connection = db::get_rushee_client().await;
-    let fetch_rushee = connection.find_one(doc! {"gtid": id.clone()}).await;
-
-    match fetch_rushee {
-        Ok(rushee_option) => match rushee_option {
-            Some(rushee) => {
-                if (rushee.pis_signup.first_brother_first_name == "none"
-                    && rushee.pis_signup.first_brother_last_name == "none")
-                {
-                    // update first and last name
-                    let update = doc! {"$set": {"pis_signup.first_brother_first_name": payload.brother_first_name}};
-                    let first_name_update = connection
-                        .update_one(doc! {"gtid": id.clone()}, update)
-                        .await;
-
-                    match first_name_update {
-                        Ok(_first_result) => {
-                            let last_update = doc! {"$set": {"pis_signup.first_brother_last_name": payload.brother_last_name}};
-                            let last_name_update = connection
-                                .update_one(doc! {"gtid": id.clone()}, last_update)
-                                .await;
-
-                            match last_name_update {
-                                Ok(_result) => {
-                                    return Ok(Json(json!({
-                                        "status": "success",
-                                        "message": "Successfully registered!"
-                                    })))
-                                }
-
-                                Err(_err) => {
-                                    return Ok(Json(json!({
-                                        "status": "error",
-                                        "message": "Couldn't update the PIS Signup for last name"
-                                    })))
-                                }
-                            }
-                        }
+    let role;
 
-                        Err(_err) => {
-                            return Ok(Json(json!({
-                                "status": "error",
-                                "message": "Couldn't update the PIS Signup for first name"
-                            })))
-                        }
-                    }
-                } else if (rushee.pis_signup.second_brother_first_name == "none"
-                    && rushee.pis_signup.second_brother_last_name == "none")
-                {
-                    // check if duplicate brother
-                    if (rushee.pis_signup.first_brother_first_name == payload.brother_first_name
-                        && rushee.pis_signup.first_brother_last_name == payload.brother_last_name)
-                    {
-                        return Ok(Json(json!({
-                            "status": "error",
-                            "message": format!("Brother {} {} has already registered for this PIS.", payload.brother_first_name, payload.brother_last_name)
-                        })));
-                    }
+    match pis::parse_panel_role(payload.role.clone()) {
+        Ok(x) => {
+            role = x;
+        }
 
-                    // update first and last name
-                    let update = doc! {"$set": {"pis_signup.second_brother_first_name": payload.brother_first_name}};
-                    let first_name_update = connection
-                        .update_one(doc! {"gtid": id.clone()}, update)
-                        .await;
-
-                    match first_name_update {
-                        Ok(_first_result) => {
-                            let last_update = doc! {"$set": {"pis_signup.second_brother_last_name": payload.brother_last_name}};
-                            let last_name_update = connection
-                                .update_one(doc! {"gtid": id.clone()}, last_update)
-                                .await;
-
-                            match last_name_update {
-                                Ok(_result) => {
-                                    return Ok(Json(json!({
-                                        "status": "success",
-                                        "message": "Successfully registered for PIS!"
-                                    })))
-                                }
-
-                                Err(_err) => {
-                                    return Ok(Json(json!({
-                                        "status": "error",
-                                        "message": "Couldn't update the PIS Signup for last name"
-                                    })))
-                                }
-                            }
-                        }
+        Err(err) => {
+            return Ok(Json(json!({
+                "status": "error",
+                "message": err.to_string()
+            })))
+        }
+    }
 
-                        Err(_err) => {
-                            return Ok(Json(json!({
-                                "status": "error",
-                                "message": "Couldn't update the PIS Signup for first name"
-                            })))
-                        }
-                    }
-                } else if (rushee.pis_signup.third_brother_first_name == "none"
-                    && rushee.pis_signup.third_brother_last_name == "none")
-                {
-                    // check if duplicate brother
-                    if (rushee.pis_signup.first_brother_first_name == payload.brother_first_name
-                        && rushee.pis_signup.first_brother_last_name == payload.brother_last_name)
-                        || (rushee.pis_signup.second_brother_first_name == payload.brother_first_name
-                        && rushee.pis_signup.second_brother_last_name == payload.brother_last_name)
-                    {
-                        return Ok(Json(json!({
-                            "status": "error",
-                            "message": format!("Brother {} {} has already registered for this PIS.", payload.brother_first_name, payload.brother_last_name)
-                        })));
-                    }
+    let new_member = PISBrother {
+        first_name: payload.brother_first_name.clone(),
+        last_name: payload.brother_last_name.clone(),
+        role: role.clone(),
+    };
 
-                    // update first and last name
-                    let update = doc! {"$set": {"pis_signup.third_brother_first_name": payload.brother_first_name}};
-                    let first_name_update = connection
-                        .update_one(doc! {"gtid": id.clone()}, update)
-                        .await;
-
-                    match first_name_update {
-                        Ok(_first_result) => {
-                            let last_update = doc! {"$set": {"pis_signup.third_brother_last_name": payload.brother_last_name}};
-                            let last_name_update = connection
-                                .update_one(doc! {"gtid": id.clone()}, last_update)
-                                .await;
-
-                            match last_name_update {
-                                Ok(_result) => {
-                                    return Ok(Json(json!({
-                                        "status": "success",
-                                        "message": "Successfully registered for PIS!"
-                                    })))
-                                }
-
-                                Err(_err) => {
-                                    return Ok(Json(json!({
-                                        "status": "error",
-                                        "message": "Couldn't update the PIS Signup for last name"
-                                    })))
-                                }
-                            }
-                        }
+    let member_bson;
+    let role_bson;
 
-                        Err(_err) => {
-                            return Ok(Json(json!({
-                                "status": "error",
-                                "message": "Couldn't update the PIS Signup for first name"
-                            })))
-                        }
-                    }
-                } else {
-                    return Ok(Json(json!({
-                        "status": "error",
-                        "message": format!("Three brothers ({} {}, {} {}, and {} {}) are already signed up",
-                                            rushee.pis_signup.first_brother_first_name,
-                                            rushee.pis_signup.first_brother_last_name,
-                                            rushee.pis_signup.second_brother_first_name,
-                                            rushee.pis_signup.second_brother_last_name,
-                                            rushee.pis_signup.third_brother_first_name,
-                                            rushee.pis_signup.third_brother_last_name)
-                    })));
-                }
-            }
-            None => {
-                return Ok(Json(json!({
-                    "status": "error",
-                    "message": format!("The rushee with GTID {} does not exist", id.clone())
-                })))
-            }
-        },
+    match (to_bson(&new_member), to_bson(&role)) {
+        (Ok(x), Ok(y)) => {
+            member_bson = x;
+            role_bson = y;
+        }
+
+        _ => {
+            return Ok(Json(json!({
+                "status": "error",
+                "message": "some issue occurred when serializing the PIS signup"
+            })))
+        }
+    }
+
+    let panel_size = assignment::panel_size();
+
+    // only push if the brother isn't on the panel yet, there's an open seat and nobody else has the role
+    let mut filter = doc! {
+        "gtid": id.clone(),
+        "pis_signup.panel": {"$not": {"$elemMatch": {
+            "first_name": payload.brother_first_name.clone(),
+            "last_name": payload.brother_last_name.clone(),
+        }}},
+        format!("pis_signup.panel.{}", panel_size - 1): {"$exists": false},
+    };
+
+    if role.is_some() {
+        filter.insert("pis_signup.panel.role", doc! {"$ne": role_bson});
+    }
+
+    let update = doc! {"$push": {"pis_signup.panel": member_bson}};
+
+    let connection = db::get_rushee_client().await;
+
+    match connection.update_one(filter, update).await {
+        Ok(update_result) if update_result.modified_count > 0 => {
+            return Ok(Json(json!({
+                "status": "success",
+                "message": "Successfully registered for PIS!"
+            })))
+        }
+
+        Ok(_update_result) => {}
 
         Err(_err) => {
             return Ok(Json(json!({
@@ -530,6 +969,81 @@ pub async fn brother_pis_sign_up(
             })))
         }
     }
+
+    // nothing was pushed, work out why
+    match connection.find_one(doc! {"gtid": id.clone()}).await {
+        Ok(Some(rushee)) => {
+            let panel = rushee.pis_signup.panel;
+
+            let message = if panel.iter().any(|member| {
+                member.first_name == payload.brother_first_name && member.last_name == payload.brother_last_name
+            }) {
+                format!("Brother {} {} has already registered for this PIS.", payload.brother_first_name, payload.brother_last_name)
+            } else if panel.len() >= panel_size {
+                let names: Vec<String> = panel
+                    .iter()
+                    .map(|member| format!("{} {}", member.first_name, member.last_name))
+                    .collect();
+
+                format!("{} brothers ({}) are already signed up", panel.len(), names.join(", "))
+            } else {
+                format!("Someone on this PIS is already the {}", payload.role.unwrap_or_default())
+            };
+
+            Ok(Json(json!({
+                "status": "error",
+                "message": message
+            })))
+        }
+
+        Ok(None) => Ok(Json(json!({
+            "status": "error",
+            "message": format!("The rushee with GTID {} does not exist", id.clone())
+        }))),
+
+        Err(_err) => Ok(Json(json!({
+            "status": "error",
+            "message": "Couldn't access the MongoDB database"
+        }))),
+    }
+}
+
+/**
+ * A brother takes themselves off a rushee's PIS panel
+ */
+pub async fn brother_pis_withdraw(
+    Path(id): Path<String>,
+    Json(payload): Json<IncomingBrotherName>,
+) -> Result<Json<Value>, StatusCode> {
+    let connection = db::get_rushee_client().await;
+
+    let filter = doc! {"gtid": id.clone()};
+    let update = doc! {"$pull": {"pis_signup.panel": {
+        "first_name": payload.first_name.clone(),
+        "last_name": payload.last_name.clone(),
+    }}};
+
+    match connection.update_one(filter, update).await {
+        Ok(update_result) if update_result.matched_count == 0 => Ok(Json(json!({
+            "status": "error",
+            "message": format!("The rushee with GTID {} does not exist", id)
+        }))),
+
+        Ok(update_result) if update_result.modified_count == 0 => Ok(Json(json!({
+            "status": "error",
+            "message": format!("Brother {} {} isn't on this PIS", payload.first_name, payload.last_name)
+        }))),
+
+        Ok(_update_result) => Ok(Json(json!({
+            "status": "success",
+            "message": "Successfully withdrew from PIS"
+        }))),
+
+        Err(_err) => Ok(Json(json!({
+            "status": "error",
+            "message": "Couldn't access the MongoDB database"
+        }))),
+    }
 }
 
 pub async fn get_brother_pis(
@@ -539,39 +1053,25 @@ pub async fn get_brother_pis(
 
     let result = connection
         .find({
-            doc! {}
+            doc! {"pis_signup.panel": {"$elemMatch": {
+                "first_name": payload.first_name.clone(),
+                "last_name": payload.last_name.clone(),
+            }}}
         })
         .await;
 
     match result {
         Ok(mut cursor) => {
             // TODO: extract useful info only
-            let mut rushees = Vec::<StrippedRushee>::new();
+            let mut rushees = Vec::<BrotherPIS>::new();
 
             while let Some(rushee) = cursor.next().await {
                 match rushee {
                     Ok(doc) => {
-                        if ((doc
-                            .pis_signup
-                            .first_brother_first_name
-                            .eq(&payload.first_name)
-                            && doc
-                                .pis_signup
-                                .first_brother_last_name
-                                .eq(&payload.last_name))
-                            || (doc.pis_signup.second_brother_first_name).eq(&payload.first_name)
-                                && doc
-                                    .pis_signup
-                                    .second_brother_last_name
-                                    .eq(&payload.last_name)
-                            || (doc.pis_signup.third_brother_first_name).eq(&payload.first_name)
-                                && doc
-                                    .pis_signup
-                                    .third_brother_last_name
-                                    .eq(&payload.last_name))
-                        {
-
-                            rushees.push(StrippedRushee {
+                        rushees.push(BrotherPIS {
+                            pis_timeslot: doc.pis_timeslot,
+                            pis_location: doc.pis_location,
+                            rushee: StrippedRushee {
                                 name: format!("{} {}", doc.first_name, doc.last_name),
                                 class: doc.class,
                                 gtid: doc.gtid,
@@ -581,9 +1081,9 @@ pub async fn get_brother_pis(
                                 email: doc.email,
                                 pronouns: doc.pronouns,
                                 attendance: doc.attendance,
-                            });
-
-                        }
+                                pis_status: doc.pis_status,
+                            },
+                        });
                     }
                     Err(err) => {
                         println!("{}", err.to_string());
@@ -607,3 +1107,346 @@ pub async fn get_brother_pis(
         }))),
     }
 }
+
+/**
+ * Reveal which brother wrote a comment that was shown anonymously (bid committee / blind review)
+ * Admin only, and every call is written to the audit log
+ */
+pub async fn deanonymize_comment(
+    headers: HeaderMap,
+    Path(id): Path<String>,
+    Json(payload): Json<DeanonymizeRequest>,
+) -> Result<Json<Value>, StatusCode> {
+    if !is_admin(&headers) {
+        return Err(StatusCode::UNAUTHORIZED);
+    }
+
+    let rushee;
+
+    match fetch_rushee(id.clone()).await {
+        Ok(x) => {
+            rushee = x;
+        }
+
+        Err(err) => {
+            return Ok(Json(json!({
+                "status": "error",
+                "message": err.message
+            })))
+        }
+    }
+
+    let pseudonyms;
+
+    match get_pseudonyms(&rushee.gtid, &rushee.comments).await {
+        Ok(x) => {
+            pseudonyms = x;
+        }
+
+        Err(err) => {
+            return Ok(Json(json!({
+                "status": "error",
+                "message": err.message
+            })))
+        }
+    }
+
+    let comment = rushee.comments.iter().find(|comment| {
+        pseudonyms.get(&comment.brother_name) == Some(&payload.pseudonym)
+            && is_comment_on_night(comment, &payload.night)
+    });
+
+    match comment {
+        Some(comment) => {
+            let audit_entry = AuditLogEntry {
+                action: "deanonymize_comment".to_string(),
+                actor: payload.admin_name,
+                rushee_gtid: id.clone(),
+                detail: format!(
+                    "{} on {} revealed as {} (reason: {})",
+                    payload.pseudonym, comment.night.name, comment.brother_name, payload.reason
+                ),
+                time: bson::DateTime::now(),
+            };
+
+            let audit_connection = db::get_audit_log_client().await;
+
+            match audit_connection.insert_one(audit_entry).await {
+                Ok(_insert_result) => Ok(Json(json!({
+                    "status": "success",
+                    "payload": {
+                        "brother_id": comment.brother_id,
+                        "brother_name": comment.brother_name,
+                    }
+                }))),
+
+                // never reveal anything that couldn't be audited
+                Err(_err) => Ok(Json(json!({
+                    "status": "error",
+                    "message": "couldn't write to the audit log"
+                }))),
+            }
+        }
+
+        None => Ok(Json(json!({
+            "status": "error",
+            "message": format!("{} has no comment for that rush night", payload.pseudonym)
+        }))),
+    }
+}
+
+/**
+ * Moderation queue: every comment that got flagged and hasn't been dealt with yet
+ */
+pub async fn get_flagged_comments(headers: HeaderMap) -> Result<Json<Value>, StatusCode> {
+    if !is_admin(&headers) {
+        return Err(StatusCode::UNAUTHORIZED);
+    }
+
+    let connection = db::get_rushee_client().await;
+
+    let result = connection
+        .find(doc! {"comments.moderation": "Flagged"})
+        .await;
+
+    match result {
+        Ok(mut cursor) => {
+            let mut flagged = Vec::<Value>::new();
+
+            while let Some(rushee) = cursor.next().await {
+                match rushee {
+                    Ok(doc) => {
+                        for comment in doc.comments.iter() {
+                            if comment.moderation == ModerationStatus::Flagged {
+                                flagged.push(json!({
+                                    "rushee": {
+                                        "gtid": doc.gtid,
+                                        "first_name": doc.first_name,
+                                        "last_name": doc.last_name,
+                                    },
+                                    "comment": comment
+                                }));
+                            }
+                        }
+                    }
+                    Err(_err) => {
+                        return Ok(Json(json!({
+                            "status": "error",
+                            "message": "some error occurred"
+                        })))
+                    }
+                }
+            }
+
+            Ok(Json(json!({
+                "status": "success",
+                "payload": flagged
+            })))
+        }
+
+        Err(_err) => Ok(Json(json!({
+            "status": "error",
+            "message": "some error occurred while fetching data"
+        }))),
+    }
+}
+
+/**
+ * Approve, hide or redact a comment
+ * None of these touch the comment's ratings, so the rushee's averages stay the same
+ */
+pub async fn moderate_comment(
+    headers: HeaderMap,
+    Path(id): Path<String>,
+    Json(payload): Json<IncomingModerationAction>,
+) -> Result<Json<Value>, StatusCode> {
+    if !is_admin(&headers) {
+        return Err(StatusCode::UNAUTHORIZED);
+    }
+
+    let connection = db::get_rushee_client().await;
+
+    let rushee;
+
+    match fetch_rushee(id.clone()).await {
+        Ok(x) => {
+            rushee = x;
+        }
+
+        Err(err) => {
+            return Ok(Json(json!({
+                "status": "error",
+                "message": err.message
+            })))
+        }
+    }
+
+    let comment;
+
+    match find_comment(&payload.brother_name, &payload.night, &rushee.comments) {
+        Some(x) => {
+            comment = x;
+        }
+
+        None => {
+            return Ok(Json(json!({
+                "status": "error",
+                "message": "comment does not exist"
+            })))
+        }
+    }
+
+    let update;
+
+    match payload.action.to_lowercase().as_str() {
+        "approve" => {
+            update = doc! {"$set": {"comments.$.moderation": "Approved"}};
+        }
+
+        "hide" => {
+            update = doc! {"$set": {"comments.$.moderation": "Hidden"}};
+        }
+
+        "redact" => {
+            let redacted = if comment.flags.is_empty() {
+                "[redacted]".to_string()
+            } else {
+                redact(&comment.comment, &comment.flags)
+            };
+
+            update = doc! {"$set": {
+                "comments.$.comment": redacted,
+                "comments.$.flags": [],
+                "comments.$.moderation": "Redacted",
+            }};
+        }
+
+        _ => {
+            return Ok(Json(json!({
+                "status": "error",
+                "message": format!("Invalid moderation action: {}", payload.action)
+            })))
+        }
+    }
+
+    let filter = doc! {
+        "gtid": id.clone(),
+        "comments": {"$elemMatch": comment_match(comment)}
+    };
+
+    let update_result = connection.update_one(filter, update).await;
+
+    match update_result {
+        Ok(update) if update.matched_count == 0 => Ok(Json(json!({
+            "status": "error",
+            "message": "comment does not exist"
+        }))),
+
+        // nothing to audit if the comment was already in that state
+        Ok(update) if update.modified_count == 0 => Ok(Json(json!({
+            "status": "error",
+            "message": "the comment already had that moderation status"
+        }))),
+
+        Ok(_update) => {
+            let audit_entry = AuditLogEntry {
+                action: format!("{}_comment", payload.action.to_lowercase()),
+                actor: payload.admin_name,
+                rushee_gtid: id.clone(),
+                detail: format!("{}'s comment on {}", payload.brother_name, comment.night.name),
+                time: bson::DateTime::now(),
+            };
+
+            let audit_connection = db::get_audit_log_client().await;
+
+            if let Err(_err) = audit_connection.insert_one(audit_entry).await {
+                println!("Couldn't write moderation action to the audit log");
+            }
+
+            Ok(Json(json!({
+                "status": "success",
+                "message": "successfully moderated comment"
+            })))
+        }
+
+        Err(_err) => Ok(Json(json!({
+            "status": "error",
+            "message": "couldn't update the comment"
+        }))),
+    }
+}
+
+/**
+ * Issue a check-in token for tonight's rush night
+ * ?purpose=rushee (default) for the QR code at the door, ?purpose=door for brothers checking people in
+ */
+pub async fn get_checkin_token(
+    headers: HeaderMap,
+    Query(params): Query<CheckinTokenParams>,
+) -> Result<Json<Value>, StatusCode> {
+    if !is_admin(&headers) {
+        return Err(StatusCode::UNAUTHORIZED);
+    }
+
+    let night;
+
+    match attendance::get_current_rush_night().await {
+        Ok(x) => {
+            night = x;
+        }
+
+        Err(err) => {
+            return Ok(Json(json!({
+                "status": "error",
+                "message": err.to_string()
+            })))
+        }
+    }
+
+    let night_id = night.id.map(|oid| oid.to_hex()).unwrap_or_default();
+    let purpose = params.purpose.unwrap_or(RUSHEE_TOKEN.to_string());
+
+    match issue_checkin_token(&night_id, &purpose) {
+        Ok((token, expires)) => Ok(Json(json!({
+            "status": "success",
+            "payload": {
+                "token": token,
+                "expires": expires,
+                "night": night,
+            }
+        }))),
+
+        Err(err) => Ok(Json(json!({
+            "status": "error",
+            "message": err.to_string()
+        }))),
+    }
+}
+
+/**
+ * Same as get_checkin_token, but rendered as an SVG QR code
+ */
+pub async fn get_checkin_qr(
+    headers: HeaderMap,
+    Query(params): Query<CheckinTokenParams>,
+) -> Result<Response, StatusCode> {
+    if !is_admin(&headers) {
+        return Err(StatusCode::UNAUTHORIZED);
+    }
+
+    let night = attendance::get_current_rush_night()
+        .await
+        .map_err(|_| StatusCode::NOT_FOUND)?;
+
+    let night_id = night.id.map(|oid| oid.to_hex()).unwrap_or_default();
+    let purpose = params.purpose.unwrap_or(RUSHEE_TOKEN.to_string());
+
+    let (token, _expires) = issue_checkin_token(&night_id, &purpose).map_err(|e| {
+        println!("Couldn't issue check-in token: {}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+
+    let svg = render_qr_svg(&checkin_qr_contents(&token)).map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    Ok(([(header::CONTENT_TYPE, "image/svg+xml")], svg).into_response())
+}
@@ -4,7 +4,14 @@ use redis::aio::ConnectionManager;
 use redis::AsyncCommands;
 use tokio::sync::OnceCell;
 
-use crate::models::{misc::RushNight, pis::{PISQuestion, PISTimeslot}, Rushee::RusheeModel};
+use crate::models::{
+    misc::{AuditLogEntry, Invitation, RushNight},
+    pis::{
+        BrotherAvailability, PISAssignmentDraft, PISFlexEntry, PISLocation, PISNotification, PISQuestion,
+        PISTimeslot, PISWaitlistEntry,
+    },
+    Rushee::{FaceEmbedding, PseudonymMap, RusheeModel},
+};
 
 pub static MONGO_CLIENT: OnceCell<Arc<Client>> = OnceCell::const_new();
 pub static REDIS_CLIENT: OnceCell<Arc<ConnectionManager>> = OnceCell::const_new();
@@ -65,3 +72,53 @@ pub async fn get_rush_nights_client() -> Collection<RushNight> {
     let client = get_mongo_client().await;
     client.database("rush-app").collection("rush-nights")
 }
+
+pub async fn get_audit_log_client() -> Collection<AuditLogEntry> {
+    let client = get_mongo_client().await;
+    client.database("rush-app").collection("audit-log")
+}
+
+pub async fn get_face_embeddings_client() -> Collection<FaceEmbedding> {
+    let client = get_mongo_client().await;
+    client.database("rush-app").collection("face-embeddings")
+}
+
+pub async fn get_invitations_client() -> Collection<Invitation> {
+    let client = get_mongo_client().await;
+    client.database("rush-app").collection("invitations")
+}
+
+pub async fn get_pis_assignment_client() -> Collection<PISAssignmentDraft> {
+    let client = get_mongo_client().await;
+    client.database("rush-app").collection("pis-assignments")
+}
+
+pub async fn get_brother_availability_client() -> Collection<BrotherAvailability> {
+    let client = get_mongo_client().await;
+    client.database("rush-app").collection("brother-availability")
+}
+
+pub async fn get_pis_notifications_client() -> Collection<PISNotification> {
+    let client = get_mongo_client().await;
+    client.database("rush-app").collection("pis-notifications")
+}
+
+pub async fn get_pis_waitlist_client() -> Collection<PISWaitlistEntry> {
+    let client = get_mongo_client().await;
+    client.database("rush-app").collection("pis-waitlist")
+}
+
+pub async fn get_pis_flex_queue_client() -> Collection<PISFlexEntry> {
+    let client = get_mongo_client().await;
+    client.database("rush-app").collection("pis-flex-queue")
+}
+
+pub async fn get_pis_locations_client() -> Collection<PISLocation> {
+    let client = get_mongo_client().await;
+    client.database("rush-app").collection("pis-locations")
+}
+
+pub async fn get_pseudonyms_client() -> Collection<PseudonymMap> {
+    let client = get_mongo_client().await;
+    client.database("rush-app").collection("pseudonyms")
+}
@@ -0,0 +1,311 @@
+use axum::{
+    extract::Path,
+    http::{HeaderMap, StatusCode},
+    response::Json,
+};
+use mongodb::bson::doc;
+use serde_json::{json, Value};
+
+use super::db;
+use crate::middlewares::{
+    attendance, auth::is_admin, invitations, rushee::fetch_rushee, timeHelpers::night_start,
+};
+use crate::models::misc::{
+    IncomingAccessCode, IncomingInvitations, IncomingRsvp, IncomingUninvite, NightType, RsvpStatus,
+};
+
+/**
+ * Invites rushees to an invite-only rush night, by gtid and/or everyone matching a filter
+ */
+pub async fn invite_to_night(
+    Path(night_id): Path<String>,
+    headers: HeaderMap,
+    Json(payload): Json<IncomingInvitations>,
+) -> Result<Json<Value>, StatusCode> {
+    if !is_admin(&headers) {
+        return Err(StatusCode::UNAUTHORIZED);
+    }
+
+    let night;
+
+    match attendance::get_rush_night(&night_id).await {
+        Ok(x) => {
+            night = x;
+        }
+
+        Err(err) => {
+            return Ok(Json(json!({
+                "status": "error",
+                "message": err.to_string()
+            })))
+        }
+    }
+
+    if night.night_type != NightType::InviteOnly {
+        return Ok(Json(json!({
+            "status": "error",
+            "message": "this rush night is open to everyone"
+        })));
+    }
+
+    let mut gtids = payload.gtids;
+
+    if let Some(filter) = &payload.filter {
+        match invitations::find_matching_rushees(filter).await {
+            Ok(matching) => gtids.extend(matching),
+
+            Err(err) => {
+                return Ok(Json(json!({
+                    "status": "error",
+                    "message": err.to_string()
+                })))
+            }
+        }
+    }
+
+    gtids.sort();
+    gtids.dedup();
+
+    if gtids.is_empty() {
+        return Ok(Json(json!({
+            "status": "error",
+            "message": "no rushees to invite"
+        })));
+    }
+
+    match invitations::invite_rushees(&night_id, &gtids, &payload.admin_name).await {
+        Ok(invited) => Ok(Json(json!({
+            "status": "success",
+            "message": format!("invited {} rushees, {} were already invited", invited, gtids.len() as u64 - invited),
+            "payload": gtids
+        }))),
+
+        Err(err) => Ok(Json(json!({
+            "status": "error",
+            "message": err.to_string()
+        }))),
+    }
+}
+
+/**
+ * Takes rushees off a rush night's invitation list
+ */
+pub async fn uninvite_from_night(
+    Path(night_id): Path<String>,
+    headers: HeaderMap,
+    Json(payload): Json<IncomingUninvite>,
+) -> Result<Json<Value>, StatusCode> {
+    if !is_admin(&headers) {
+        return Err(StatusCode::UNAUTHORIZED);
+    }
+
+    let connection = db::get_invitations_client().await;
+
+    let filter = doc! {"night_id": night_id, "gtid": {"$in": payload.gtids}};
+
+    match connection.delete_many(filter).await {
+        Ok(delete_result) => Ok(Json(json!({
+            "status": "success",
+            "message": format!("removed {} invitations", delete_result.deleted_count)
+        }))),
+
+        Err(_err) => Ok(Json(json!({
+            "status": "error",
+            "message": "couldn't remove the invitations"
+        }))),
+    }
+}
+
+/**
+ * A rush night's invitation list with everyone's RSVP
+ */
+pub async fn get_night_invitations(
+    Path(night_id): Path<String>,
+    headers: HeaderMap,
+) -> Result<Json<Value>, StatusCode> {
+    if !is_admin(&headers) {
+        return Err(StatusCode::UNAUTHORIZED);
+    }
+
+    match invitations::get_invitations(&night_id).await {
+        Ok(invitation_list) => Ok(Json(json!({
+            "status": "success",
+            "payload": invitation_list
+        }))),
+
+        Err(err) => Ok(Json(json!({
+            "status": "error",
+            "message": err.to_string()
+        }))),
+    }
+}
+
+/**
+ * The rush nights a rushee is invited to, the rushee proves who they are with their access code
+ */
+pub async fn get_rushee_invitations(
+    Path(id): Path<String>,
+    Json(payload): Json<IncomingAccessCode>,
+) -> Result<Json<Value>, StatusCode> {
+    let rushee;
+
+    match fetch_rushee(id.clone()).await {
+        Ok(x) => {
+            rushee = x;
+        }
+
+        Err(err) => {
+            return Ok(Json(json!({
+                "status": "error",
+                "message": err.message
+            })))
+        }
+    }
+
+    if rushee.access_code != payload.access_code {
+        return Ok(Json(json!({
+            "status": "error",
+            "message": "invalid access code"
+        })));
+    }
+
+    let rush_nights;
+
+    match attendance::get_rush_nights().await {
+        Ok(x) => {
+            rush_nights = x;
+        }
+
+        Err(_err) => {
+            return Ok(Json(json!({
+                "status": "error",
+                "message": "couldn't fetch the rush nights"
+            })))
+        }
+    }
+
+    let mut answer = Vec::<Value>::new();
+
+    for night in rush_nights.iter().filter(|night| night.night_type == NightType::InviteOnly) {
+        let night_id = night.id.map(|oid| oid.to_hex()).unwrap_or_default();
+
+        match invitations::get_invitation(&night_id, &id).await {
+            Ok(Some(invitation)) => answer.push(json!({
+                "night": night,
+                "rsvp": invitation.rsvp
+            })),
+
+            Ok(None) => {}
+
+            Err(err) => {
+                return Ok(Json(json!({
+                    "status": "error",
+                    "message": err.to_string()
+                })))
+            }
+        }
+    }
+
+    Ok(Json(json!({
+        "status": "success",
+        "payload": answer
+    })))
+}
+
+/**
+ * RSVP to an invite-only rush night with the rushee's access code. Can be changed until the night starts
+ */
+pub async fn rsvp(
+    Path(id): Path<String>,
+    Json(payload): Json<IncomingRsvp>,
+) -> Result<Json<Value>, StatusCode> {
+    let rushee;
+
+    match fetch_rushee(id.clone()).await {
+        Ok(x) => {
+            rushee = x;
+        }
+
+        Err(err) => {
+            return Ok(Json(json!({
+                "status": "error",
+                "message": err.message
+            })))
+        }
+    }
+
+    if rushee.access_code != payload.access_code {
+        return Ok(Json(json!({
+            "status": "error",
+            "message": "invalid access code"
+        })));
+    }
+
+    let night;
+
+    match attendance::get_rush_night(&payload.night_id).await {
+        Ok(x) => {
+            night = x;
+        }
+
+        Err(err) => {
+            return Ok(Json(json!({
+                "status": "error",
+                "message": err.to_string()
+            })))
+        }
+    }
+
+    if night_start(&night) <= bson::DateTime::now() {
+        return Ok(Json(json!({
+            "status": "error",
+            "message": "this rush night has already started"
+        })));
+    }
+
+    let rsvp = if payload.attending {
+        RsvpStatus::Attending
+    } else {
+        RsvpStatus::Declined
+    };
+
+    let rsvp_bson;
+
+    match bson::to_bson(&rsvp) {
+        Ok(x) => {
+            rsvp_bson = x;
+        }
+
+        Err(_err) => {
+            return Ok(Json(json!({
+                "status": "error",
+                "message": "some issue occurred when serializing the RSVP"
+            })))
+        }
+    }
+
+    let connection = db::get_invitations_client().await;
+
+    let filter = doc! {"night_id": payload.night_id, "gtid": id};
+    let update = doc! {"$set": {
+        "rsvp": rsvp_bson,
+        "responded_at": bson::DateTime::now(),
+    }};
+
+    match connection.update_one(filter, update).await {
+        Ok(update_result) if update_result.matched_count == 0 => Ok(Json(json!({
+            "status": "error",
+            "message": "you aren't invited to this rush night"
+        }))),
+
+        Ok(_update_result) => Ok(Json(json!({
+            "status": "success",
+            "message": "successfully updated RSVP"
+        }))),
+
+        Err(_err) => Ok(Json(json!({
+            "status": "error",
+            "message": "couldn't update RSVP"
+        }))),
+    }
+}
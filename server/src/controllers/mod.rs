@@ -2,3 +2,6 @@ pub mod rushee;
 pub mod db;
 pub mod admin;
 pub mod voting;
+pub mod reports;
+pub mod invitations;
+pub mod pis;
@@ -0,0 +1,2069 @@
+use axum::{
+    extract::{Path, Query},
+    http::{HeaderMap, StatusCode},
+    response::Json,
+};
+use futures::stream::StreamExt;
+use mongodb::bson::{doc, to_bson};
+use serde_json::{json, Value};
+
+use super::db;
+use crate::middlewares::{
+    assignment,
+    auth::{get_role, is_admin, Role},
+    flex, locations, notifications, pis, questions,
+    rushee::fetch_rushee,
+    scorecards, timeHelpers, waitlist,
+};
+use crate::models::misc::{IncomingAccessCode, IncomingBrotherName};
+use crate::models::pis::{
+    AssignmentBrother, BrotherAvailability, CalendarParams, FlexQueueEntry, FlexStatus, IncomingFlexPlacement, IncomingPISLocation, IncomingPISStatus, IncomingScorecard, IncomingAssignmentRequest, IncomingAssignmentTweak,
+    IncomingAvailabilityEdit, IncomingBrotherAvailability, IncomingTimeslotGeneration, IncomingWaitlist,
+    IncomingWaitlistOffer, PISAssignmentDraft, PISBrother, PISCalendarBooking, PISCalendarSlot, PISLocation, PISOutcome, PISStatusRecord, PISReschedule,
+    LocationKind, PISTimeslot, TimeslotAvailability, WaitlistDepth, WaitlistStatus,
+};
+use crate::models::Rushee::RusheeModel;
+
+const DRAFT_ID: &str = "current";
+
+async fn fetch_scheduled_rushees() -> Result<Vec<RusheeModel>, String> {
+    let connection = db::get_rushee_client().await;
+    let mut rushees = Vec::<RusheeModel>::new();
+
+    // flex-window rushees still in the queue don't have a PIS to plan around
+    match connection.find(doc! {"pis_timeslot": {"$ne": flex::UNSCHEDULED}}).await {
+        Ok(mut cursor) => {
+            while let Some(rushee) = cursor.next().await {
+                match rushee {
+                    Ok(x) => rushees.push(x),
+                    Err(_err) => return Err("there was an error reading a rushee".to_string()),
+                }
+            }
+
+            Ok(rushees)
+        }
+
+        Err(_err) => Err("some network error occurred".to_string()),
+    }
+}
+
+/**
+ * Fetches a rushee and checks the access code they gave
+ */
+async fn verify_rushee(id: &str, access_code: &str) -> Result<RusheeModel, String> {
+    match fetch_rushee(id.to_string()).await {
+        Ok(rushee) if rushee.access_code == access_code => Ok(rushee),
+        Ok(_rushee) => Err("invalid access code".to_string()),
+        Err(err) => Err(err.message),
+    }
+}
+
+async fn fetch_draft() -> Result<Option<PISAssignmentDraft>, String> {
+    let connection = db::get_pis_assignment_client().await;
+
+    connection
+        .find_one(doc! {"_id": DRAFT_ID})
+        .await
+        .map_err(|_err| "couldn't fetch the PIS assignment".to_string())
+}
+
+async fn save_draft(draft: &PISAssignmentDraft) -> Result<(), String> {
+    let connection = db::get_pis_assignment_client().await;
+
+    connection
+        .replace_one(doc! {"_id": DRAFT_ID}, draft)
+        .upsert(true)
+        .await
+        .map(|_| ())
+        .map_err(|_err| "couldn't save the PIS assignment".to_string())
+}
+
+/**
+ * Builds a new PIS interviewer assignment from the brothers' availability and saves it as a draft
+ * to look over and tweak before committing. Replaces any draft that hasn't been committed
+ */
+pub async fn preview_pis_assignment(
+    headers: HeaderMap,
+    Json(payload): Json<IncomingAssignmentRequest>,
+) -> Result<Json<Value>, StatusCode> {
+    if !is_admin(&headers) {
+        return Err(StatusCode::UNAUTHORIZED);
+    }
+
+    let max_load = payload.max_load.unwrap_or(assignment::default_max_load());
+    let mut brothers = Vec::<AssignmentBrother>::new();
+
+    for brother in payload.brothers.into_iter() {
+        let available;
+
+        match assignment::parse_times(&brother.available) {
+            Ok(x) => {
+                available = x;
+            }
+
+            Err(err) => {
+                return Ok(Json(json!({
+                    "status": "error",
+                    "message": format!("{} {}: {}", brother.first_name, brother.last_name, err)
+                })))
+            }
+        }
+
+        brothers.push(AssignmentBrother {
+            first_name: brother.first_name,
+            last_name: brother.last_name,
+            freshman: brother.freshman,
+            available: available,
+            referrals: brother.referrals,
+            max_load: brother.max_load.unwrap_or(max_load),
+        });
+    }
+
+    if brothers.is_empty() {
+        match pis::get_brother_availability().await {
+            Ok(submitted) => {
+                brothers = submitted
+                    .into_iter()
+                    .map(|brother| AssignmentBrother {
+                        first_name: brother.first_name,
+                        last_name: brother.last_name,
+                        freshman: brother.freshman,
+                        available: brother.available,
+                        referrals: brother.referrals,
+                        max_load: brother.max_load.unwrap_or(max_load),
+                    })
+                    .collect();
+            }
+
+            Err(err) => {
+                return Ok(Json(json!({
+                    "status": "error",
+                    "message": err.to_string()
+                })))
+            }
+        }
+    }
+
+    let rushees;
+
+    match fetch_scheduled_rushees().await {
+        Ok(x) => {
+            rushees = x;
+        }
+
+        Err(message) => {
+            return Ok(Json(json!({
+                "status": "error",
+                "message": message
+            })))
+        }
+    }
+
+    let assignments =
+        assignment::build_assignment(&rushees, &brothers, payload.keep_existing.unwrap_or(true));
+
+    let draft = PISAssignmentDraft {
+        id: DRAFT_ID.to_string(),
+        created_at: bson::DateTime::now(),
+        brothers: brothers,
+        assignments: assignments,
+    };
+
+    match save_draft(&draft).await {
+        Ok(_) => Ok(Json(json!({
+            "status": "success",
+            "payload": draft
+        }))),
+
+        Err(message) => Ok(Json(json!({
+            "status": "error",
+            "message": message
+        }))),
+    }
+}
+
+/**
+ * The PIS assignment draft being worked on
+ */
+pub async fn get_pis_assignment(headers: HeaderMap) -> Result<Json<Value>, StatusCode> {
+    if !is_admin(&headers) {
+        return Err(StatusCode::UNAUTHORIZED);
+    }
+
+    match fetch_draft().await {
+        Ok(Some(draft)) => Ok(Json(json!({
+            "status": "success",
+            "payload": draft
+        }))),
+
+        Ok(None) => Ok(Json(json!({
+            "status": "error",
+            "message": "there's no PIS assignment to look at, preview one first"
+        }))),
+
+        Err(message) => Ok(Json(json!({
+            "status": "error",
+            "message": message
+        }))),
+    }
+}
+
+/**
+ * Swaps out the panel for one rushee's PIS in the draft. Anything that breaks the rules is
+ * allowed but comes back as a warning on the affected PIS
+ */
+pub async fn tweak_pis_assignment(
+    headers: HeaderMap,
+    Json(payload): Json<IncomingAssignmentTweak>,
+) -> Result<Json<Value>, StatusCode> {
+    if !is_admin(&headers) {
+        return Err(StatusCode::UNAUTHORIZED);
+    }
+
+    if payload.brothers.len() > assignment::panel_size() {
+        return Ok(Json(json!({
+            "status": "error",
+            "message": format!("a PIS panel can have at most {} brothers", assignment::panel_size())
+        })));
+    }
+
+    let mut draft;
+
+    match fetch_draft().await {
+        Ok(Some(x)) => {
+            draft = x;
+        }
+
+        Ok(None) => {
+            return Ok(Json(json!({
+                "status": "error",
+                "message": "there's no PIS assignment to tweak, preview one first"
+            })))
+        }
+
+        Err(message) => {
+            return Ok(Json(json!({
+                "status": "error",
+                "message": message
+            })))
+        }
+    }
+
+    match draft
+        .assignments
+        .iter_mut()
+        .find(|assignment| assignment.rushee_gtid == payload.rushee_gtid)
+    {
+        Some(assignment) => {
+            assignment.brothers = payload.brothers;
+        }
+
+        None => {
+            return Ok(Json(json!({
+                "status": "error",
+                "message": format!("The rushee with GTID {} isn't in the PIS assignment", payload.rushee_gtid)
+            })))
+        }
+    }
+
+    let rushees;
+
+    match fetch_scheduled_rushees().await {
+        Ok(x) => {
+            rushees = x;
+        }
+
+        Err(message) => {
+            return Ok(Json(json!({
+                "status": "error",
+                "message": message
+            })))
+        }
+    }
+
+    assignment::check_assignments(&mut draft.assignments, &rushees, &draft.brothers);
+
+    match save_draft(&draft).await {
+        Ok(_) => Ok(Json(json!({
+            "status": "success",
+            "payload": draft
+        }))),
+
+        Err(message) => Ok(Json(json!({
+            "status": "error",
+            "message": message
+        }))),
+    }
+}
+
+/**
+ * Writes the draft's panels onto every rushee's PIS signup and throws the draft away
+ */
+pub async fn commit_pis_assignment(headers: HeaderMap) -> Result<Json<Value>, StatusCode> {
+    if !is_admin(&headers) {
+        return Err(StatusCode::UNAUTHORIZED);
+    }
+
+    let draft;
+
+    match fetch_draft().await {
+        Ok(Some(x)) => {
+            draft = x;
+        }
+
+        Ok(None) => {
+            return Ok(Json(json!({
+                "status": "error",
+                "message": "there's no PIS assignment to commit, preview one first"
+            })))
+        }
+
+        Err(message) => {
+            return Ok(Json(json!({
+                "status": "error",
+                "message": message
+            })))
+        }
+    }
+
+    let connection = db::get_rushee_client().await;
+
+    for assignment in draft.assignments.iter() {
+        let panel_bson;
+
+        match to_bson(&assignment.brothers) {
+            Ok(x) => {
+                panel_bson = x;
+            }
+
+            Err(_err) => {
+                return Ok(Json(json!({
+                    "status": "error",
+                    "message": "some issue occurred when serializing the PIS panel"
+                })))
+            }
+        }
+
+        let update = doc! {"$set": {"pis_signup.panel": panel_bson}};
+
+        match connection
+            .update_one(doc! {"gtid": assignment.rushee_gtid.clone()}, update)
+            .await
+        {
+            Ok(_update_result) => {}
+
+            Err(_err) => {
+                return Ok(Json(json!({
+                    "status": "error",
+                    "message": format!("couldn't update the PIS signup for {}, the draft was kept", assignment.rushee_name)
+                })))
+            }
+        }
+    }
+
+    let assignment_connection = db::get_pis_assignment_client().await;
+
+    match assignment_connection.delete_one(doc! {"_id": DRAFT_ID}).await {
+        Ok(_delete_result) => Ok(Json(json!({
+            "status": "success",
+            "message": format!("assigned interviewers for {} PIS", draft.assignments.len())
+        }))),
+
+        Err(_err) => Ok(Json(json!({
+            "status": "error",
+            "message": "the assignment was committed but the draft couldn't be cleared"
+        }))),
+    }
+}
+
+/**
+ * A brother submits the PIS timeslots they can interview at, replacing whatever they submitted before
+ */
+pub async fn submit_availability(
+    Json(payload): Json<IncomingBrotherAvailability>,
+) -> Result<Json<Value>, StatusCode> {
+    let available;
+
+    match assignment::parse_times(&payload.available) {
+        Ok(x) => {
+            available = x;
+        }
+
+        Err(err) => {
+            return Ok(Json(json!({
+                "status": "error",
+                "message": err.to_string()
+            })))
+        }
+    }
+
+    if let Err(err) = pis::check_times_are_timeslots(&available).await {
+        return Ok(Json(json!({
+            "status": "error",
+            "message": err.to_string()
+        })));
+    }
+
+    let availability = BrotherAvailability {
+        first_name: payload.first_name.clone(),
+        last_name: payload.last_name.clone(),
+        freshman: payload.freshman,
+        available: available,
+        referrals: payload.referrals,
+        max_load: payload.max_load,
+        updated_at: bson::DateTime::now(),
+    };
+
+    let connection = db::get_brother_availability_client().await;
+
+    let filter = doc! {"first_name": payload.first_name, "last_name": payload.last_name};
+
+    match connection.replace_one(filter, &availability).upsert(true).await {
+        Ok(_replace_result) => Ok(Json(json!({
+            "status": "success",
+            "message": "successfully saved availability",
+            "payload": availability
+        }))),
+
+        Err(_err) => Ok(Json(json!({
+            "status": "error",
+            "message": "couldn't save availability"
+        }))),
+    }
+}
+
+/**
+ * A brother adds or removes PIS timeslots from their availability
+ */
+pub async fn edit_availability(
+    Json(payload): Json<IncomingAvailabilityEdit>,
+) -> Result<Json<Value>, StatusCode> {
+    let parsed_times = (
+        assignment::parse_times(&payload.add),
+        assignment::parse_times(&payload.remove),
+    );
+
+    let add;
+    let remove;
+
+    match parsed_times {
+        (Ok(x), Ok(y)) => {
+            add = x;
+            remove = y;
+        }
+
+        (Err(err), _) | (_, Err(err)) => {
+            return Ok(Json(json!({
+                "status": "error",
+                "message": err.to_string()
+            })))
+        }
+    }
+
+    if let Err(err) = pis::check_times_are_timeslots(&add).await {
+        return Ok(Json(json!({
+            "status": "error",
+            "message": err.to_string()
+        })));
+    }
+
+    let connection = db::get_brother_availability_client().await;
+
+    let filter = doc! {"first_name": payload.first_name.clone(), "last_name": payload.last_name.clone()};
+
+    // mongo won't $addToSet and $pull the same field in one update
+    let updates = [
+        doc! {"$addToSet": {"available": {"$each": add}}, "$set": {"updated_at": bson::DateTime::now()}},
+        doc! {"$pull": {"available": {"$in": remove}}},
+    ];
+
+    for update in updates {
+        match connection.update_one(filter.clone(), update).await {
+            Ok(update_result) if update_result.matched_count == 0 => {
+                return Ok(Json(json!({
+                    "status": "error",
+                    "message": format!("{} {} hasn't submitted their availability yet", payload.first_name, payload.last_name)
+                })))
+            }
+
+            Ok(_update_result) => {}
+
+            Err(_err) => {
+                return Ok(Json(json!({
+                    "status": "error",
+                    "message": "couldn't update availability"
+                })))
+            }
+        }
+    }
+
+    Ok(Json(json!({
+        "status": "success",
+        "message": "successfully updated availability"
+    })))
+}
+
+/**
+ * The PIS timeslots a brother said they're free for
+ */
+pub async fn get_availability(
+    Json(payload): Json<IncomingBrotherName>,
+) -> Result<Json<Value>, StatusCode> {
+    let connection = db::get_brother_availability_client().await;
+
+    let filter = doc! {"first_name": payload.first_name, "last_name": payload.last_name};
+
+    match connection.find_one(filter).await {
+        Ok(Some(availability)) => Ok(Json(json!({
+            "status": "success",
+            "payload": availability
+        }))),
+
+        Ok(None) => Ok(Json(json!({
+            "status": "error",
+            "message": "no availability submitted yet"
+        }))),
+
+        Err(_err) => Ok(Json(json!({
+            "status": "error",
+            "message": "some network error occurred"
+        }))),
+    }
+}
+
+/**
+ * Per PIS timeslot: how many brothers are free against how many rushees booked it,
+ * and how many seats to open or close so the two line up
+ */
+pub async fn get_timeslot_availability(headers: HeaderMap) -> Result<Json<Value>, StatusCode> {
+    if !is_admin(&headers) {
+        return Err(StatusCode::UNAUTHORIZED);
+    }
+
+    let fetched = (
+        pis::get_pis_timeslots().await,
+        pis::get_brother_availability().await,
+        fetch_scheduled_rushees().await.map_err(|message| std::io::Error::new(std::io::ErrorKind::Other, message)),
+    );
+
+    let timeslots;
+    let availability;
+    let rushees;
+
+    match fetched {
+        (Ok(x), Ok(y), Ok(z)) => {
+            timeslots = x;
+            availability = y;
+            rushees = z;
+        }
+
+        (Err(err), _, _) | (_, Err(err), _) | (_, _, Err(err)) => {
+            return Ok(Json(json!({
+                "status": "error",
+                "message": err.to_string()
+            })))
+        }
+    }
+
+    let report: Vec<TimeslotAvailability> = timeslots
+        .into_iter()
+        .map(|timeslot| {
+            let booked = rushees
+                .iter()
+                .filter(|rushee| rushee.pis_timeslot == timeslot.time)
+                .count() as u32;
+
+            let brothers_free: Vec<String> = availability
+                .iter()
+                .filter(|brother| brother.available.contains(&timeslot.time))
+                .map(|brother| assignment::full_name(&brother.first_name, &brother.last_name))
+                .collect();
+
+            let supportable = (brothers_free.len() / assignment::panel_size()) as u32;
+
+            TimeslotAvailability {
+                time: timeslot.time,
+                num_available: timeslot.num_available,
+                booked: booked,
+                brothers_free: brothers_free,
+                supportable: supportable,
+                suggested_change: supportable as i32 - (booked as i32 + timeslot.num_available),
+            }
+        })
+        .collect();
+
+    Ok(Json(json!({
+        "status": "success",
+        "payload": report
+    })))
+}
+
+/**
+ * A brother's PIS notifications (reschedules and other changes to PIS they're on), newest first
+ */
+pub async fn get_pis_notifications(
+    Json(payload): Json<IncomingBrotherName>,
+) -> Result<Json<Value>, StatusCode> {
+    match notifications::get_notifications(&payload.first_name, &payload.last_name).await {
+        Ok(notification_list) => Ok(Json(json!({
+            "status": "success",
+            "payload": notification_list
+        }))),
+
+        Err(err) => Ok(Json(json!({
+            "status": "error",
+            "message": err.to_string()
+        }))),
+    }
+}
+
+/**
+ * A rushee joins the waitlist for PIS timeslots that are full
+ */
+pub async fn join_pis_waitlist(
+    Path(id): Path<String>,
+    Json(payload): Json<IncomingWaitlist>,
+) -> Result<Json<Value>, StatusCode> {
+    let rushee;
+
+    match verify_rushee(&id, &payload.access_code).await {
+        Ok(x) => {
+            rushee = x;
+        }
+
+        Err(message) => {
+            return Ok(Json(json!({
+                "status": "error",
+                "message": message
+            })))
+        }
+    }
+
+    if rushee.pis_timeslot == flex::UNSCHEDULED {
+        return Ok(Json(json!({
+            "status": "error",
+            "message": "you're in the flex-window queue, you'll be given a PIS time soon"
+        })));
+    }
+
+    let times;
+
+    match assignment::parse_times(&payload.times) {
+        Ok(x) => {
+            times = x;
+        }
+
+        Err(err) => {
+            return Ok(Json(json!({
+                "status": "error",
+                "message": err.to_string()
+            })))
+        }
+    }
+
+    if let Err(err) = waitlist::expire_holds().await {
+        println!("Couldn't expire PIS holds: {}", err);
+    }
+
+    let timeslots;
+
+    match pis::get_pis_timeslots().await {
+        Ok(x) => {
+            timeslots = x;
+        }
+
+        Err(err) => {
+            return Ok(Json(json!({
+                "status": "error",
+                "message": err.to_string()
+            })))
+        }
+    }
+
+    for time in times.iter() {
+        match timeslots.iter().find(|timeslot| timeslot.time == *time) {
+            Some(_timeslot) if *time == rushee.pis_timeslot => {
+                return Ok(Json(json!({
+                    "status": "error",
+                    "message": "your PIS is already at this time"
+                })))
+            }
+
+            Some(timeslot) if timeslot.num_available > 0 => {
+                return Ok(Json(json!({
+                    "status": "error",
+                    "message": format!("{} still has open slots, reschedule instead", time)
+                })))
+            }
+
+            Some(_timeslot) => {}
+
+            None => {
+                return Ok(Json(json!({
+                    "status": "error",
+                    "message": format!("{} isn't a PIS timeslot", time)
+                })))
+            }
+        }
+    }
+
+    for time in times.iter() {
+        if let Err(err) = waitlist::join_waitlist(&id, *time).await {
+            return Ok(Json(json!({
+                "status": "error",
+                "message": err.to_string()
+            })));
+        }
+    }
+
+    Ok(Json(json!({
+        "status": "success",
+        "message": "successfully joined the waitlist"
+    })))
+}
+
+/**
+ * A rushee's waitlist entries, including any seat being held for them and when the hold runs out
+ */
+pub async fn get_rushee_waitlist(
+    Path(id): Path<String>,
+    Json(payload): Json<IncomingAccessCode>,
+) -> Result<Json<Value>, StatusCode> {
+    if let Err(message) = verify_rushee(&id, &payload.access_code).await {
+        return Ok(Json(json!({
+            "status": "error",
+            "message": message
+        })));
+    }
+
+    if let Err(err) = waitlist::expire_holds().await {
+        println!("Couldn't expire PIS holds: {}", err);
+    }
+
+    match waitlist::get_waitlist(doc! {"gtid": id}).await {
+        Ok(entries) => Ok(Json(json!({
+            "status": "success",
+            "payload": entries
+        }))),
+
+        Err(err) => Ok(Json(json!({
+            "status": "error",
+            "message": err.to_string()
+        }))),
+    }
+}
+
+/**
+ * A rushee takes the seat being held for them. Their PIS moves to the new time
+ * and their old seat goes to whoever is waiting for it
+ */
+pub async fn accept_waitlist_offer(
+    Path(id): Path<String>,
+    Json(payload): Json<IncomingWaitlistOffer>,
+) -> Result<Json<Value>, StatusCode> {
+    let rushee;
+
+    match verify_rushee(&id, &payload.access_code).await {
+        Ok(x) => {
+            rushee = x;
+        }
+
+        Err(message) => {
+            return Ok(Json(json!({
+                "status": "error",
+                "message": message
+            })))
+        }
+    }
+
+    let time;
+
+    match timeHelpers::string_to_bson_datetime(&payload.time) {
+        Ok(x) => {
+            time = x;
+        }
+
+        Err(err) => {
+            return Ok(Json(json!({
+                "status": "error",
+                "message": err.to_string()
+            })))
+        }
+    }
+
+    if let Err(err) = waitlist::expire_holds().await {
+        println!("Couldn't expire PIS holds: {}", err);
+    }
+
+    let offer_filter = doc! {
+        "gtid": id.clone(),
+        "time": time,
+        "status": to_bson(&WaitlistStatus::Offered).unwrap_or_default(),
+    };
+
+    match waitlist::get_waitlist(offer_filter).await {
+        Ok(offers) if offers.is_empty() => {
+            return Ok(Json(json!({
+                "status": "error",
+                "message": "there's no seat being held for you at this time, it may have expired"
+            })))
+        }
+
+        Ok(_offers) => {}
+
+        Err(err) => {
+            return Ok(Json(json!({
+                "status": "error",
+                "message": err.to_string()
+            })))
+        }
+    }
+
+    let entry = PISReschedule {
+        from: rushee.pis_timeslot,
+        to: time,
+        rescheduled_at: bson::DateTime::now(),
+        requested_by: Some("waitlist".to_string()),
+        reason: None,
+    };
+
+    if let Err(err) = pis::move_pis_timeslot(&id, &entry, true).await {
+        return Ok(Json(json!({
+            "status": "error",
+            "message": err.to_string()
+        })));
+    }
+
+    if let Err(err) = locations::allocate_location(&id).await {
+        println!("Couldn't give the PIS a location: {}", err);
+    }
+
+    // a rushee released after a no-show is also in the flex-window queue, taking the offer places them
+    if entry.from == flex::UNSCHEDULED {
+        let confirmation = format!("Your PIS is confirmed for {}", timeHelpers::format_local(&time));
+
+        if let Err(err) = flex::mark_placed(&id, time, "waitlist", &confirmation).await {
+            println!("Couldn't update the flex queue: {}", err);
+        }
+    }
+
+    if let Err(err) = waitlist::offer_open_seats(entry.from).await {
+        println!("Couldn't offer the released PIS seat: {}", err);
+    }
+
+    let message = format!(
+        "{} {}'s PIS moved from {} to {}",
+        rushee.first_name,
+        rushee.last_name,
+        entry.from.try_to_rfc3339_string().unwrap_or_default(),
+        entry.to.try_to_rfc3339_string().unwrap_or_default()
+    );
+
+    if let Err(err) = notifications::notify_brothers(&rushee.pis_signup.panel, &id, &message).await {
+        println!("Couldn't notify the PIS panel: {}", err);
+    }
+
+    Ok(Json(json!({
+        "status": "success",
+        "message": "successfully moved your PIS",
+        "payload": entry
+    })))
+}
+
+/**
+ * A rushee leaves the waitlist for a timeslot, or turns down the seat being held for them
+ */
+pub async fn leave_pis_waitlist(
+    Path(id): Path<String>,
+    Json(payload): Json<IncomingWaitlistOffer>,
+) -> Result<Json<Value>, StatusCode> {
+    if let Err(message) = verify_rushee(&id, &payload.access_code).await {
+        return Ok(Json(json!({
+            "status": "error",
+            "message": message
+        })));
+    }
+
+    let time;
+
+    match timeHelpers::string_to_bson_datetime(&payload.time) {
+        Ok(x) => {
+            time = x;
+        }
+
+        Err(err) => {
+            return Ok(Json(json!({
+                "status": "error",
+                "message": err.to_string()
+            })))
+        }
+    }
+
+    match waitlist::leave_waitlist(&id, time).await {
+        Ok(true) => Ok(Json(json!({
+            "status": "success",
+            "message": "successfully left the waitlist"
+        }))),
+
+        Ok(false) => Ok(Json(json!({
+            "status": "error",
+            "message": "you aren't on the waitlist for this time"
+        }))),
+
+        Err(err) => Ok(Json(json!({
+            "status": "error",
+            "message": err.to_string()
+        }))),
+    }
+}
+
+/**
+ * How many rushees are waiting for each PIS timeslot and how many seats are being held
+ */
+pub async fn get_waitlist_depth(headers: HeaderMap) -> Result<Json<Value>, StatusCode> {
+    if !is_admin(&headers) {
+        return Err(StatusCode::UNAUTHORIZED);
+    }
+
+    if let Err(err) = waitlist::expire_holds().await {
+        println!("Couldn't expire PIS holds: {}", err);
+    }
+
+    let active_filter = doc! {"status": {"$in": [
+        to_bson(&WaitlistStatus::Waiting).unwrap_or_default(),
+        to_bson(&WaitlistStatus::Offered).unwrap_or_default(),
+    ]}};
+
+    let fetched = (pis::get_pis_timeslots().await, waitlist::get_waitlist(active_filter).await);
+
+    let timeslots;
+    let entries;
+
+    match fetched {
+        (Ok(x), Ok(y)) => {
+            timeslots = x;
+            entries = y;
+        }
+
+        (Err(err), _) | (_, Err(err)) => {
+            return Ok(Json(json!({
+                "status": "error",
+                "message": err.to_string()
+            })))
+        }
+    }
+
+    let depth: Vec<WaitlistDepth> = timeslots
+        .into_iter()
+        .map(|timeslot| {
+            let at_time = entries.iter().filter(|entry| entry.time == timeslot.time);
+
+            WaitlistDepth {
+                time: timeslot.time,
+                num_available: timeslot.num_available,
+                waiting: at_time.clone().filter(|entry| entry.status == WaitlistStatus::Waiting).count() as u32,
+                held: at_time.filter(|entry| entry.status == WaitlistStatus::Offered).count() as u32,
+            }
+        })
+        .collect();
+
+    Ok(Json(json!({
+        "status": "success",
+        "payload": depth
+    })))
+}
+
+/**
+ * Generates PIS timeslots in bulk: every interval_minutes between daily_start and daily_end on each day
+ * from start_date to end_date, skipping blackout windows. Each timeslot gets `capacity` seats.
+ * Timeslots that already exist are skipped unless overwrite is set, in which case their capacity is changed
+ * (seats that are already booked or held for the waitlist count against it)
+ */
+pub async fn generate_pis_timeslots(
+    headers: HeaderMap,
+    Json(payload): Json<IncomingTimeslotGeneration>,
+) -> Result<Json<Value>, StatusCode> {
+    if !is_admin(&headers) {
+        return Err(StatusCode::UNAUTHORIZED);
+    }
+
+    let times;
+
+    match pis::generate_timeslot_times(&payload) {
+        Ok(x) => {
+            times = x;
+        }
+
+        Err(err) => {
+            return Ok(Json(json!({
+                "status": "error",
+                "message": err.to_string()
+            })))
+        }
+    }
+
+    if times.is_empty() {
+        return Ok(Json(json!({
+            "status": "error",
+            "message": "no timeslots fit in the given dates and times"
+        })));
+    }
+
+    let held_filter = doc! {"status": to_bson(&WaitlistStatus::Offered).unwrap_or_default()};
+
+    let fetched = (
+        pis::get_pis_timeslots().await,
+        fetch_scheduled_rushees().await.map_err(|message| std::io::Error::new(std::io::ErrorKind::Other, message)),
+        waitlist::get_waitlist(held_filter).await,
+    );
+
+    let existing;
+    let rushees;
+    let holds;
+
+    match fetched {
+        (Ok(x), Ok(y), Ok(z)) => {
+            existing = x;
+            rushees = y;
+            holds = z;
+        }
+
+        (Err(err), _, _) | (_, Err(err), _) | (_, _, Err(err)) => {
+            return Ok(Json(json!({
+                "status": "error",
+                "message": err.to_string()
+            })))
+        }
+    }
+
+    let connection = db::get_pis_timeslots_client().await;
+
+    let mut new_timeslots = Vec::<PISTimeslot>::new();
+    let mut updated = 0;
+    let mut skipped = 0;
+
+    let mut conflicted = 0;
+
+    for time in times.iter() {
+        let current;
+
+        match existing.iter().find(|timeslot| timeslot.time == *time) {
+            Some(x) => {
+                current = x;
+            }
+
+            None => {
+                new_timeslots.push(PISTimeslot {
+                    time: *time,
+                    num_available: payload.capacity,
+                });
+
+                continue;
+            }
+        }
+
+        if !payload.overwrite {
+            skipped += 1;
+            continue;
+        }
+
+        let taken = rushees.iter().filter(|rushee| rushee.pis_timeslot == *time).count()
+            + holds.iter().filter(|entry| entry.time == *time).count();
+        let change = (payload.capacity - taken as i32).max(0) - current.num_available;
+
+        // only change the seats if nobody booked or got held a seat since we counted, otherwise their seat is lost
+        let filter = doc! {"time": *time, "num_available": current.num_available};
+        let update = doc! {"$inc": {"num_available": change}};
+
+        match connection.update_one(filter, update).await {
+            Ok(update_result) if update_result.matched_count == 0 => {
+                conflicted += 1;
+                continue;
+            }
+
+            Ok(_update_result) => {}
+
+            Err(_err) => {
+                return Ok(Json(json!({
+                    "status": "error",
+                    "message": "couldn't update PIS timeslot"
+                })))
+            }
+        }
+
+        if let Err(err) = waitlist::offer_open_seats(*time).await {
+            println!("Couldn't offer the new PIS seats: {}", err);
+        }
+
+        updated += 1;
+    }
+
+    let created = new_timeslots.len();
+
+    if !new_timeslots.is_empty() {
+        if let Err(_err) = connection.insert_many(new_timeslots).await {
+            return Ok(Json(json!({
+                "status": "error",
+                "message": "some error occurred while creating the PIS timeslots"
+            })));
+        }
+    }
+
+    Ok(Json(json!({
+        "status": "success",
+        "message": format!(
+            "created {} timeslots, updated {}, skipped {} that already existed, {} changed while updating (try again to update them)",
+            created, updated, skipped, conflicted
+        ),
+        "payload": times
+    })))
+}
+
+/**
+ * Every PIS timeslot (optionally between `from` and `to`) with its capacity, how many seats are booked
+ * or held for the waitlist, and who is interviewing whom
+ */
+pub async fn get_pis_calendar(
+    headers: HeaderMap,
+    Query(params): Query<CalendarParams>,
+) -> Result<Json<Value>, StatusCode> {
+    if !is_admin(&headers) {
+        return Err(StatusCode::UNAUTHORIZED);
+    }
+
+    let mut range = (None, None);
+
+    for (bound, param) in [(&mut range.0, &params.from), (&mut range.1, &params.to)] {
+        if let Some(param) = param {
+            match timeHelpers::string_to_bson_datetime(param) {
+                Ok(x) => {
+                    *bound = Some(x);
+                }
+
+                Err(err) => {
+                    return Ok(Json(json!({
+                        "status": "error",
+                        "message": err.to_string()
+                    })))
+                }
+            }
+        }
+    }
+
+    if let Err(err) = waitlist::expire_holds().await {
+        println!("Couldn't expire PIS holds: {}", err);
+    }
+
+    let held_filter = doc! {"status": to_bson(&WaitlistStatus::Offered).unwrap_or_default()};
+
+    let fetched = (
+        pis::get_pis_timeslots().await,
+        fetch_scheduled_rushees().await.map_err(|message| std::io::Error::new(std::io::ErrorKind::Other, message)),
+        waitlist::get_waitlist(held_filter).await,
+    );
+
+    let timeslots;
+    let rushees;
+    let holds;
+
+    match fetched {
+        (Ok(x), Ok(y), Ok(z)) => {
+            timeslots = x;
+            rushees = y;
+            holds = z;
+        }
+
+        (Err(err), _, _) | (_, Err(err), _) | (_, _, Err(err)) => {
+            return Ok(Json(json!({
+                "status": "error",
+                "message": err.to_string()
+            })))
+        }
+    }
+
+    let calendar: Vec<PISCalendarSlot> = timeslots
+        .into_iter()
+        .filter(|timeslot| range.0.map_or(true, |from| timeslot.time >= from))
+        .filter(|timeslot| range.1.map_or(true, |to| timeslot.time <= to))
+        .map(|timeslot| {
+            let bookings: Vec<PISCalendarBooking> = rushees
+                .iter()
+                .filter(|rushee| rushee.pis_timeslot == timeslot.time)
+                .map(|rushee| PISCalendarBooking {
+                    rushee_gtid: rushee.gtid.clone(),
+                    rushee_name: assignment::full_name(&rushee.first_name, &rushee.last_name),
+                    brothers: rushee.pis_signup.panel.clone(),
+                })
+                .collect();
+
+            let mut brothers = Vec::<PISBrother>::new();
+
+            for member in bookings.iter().flat_map(|booking| booking.brothers.iter()) {
+                let already_listed = brothers
+                    .iter()
+                    .any(|brother| brother.first_name == member.first_name && brother.last_name == member.last_name);
+
+                if !already_listed {
+                    brothers.push(PISBrother {
+                        first_name: member.first_name.clone(),
+                        last_name: member.last_name.clone(),
+                        role: None,
+                    });
+                }
+            }
+
+            let booked = bookings.len() as u32;
+            let held = holds.iter().filter(|entry| entry.time == timeslot.time).count() as u32;
+
+            PISCalendarSlot {
+                time: timeslot.time,
+                capacity: timeslot.num_available + booked as i32 + held as i32,
+                num_available: timeslot.num_available,
+                booked: booked,
+                held: held,
+                bookings: bookings,
+                brothers: brothers,
+            }
+        })
+        .collect();
+
+    Ok(Json(json!({
+        "status": "success",
+        "payload": calendar
+    })))
+}
+
+/**
+ * Flex-window rushees waiting for a PIS time, longest waiting first, each with the timeslots they could
+ * be placed into (seats left over and enough free brothers for a panel)
+ */
+pub async fn get_flex_queue(headers: HeaderMap) -> Result<Json<Value>, StatusCode> {
+    if !is_admin(&headers) {
+        return Err(StatusCode::UNAUTHORIZED);
+    }
+
+    let queued_filter = doc! {"status": to_bson(&FlexStatus::Queued).unwrap_or_default()};
+
+    let fetched = (
+        flex::get_flex_queue(queued_filter).await,
+        pis::get_pis_timeslots().await,
+        pis::get_brother_availability().await,
+        fetch_scheduled_rushees().await.map_err(|message| std::io::Error::new(std::io::ErrorKind::Other, message)),
+    );
+
+    let queue;
+    let timeslots;
+    let availability;
+    let rushees;
+
+    match fetched {
+        (Ok(w), Ok(x), Ok(y), Ok(z)) => {
+            queue = w;
+            timeslots = x;
+            availability = y;
+            rushees = z;
+        }
+
+        (Err(err), _, _, _) | (_, Err(err), _, _) | (_, _, Err(err), _) | (_, _, _, Err(err)) => {
+            return Ok(Json(json!({
+                "status": "error",
+                "message": err.to_string()
+            })))
+        }
+    }
+
+    let answer: Vec<FlexQueueEntry> = queue
+        .into_iter()
+        .map(|entry| FlexQueueEntry {
+            options: flex::placement_options(&entry, &timeslots, &availability, &rushees),
+            entry: entry,
+        })
+        .collect();
+
+    Ok(Json(json!({
+        "status": "success",
+        "payload": answer
+    })))
+}
+
+/**
+ * Places a flex-window rushee into a PIS timeslot. Takes a seat there, gives the rushee the time and
+ * leaves them a confirmation they can see with their access code
+ */
+pub async fn place_flex_rushee(
+    Path(id): Path<String>,
+    headers: HeaderMap,
+    Json(payload): Json<IncomingFlexPlacement>,
+) -> Result<Json<Value>, StatusCode> {
+    if !is_admin(&headers) {
+        return Err(StatusCode::UNAUTHORIZED);
+    }
+
+    let time;
+
+    match timeHelpers::string_to_bson_datetime(&payload.time) {
+        Ok(x) => {
+            time = x;
+        }
+
+        Err(err) => {
+            return Ok(Json(json!({
+                "status": "error",
+                "message": err.to_string()
+            })))
+        }
+    }
+
+    match flex::get_flex_entry(&id).await {
+        Ok(Some(entry)) if entry.status == FlexStatus::Queued => {}
+
+        Ok(Some(_entry)) => {
+            return Ok(Json(json!({
+                "status": "error",
+                "message": "this rushee isn't waiting in the flex-window queue"
+            })))
+        }
+
+        Ok(None) => {
+            return Ok(Json(json!({
+                "status": "error",
+                "message": "this rushee isn't a flex-window rushee"
+            })))
+        }
+
+        Err(err) => {
+            return Ok(Json(json!({
+                "status": "error",
+                "message": err.to_string()
+            })))
+        }
+    }
+
+    if let Err(err) = waitlist::expire_holds().await {
+        println!("Couldn't expire PIS holds: {}", err);
+    }
+
+    let entry = PISReschedule {
+        from: flex::UNSCHEDULED,
+        to: time,
+        rescheduled_at: bson::DateTime::now(),
+        requested_by: Some(payload.placed_by.clone()),
+        reason: Some("flex-window placement".to_string()),
+    };
+
+    if let Err(err) = pis::move_pis_timeslot(&id, &entry, false).await {
+        return Ok(Json(json!({
+            "status": "error",
+            "message": err.to_string()
+        })));
+    }
+
+    if let Err(err) = locations::allocate_location(&id).await {
+        println!("Couldn't give the PIS a location: {}", err);
+    }
+
+    let local_time = timeHelpers::format_local(&time);
+    let confirmation = format!("Your PIS is confirmed for {}", local_time);
+
+    if let Err(err) = flex::mark_placed(&id, time, &payload.placed_by, &confirmation).await {
+        println!("Couldn't update the flex queue: {}", err);
+    }
+
+    Ok(Json(json!({
+        "status": "success",
+        "message": format!("placed rushee at {}", local_time),
+        "payload": entry
+    })))
+}
+
+/**
+ * Where a flex-window rushee is in the queue, and their confirmation once they've been given a PIS time
+ */
+pub async fn get_flex_status(
+    Path(id): Path<String>,
+    Json(payload): Json<IncomingAccessCode>,
+) -> Result<Json<Value>, StatusCode> {
+    if let Err(message) = verify_rushee(&id, &payload.access_code).await {
+        return Ok(Json(json!({
+            "status": "error",
+            "message": message
+        })));
+    }
+
+    match flex::get_flex_entry(&id).await {
+        Ok(Some(entry)) => Ok(Json(json!({
+            "status": "success",
+            "payload": entry
+        }))),
+
+        Ok(None) => Ok(Json(json!({
+            "status": "error",
+            "message": "you didn't sign up with a flex window"
+        }))),
+
+        Err(err) => Ok(Json(json!({
+            "status": "error",
+            "message": err.to_string()
+        }))),
+    }
+}
+
+/**
+ * A brother on a rushee's PIS panel submits their scorecard: notes and a rubric score per question and an
+ * overall recommendation. Submitting again replaces their scorecard. The rushee's PIS summary is updated
+ */
+pub async fn submit_scorecard(
+    Path(id): Path<String>,
+    Json(payload): Json<IncomingScorecard>,
+) -> Result<Json<Value>, StatusCode> {
+    let rushee;
+
+    match fetch_rushee(id.clone()).await {
+        Ok(x) => {
+            rushee = x;
+        }
+
+        Err(err) => {
+            return Ok(Json(json!({
+                "status": "error",
+                "message": err.message
+            })))
+        }
+    }
+
+    let member;
+
+    match scorecards::panel_member(&rushee, &payload.first_name, &payload.last_name) {
+        Some(x) => {
+            member = x.clone();
+        }
+
+        None => {
+            return Ok(Json(json!({
+                "status": "error",
+                "message": "only brothers on this rushee's PIS panel can submit a scorecard"
+            })))
+        }
+    }
+
+    // archived questions are included so scorecards written against them can still be edited
+    let question_bank;
+
+    match questions::get_questions(true).await {
+        Ok(x) => {
+            question_bank = x;
+        }
+
+        Err(err) => {
+            return Ok(Json(json!({
+                "status": "error",
+                "message": err.to_string()
+            })))
+        }
+    }
+
+    let submitted_at = rushee
+        .pis_scorecards
+        .iter()
+        .find(|scorecard| scorecards::same_brother(scorecard, &member.first_name, &member.last_name))
+        .map(|scorecard| scorecard.submitted_at)
+        .unwrap_or(bson::DateTime::now());
+
+    let scorecard;
+
+    match scorecards::build_scorecard(&member, payload, &question_bank, submitted_at) {
+        Ok(x) => {
+            scorecard = x;
+        }
+
+        Err(err) => {
+            return Ok(Json(json!({
+                "status": "error",
+                "message": err.to_string()
+            })))
+        }
+    }
+
+    if let Err(err) = scorecards::save_scorecard(&id, &scorecard).await {
+        return Ok(Json(json!({
+            "status": "error",
+            "message": err.to_string()
+        })));
+    }
+
+    let summary = match fetch_rushee(id.clone()).await {
+        Ok(updated) => scorecards::refresh_summary(&updated).await.map_err(|err| err.to_string()),
+        Err(err) => Err(err.message),
+    };
+
+    match summary {
+        Ok(summary) => Ok(Json(json!({
+            "status": "success",
+            "message": "successfully submitted scorecard",
+            "payload": summary
+        }))),
+
+        Err(message) => Ok(Json(json!({
+            "status": "error",
+            "message": format!("the scorecard was saved but the PIS summary couldn't be updated: {}", message)
+        }))),
+    }
+}
+
+/**
+ * The scorecard a brother submitted for a rushee, if they have
+ */
+pub async fn get_scorecard(
+    Path(id): Path<String>,
+    Json(payload): Json<IncomingBrotherName>,
+) -> Result<Json<Value>, StatusCode> {
+    match fetch_rushee(id).await {
+        Ok(rushee) => {
+            let scorecard = rushee
+                .pis_scorecards
+                .into_iter()
+                .find(|scorecard| scorecards::same_brother(scorecard, &payload.first_name, &payload.last_name));
+
+            Ok(Json(json!({
+                "status": "success",
+                "payload": scorecard
+            })))
+        }
+
+        Err(err) => Ok(Json(json!({
+            "status": "error",
+            "message": err.message
+        }))),
+    }
+}
+
+/**
+ * Every scorecard on a rushee's PIS with the PIS summary, for admins and bid committee.
+ * Bid committee sees the scorecards without interviewer names
+ */
+pub async fn get_pis_scorecards(
+    Path(id): Path<String>,
+    headers: HeaderMap,
+) -> Result<Json<Value>, StatusCode> {
+    let role = get_role(&headers);
+
+    if role == Role::Brother {
+        return Err(StatusCode::UNAUTHORIZED);
+    }
+
+    match fetch_rushee(id).await {
+        Ok(mut rushee) => {
+            if role == Role::BidCommittee {
+                scorecards::anonymize_scorecards(&mut rushee.pis_scorecards);
+                pis::anonymize_pis_status(&mut rushee.pis_status, &mut rushee.pis_status_history);
+            }
+
+            Ok(Json(json!({
+                "status": "success",
+                "payload": {
+                    "pis_status": rushee.pis_status,
+                    "pis_status_history": rushee.pis_status_history,
+                    "scorecards": rushee.pis_scorecards,
+                    "summary": rushee.pis_summary,
+                    "rubric_max": scorecards::rubric_max(),
+                }
+            })))
+        }
+
+        Err(err) => Ok(Json(json!({
+            "status": "error",
+            "message": err.message
+        }))),
+    }
+}
+
+/**
+ * Builds a PIS location from an admin's input
+ */
+fn build_location(payload: IncomingPISLocation, id: Option<bson::oid::ObjectId>) -> Result<PISLocation, std::io::Error> {
+    let kind = locations::parse_location_kind(&payload.kind)?;
+    let link = payload.link.unwrap_or_default().trim().to_string();
+
+    if payload.name.trim().is_empty() {
+        return Err(std::io::Error::new(std::io::ErrorKind::InvalidInput, "the location needs a name"));
+    }
+
+    if kind == LocationKind::Virtual && !link.starts_with("https://") && !link.starts_with("http://") {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::InvalidInput,
+            "virtual locations need a meeting link",
+        ));
+    }
+
+    Ok(PISLocation {
+        id: id,
+        name: payload.name.trim().to_string(),
+        kind: kind,
+        link: link,
+        meeting_id: payload.meeting_id.unwrap_or_default().trim().to_string(),
+        capacity: payload.capacity.unwrap_or(1),
+        retired: false,
+        revision: 0,
+    })
+}
+
+/**
+ * Adds a room or meeting link to the pool PIS locations are given out from
+ */
+pub async fn add_pis_location(
+    headers: HeaderMap,
+    Json(payload): Json<IncomingPISLocation>,
+) -> Result<Json<Value>, StatusCode> {
+    if !is_admin(&headers) {
+        return Err(StatusCode::UNAUTHORIZED);
+    }
+
+    let location;
+
+    match build_location(payload, Some(bson::oid::ObjectId::new())) {
+        Ok(x) => {
+            location = x;
+        }
+
+        Err(err) => {
+            return Ok(Json(json!({
+                "status": "error",
+                "message": err.to_string()
+            })))
+        }
+    }
+
+    let connection = db::get_pis_locations_client().await;
+
+    match connection.insert_one(&location).await {
+        Ok(_insert_result) => Ok(Json(json!({
+            "status": "success",
+            "message": "successfully added PIS location",
+            "payload": location
+        }))),
+
+        Err(_err) => Ok(Json(json!({
+            "status": "error",
+            "message": "failed to add PIS location"
+        }))),
+    }
+}
+
+/**
+ * Changes a PIS location. Rushees already booked there keep it, their link and meeting id are updated
+ */
+pub async fn update_pis_location(
+    Path(id): Path<String>,
+    headers: HeaderMap,
+    Json(payload): Json<IncomingPISLocation>,
+) -> Result<Json<Value>, StatusCode> {
+    if !is_admin(&headers) {
+        return Err(StatusCode::UNAUTHORIZED);
+    }
+
+    let location;
+
+    match locations::parse_location_id(&id).and_then(|oid| build_location(payload, Some(oid))) {
+        Ok(x) => {
+            location = x;
+        }
+
+        Err(err) => {
+            return Ok(Json(json!({
+                "status": "error",
+                "message": err.to_string()
+            })))
+        }
+    }
+
+    let connection = db::get_pis_locations_client().await;
+
+    let update = doc! {"$set": {
+        "name": location.name.clone(),
+        "kind": to_bson(&location.kind).unwrap_or_default(),
+        "link": location.link.clone(),
+        "meeting_id": location.meeting_id.clone(),
+        "capacity": location.capacity,
+    }};
+
+    match connection.update_one(doc! {"_id": location.id}, update).await {
+        Ok(update_result) if update_result.matched_count == 0 => {
+            return Ok(Json(json!({
+                "status": "error",
+                "message": "PIS location does not exist"
+            })))
+        }
+
+        Ok(_update_result) => {}
+
+        Err(_err) => {
+            return Ok(Json(json!({
+                "status": "error",
+                "message": "couldn't update the PIS location"
+            })))
+        }
+    }
+
+    let rushees = db::get_rushee_client().await;
+
+    let booked_update = doc! {"$set": {
+        "pis_location.name": location.name.clone(),
+        "pis_location.kind": to_bson(&location.kind).unwrap_or_default(),
+        "pis_location.link": location.link.clone(),
+        "pis_location.meeting_id": location.meeting_id.clone(),
+        "pis_link": location.link.clone(),
+        "pis_meeting_id": location.meeting_id.clone(),
+    }};
+
+    if let Err(_err) = rushees.update_many(doc! {"pis_location.location_id": id}, booked_update).await {
+        return Ok(Json(json!({
+            "status": "error",
+            "message": "the location was updated but the rushees booked there couldn't be"
+        })));
+    }
+
+    Ok(Json(json!({
+        "status": "success",
+        "message": "updated PIS location",
+        "payload": location
+    })))
+}
+
+/**
+ * Takes a PIS location out of the pool. Upcoming PIS booked there are moved to other locations
+ */
+pub async fn retire_pis_location(
+    Path(id): Path<String>,
+    headers: HeaderMap,
+) -> Result<Json<Value>, StatusCode> {
+    if !is_admin(&headers) {
+        return Err(StatusCode::UNAUTHORIZED);
+    }
+
+    let oid;
+
+    match locations::parse_location_id(&id) {
+        Ok(x) => {
+            oid = x;
+        }
+
+        Err(err) => {
+            return Ok(Json(json!({
+                "status": "error",
+                "message": err.to_string()
+            })))
+        }
+    }
+
+    let connection = db::get_pis_locations_client().await;
+
+    match connection.update_one(doc! {"_id": oid}, doc! {"$set": {"retired": true}}).await {
+        Ok(update_result) if update_result.matched_count == 0 => {
+            return Ok(Json(json!({
+                "status": "error",
+                "message": "PIS location does not exist"
+            })))
+        }
+
+        Ok(_update_result) => {}
+
+        Err(_err) => {
+            return Ok(Json(json!({
+                "status": "error",
+                "message": "couldn't retire the PIS location"
+            })))
+        }
+    }
+
+    let upcoming_filter = doc! {
+        "pis_location.location_id": id,
+        "pis_timeslot": {"$gte": bson::DateTime::now()},
+    };
+
+    let mut moved = 0;
+    let mut unplaced = Vec::<String>::new();
+
+    match db::get_rushee_client().await.find(upcoming_filter).await {
+        Ok(mut cursor) => {
+            while let Some(rushee) = cursor.next().await {
+                match rushee {
+                    Ok(rushee) => match locations::allocate_location(&rushee.gtid).await {
+                        Ok(_location) => moved += 1,
+                        Err(_err) => unplaced.push(rushee.gtid),
+                    },
+
+                    Err(_err) => {
+                        return Ok(Json(json!({
+                            "status": "error",
+                            "message": "there was an error reading a rushee"
+                        })))
+                    }
+                }
+            }
+        }
+
+        Err(_err) => {
+            return Ok(Json(json!({
+                "status": "error",
+                "message": "some network error occurred"
+            })))
+        }
+    }
+
+    Ok(Json(json!({
+        "status": "success",
+        "message": format!("retired PIS location, moved {} PIS and {} couldn't be moved", moved, unplaced.len()),
+        "payload": unplaced
+    })))
+}
+
+/**
+ * The pool of PIS locations
+ */
+pub async fn get_pis_locations(headers: HeaderMap) -> Result<Json<Value>, StatusCode> {
+    if !is_admin(&headers) {
+        return Err(StatusCode::UNAUTHORIZED);
+    }
+
+    match locations::get_locations(true).await {
+        Ok(location_list) => Ok(Json(json!({
+            "status": "success",
+            "payload": location_list
+        }))),
+
+        Err(err) => Ok(Json(json!({
+            "status": "error",
+            "message": err.to_string()
+        }))),
+    }
+}
+
+/**
+ * Gives every scheduled PIS without a location one, e.g. after adding rooms to the pool.
+ * Returns the rushees who still couldn't be given one
+ */
+pub async fn allocate_pis_locations(headers: HeaderMap) -> Result<Json<Value>, StatusCode> {
+    if !is_admin(&headers) {
+        return Err(StatusCode::UNAUTHORIZED);
+    }
+
+    let rushees;
+
+    match fetch_scheduled_rushees().await {
+        Ok(x) => {
+            rushees = x;
+        }
+
+        Err(message) => {
+            return Ok(Json(json!({
+                "status": "error",
+                "message": message
+            })))
+        }
+    }
+
+    let mut allocated = 0;
+    let mut unplaced = Vec::<String>::new();
+
+    for rushee in rushees.iter().filter(|rushee| rushee.pis_location.is_none()) {
+        match locations::allocate_location(&rushee.gtid).await {
+            Ok(_location) => allocated += 1,
+            Err(_err) => unplaced.push(rushee.gtid.clone()),
+        }
+    }
+
+    Ok(Json(json!({
+        "status": "success",
+        "message": format!("gave {} PIS a location, {} couldn't be given one", allocated, unplaced.len()),
+        "payload": unplaced
+    })))
+}
+
+/**
+ * Marks whether a rushee's PIS happened: completed, no-show or cancelled. Interviewers on the panel and
+ * admins can mark it. A no-show can give the timeslot back (release_slot), in which case the rushee is
+ * put in the flex-window queue and offered the next open timeslot, if there is one
+ */
+pub async fn mark_pis_status(
+    Path(id): Path<String>,
+    headers: HeaderMap,
+    Json(payload): Json<IncomingPISStatus>,
+) -> Result<Json<Value>, StatusCode> {
+    let rushee;
+
+    match fetch_rushee(id.clone()).await {
+        Ok(x) => {
+            rushee = x;
+        }
+
+        Err(err) => {
+            return Ok(Json(json!({
+                "status": "error",
+                "message": err.message
+            })))
+        }
+    }
+
+    if rushee.pis_timeslot == flex::UNSCHEDULED {
+        return Ok(Json(json!({
+            "status": "error",
+            "message": "this rushee doesn't have a PIS scheduled"
+        })));
+    }
+
+    let marked_by = match scorecards::panel_member(&rushee, &payload.first_name, &payload.last_name) {
+        Some(member) => assignment::full_name(&member.first_name, &member.last_name),
+        None if is_admin(&headers) => "admin".to_string(),
+        None => {
+            return Ok(Json(json!({
+                "status": "error",
+                "message": "only brothers on this rushee's PIS panel and admins can mark the PIS"
+            })))
+        }
+    };
+
+    let status;
+
+    match pis::parse_pis_outcome(&payload.status) {
+        Ok(x) => {
+            status = x;
+        }
+
+        Err(err) => {
+            return Ok(Json(json!({
+                "status": "error",
+                "message": err.to_string()
+            })))
+        }
+    }
+
+    if status != PISOutcome::Cancelled && rushee.pis_timeslot > bson::DateTime::now() {
+        return Ok(Json(json!({
+            "status": "error",
+            "message": "this PIS hasn't happened yet"
+        })));
+    }
+
+    if payload.release_slot && status != PISOutcome::NoShow {
+        return Ok(Json(json!({
+            "status": "error",
+            "message": "only no-shows can give their timeslot back"
+        })));
+    }
+
+    let record = PISStatusRecord {
+        status: status.clone(),
+        time: rushee.pis_timeslot,
+        marked_at: bson::DateTime::now(),
+        marked_by: marked_by.clone(),
+        note: payload.note.map(|note| note.trim().to_string()).filter(|note| !note.is_empty()),
+    };
+
+    let record_bson;
+
+    match to_bson(&record) {
+        Ok(x) => {
+            record_bson = x;
+        }
+
+        Err(_err) => {
+            return Ok(Json(json!({
+                "status": "error",
+                "message": "some issue occurred when serializing the PIS status"
+            })))
+        }
+    }
+
+    let connection = db::get_rushee_client().await;
+
+    // only mark the PIS if it wasn't moved in the meantime
+    let filter = doc! {"gtid": id.clone(), "pis_timeslot": rushee.pis_timeslot};
+    let update = doc! {
+        "$set": {"pis_status": record_bson.clone()},
+        "$push": {"pis_status_history": record_bson},
+    };
+
+    match connection.update_one(filter, update).await {
+        Ok(update_result) if update_result.matched_count == 0 => {
+            return Ok(Json(json!({
+                "status": "error",
+                "message": "the PIS was rescheduled in the meantime, try again"
+            })))
+        }
+
+        Ok(_update_result) => {}
+
+        Err(_err) => {
+            return Ok(Json(json!({
+                "status": "error",
+                "message": "failed to update rushee"
+            })))
+        }
+    }
+
+    let local_time = timeHelpers::format_local(&rushee.pis_timeslot);
+
+    let message = match status {
+        PISOutcome::Completed => None,
+        PISOutcome::NoShow => Some(format!(
+            "{} {} didn't show up to their PIS at {}",
+            rushee.first_name, rushee.last_name, local_time
+        )),
+        PISOutcome::Cancelled => Some(format!(
+            "{} {}'s PIS at {} was cancelled",
+            rushee.first_name, rushee.last_name, local_time
+        )),
+    };
+
+    if let Some(message) = message {
+        if let Err(err) = notifications::notify_brothers(&rushee.pis_signup.panel, &id, &message).await {
+            println!("Couldn't notify the PIS panel: {}", err);
+        }
+    }
+
+    if !payload.release_slot {
+        return Ok(Json(json!({
+            "status": "success",
+            "message": "successfully marked PIS",
+            "payload": record
+        })));
+    }
+
+    if let Err(err) = pis::release_pis_timeslot(&id, rushee.pis_timeslot, &marked_by, "no-show").await {
+        return Ok(Json(json!({
+            "status": "error",
+            "message": format!("the PIS was marked but the timeslot couldn't be released: {}", err)
+        })));
+    }
+
+    if let Err(err) = locations::allocate_location(&id).await {
+        println!("Couldn't give the PIS a location: {}", err);
+    }
+
+    // the rushee always goes in the flex-window queue, so they still get placed if they let the offer below run out
+    let rushee_name = assignment::full_name(&rushee.first_name, &rushee.last_name);
+
+    if let Err(err) = flex::queue_flex_rushee(&id, &rushee_name, Vec::new()).await {
+        return Ok(Json(json!({
+            "status": "error",
+            "message": format!("the PIS was marked and the timeslot released, but the rushee couldn't be queued for a new time: {}", err)
+        })));
+    }
+
+    let follow_up = match waitlist::offer_reschedule(&id).await {
+        Ok(Some(time)) => format!(
+            "the rushee was offered a new PIS at {} and is in the flex-window queue until they take it",
+            timeHelpers::format_local(&time)
+        ),
+
+        Ok(None) => "every upcoming timeslot is full, the rushee was put in the flex-window queue".to_string(),
+
+        Err(err) => format!("the rushee was put in the flex-window queue but couldn't be offered a new time: {}", err),
+    };
+
+    Ok(Json(json!({
+        "status": "success",
+        "message": format!("successfully marked PIS, {}", follow_up),
+        "payload": record
+    })))
+}
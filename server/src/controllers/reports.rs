@@ -0,0 +1,689 @@
+use axum::{
+    extract::{Path, Query},
+    http::{header, HeaderMap, StatusCode},
+    response::{IntoResponse, Json, Response},
+};
+use futures::stream::StreamExt;
+use mongodb::bson::doc;
+use redis::AsyncCommands;
+use serde_json::{json, Value};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::env;
+
+use super::db;
+use super::voting::VOTE_COUNTS;
+use crate::middlewares::{assignment::full_name, attendance, attendance::night_key, auth::is_admin, invitations};
+use crate::models::misc::RsvpStatus;
+use crate::models::reports::{
+    BrotherParticipation, CoverageParams, InvitationReconciliation, InviteeAttendance, NightAttendance,
+    ReportParams, RsvpCounts, RusheeCoverage,
+};
+use crate::models::Rushee::RusheeModel;
+
+const DEFAULT_COVERAGE_MIN_COMMENTS: usize = 5;
+const DEFAULT_COVERAGE_MIN_BROTHERS: usize = 3;
+const DEFAULT_COVERAGE_MIN_NIGHTS: usize = 1;
+const DEFAULT_MAX_SUGGESTIONS: usize = 5;
+
+/**
+ * Reads a coverage threshold, query param first, then the env var, then the default
+ */
+fn coverage_threshold(param: Option<usize>, env_var: &str, default: usize) -> usize {
+    param.unwrap_or_else(|| {
+        env::var(env_var)
+            .ok()
+            .and_then(|value| value.parse::<usize>().ok())
+            .unwrap_or(default)
+    })
+}
+
+/**
+ * Quotes a value for a CSV cell if it needs it
+ */
+fn csv_field(value: &str) -> String {
+    if value.contains(',') || value.contains('"') || value.contains('\n') {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+fn csv_response(filename: &str, body: String) -> Response {
+    (
+        [
+            (header::CONTENT_TYPE, "text/csv".to_string()),
+            (
+                header::CONTENT_DISPOSITION,
+                format!("attachment; filename=\"{}\"", filename),
+            ),
+        ],
+        body,
+    )
+        .into_response()
+}
+
+/**
+ * Comments, PIS panels, votes and the roster don't always agree on spacing or capitalization,
+ * so brothers are matched on their normalized name
+ */
+fn participation_key(brother_name: &str) -> String {
+    brother_name.split_whitespace().collect::<Vec<&str>>().join(" ").to_lowercase()
+}
+
+fn participation_entry<'a>(
+    participation: &'a mut HashMap<String, BrotherParticipation>,
+    brother_name: &str,
+) -> &'a mut BrotherParticipation {
+    participation
+        .entry(participation_key(brother_name))
+        .or_insert_with(|| BrotherParticipation {
+            brother_name: brother_name.split_whitespace().collect::<Vec<&str>>().join(" "),
+            ..Default::default()
+        })
+}
+
+/**
+ * Brother participation report: comments per night, distinct rushees met, average rating given,
+ * PIS assignments and votes cast per brother.
+ * Every brother on the roster gets a row, even with nothing to show, so inactive brothers stand out.
+ * The roster is everyone who submitted PIS availability plus anyone in ?brothers=First Last,First Last
+ * Votes only count the ones cast since vote counting was added, older votes aren't included.
+ * Sort with ?sort_by=name|comments|rushees_met|average_rating|pis|votes&order=asc|desc,
+ * export with ?format=csv
+ */
+pub async fn get_brother_participation(
+    headers: HeaderMap,
+    Query(params): Query<ReportParams>,
+) -> Result<Response, StatusCode> {
+    if !is_admin(&headers) {
+        return Err(StatusCode::UNAUTHORIZED);
+    }
+
+    let mut participation = HashMap::<String, BrotherParticipation>::new();
+    let mut rushees_met = HashMap::<String, HashSet<String>>::new();
+    let mut ratings_given = HashMap::<String, Vec<f32>>::new();
+
+    // roster first, so brothers keep the name they're listed under
+    for brother_name in params.brothers.as_deref().unwrap_or("").split(',') {
+        if !brother_name.trim().is_empty() {
+            participation_entry(&mut participation, brother_name);
+        }
+    }
+
+    let availability_connection = db::get_brother_availability_client().await;
+
+    match availability_connection.find(doc! {}).await {
+        Ok(mut cursor) => {
+            while let Some(availability) = cursor.next().await {
+                if let Ok(brother) = availability {
+                    participation_entry(&mut participation, &full_name(&brother.first_name, &brother.last_name));
+                }
+            }
+        }
+
+        Err(_err) => {
+            return Ok(Json(json!({
+                "status": "error",
+                "message": "some network error occurred"
+            }))
+            .into_response())
+        }
+    }
+
+    let connection = db::get_rushee_client().await;
+
+    let result = connection.find(doc! {}).await;
+
+    match result {
+        Ok(mut cursor) => {
+            while let Some(rushee) = cursor.next().await {
+                match rushee {
+                    Ok(doc) => {
+                        for comment in doc.comments.iter() {
+                            let entry = participation_entry(&mut participation, &comment.brother_name);
+
+                            entry.total_comments += 1;
+                            *entry
+                                .comments_per_night
+                                .entry(comment.night.name.clone())
+                                .or_insert(0) += 1;
+
+                            rushees_met
+                                .entry(participation_key(&comment.brother_name))
+                                .or_default()
+                                .insert(doc.gtid.clone());
+
+                            ratings_given
+                                .entry(participation_key(&comment.brother_name))
+                                .or_default()
+                                .extend(comment.ratings.iter().map(|rating| rating.value));
+                        }
+
+                        for interviewer in doc.pis_signup.panel.iter() {
+                            let brother_name = full_name(&interviewer.first_name, &interviewer.last_name);
+
+                            participation_entry(&mut participation, &brother_name).pis_assignments += 1;
+                        }
+                    }
+
+                    Err(_err) => {
+                        return Ok(Json(json!({
+                            "status": "error",
+                            "message": "there was an error reading a rushee"
+                        }))
+                        .into_response())
+                    }
+                }
+            }
+        }
+
+        Err(_err) => {
+            return Ok(Json(json!({
+                "status": "error",
+                "message": "some network error occurred"
+            }))
+            .into_response())
+        }
+    }
+
+    // votes live in redis, keyed by "first last"
+    let mut redis = db::get_redis_conn().await.as_ref().clone();
+
+    let vote_counts: HashMap<String, i32> = redis.hgetall(VOTE_COUNTS).await.map_err(|e| {
+        println!("Redis HGETALL error: {}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+
+    for (brother_name, votes) in vote_counts {
+        participation_entry(&mut participation, &brother_name).votes_cast += votes;
+    }
+
+    let mut report: Vec<BrotherParticipation> = participation
+        .into_iter()
+        .map(|(key, mut entry)| {
+            entry.rushees_met = rushees_met
+                .get(&key)
+                .map(|met| met.len() as i32)
+                .unwrap_or(0);
+
+            entry.average_rating = match ratings_given.get(&key) {
+                Some(values) if !values.is_empty() => values.iter().sum::<f32>() / values.len() as f32,
+                _ => 0.0,
+            };
+
+            entry
+        })
+        .collect();
+
+    match params.sort_by.as_deref().unwrap_or("name") {
+        "comments" => report.sort_by_key(|entry| entry.total_comments),
+        "rushees_met" => report.sort_by_key(|entry| entry.rushees_met),
+        "average_rating" => report.sort_by(|a, b| a.average_rating.total_cmp(&b.average_rating)),
+        "pis" => report.sort_by_key(|entry| entry.pis_assignments),
+        "votes" => report.sort_by_key(|entry| entry.votes_cast),
+        _ => report.sort_by(|a, b| a.brother_name.cmp(&b.brother_name)),
+    }
+
+    if params.order.as_deref() == Some("desc") {
+        report.reverse();
+    }
+
+    if params.format.as_deref() == Some("csv") {
+        // one column per rush night, in the order the nights happen
+        let night_names: Vec<String> = match attendance::get_rush_nights().await {
+            Ok(mut nights) => {
+                nights.sort_by_key(|night| night.time);
+                nights.into_iter().map(|night| night.name).collect()
+            }
+            Err(_err) => Vec::new(),
+        };
+
+        let mut csv = String::from("brother_name,total_comments,rushees_met,average_rating,pis_assignments,votes_cast");
+
+        for night_name in night_names.iter() {
+            csv.push(',');
+            csv.push_str(&csv_field(night_name));
+        }
+
+        csv.push('\n');
+
+        for entry in report.iter() {
+            csv.push_str(&format!(
+                "{},{},{},{:.2},{},{}",
+                csv_field(&entry.brother_name),
+                entry.total_comments,
+                entry.rushees_met,
+                entry.average_rating,
+                entry.pis_assignments,
+                entry.votes_cast
+            ));
+
+            for night_name in night_names.iter() {
+                csv.push_str(&format!(
+                    ",{}",
+                    entry.comments_per_night.get(night_name).unwrap_or(&0)
+                ));
+            }
+
+            csv.push('\n');
+        }
+
+        return Ok(csv_response("brother_participation.csv", csv));
+    }
+
+    Ok(Json(json!({
+        "status": "success",
+        "payload": report
+    }))
+    .into_response())
+}
+
+/**
+ * Coverage report: every rushee below the comment, commenting brother or attendance thresholds,
+ * with suggestions for brothers who haven't met them yet.
+ * Brothers who were out commenting on the nights the rushee attended are suggested first.
+ * Thresholds come from the query (min_comments, min_brothers, min_nights) or
+ * COVERAGE_MIN_COMMENTS / COVERAGE_MIN_BROTHERS / COVERAGE_MIN_NIGHTS
+ */
+pub async fn get_coverage_report(
+    headers: HeaderMap,
+    Query(params): Query<CoverageParams>,
+) -> Result<Json<Value>, StatusCode> {
+    if !is_admin(&headers) {
+        return Err(StatusCode::UNAUTHORIZED);
+    }
+
+    let min_comments = coverage_threshold(params.min_comments, "COVERAGE_MIN_COMMENTS", DEFAULT_COVERAGE_MIN_COMMENTS);
+    let min_brothers = coverage_threshold(params.min_brothers, "COVERAGE_MIN_BROTHERS", DEFAULT_COVERAGE_MIN_BROTHERS);
+    let min_nights = coverage_threshold(params.min_nights, "COVERAGE_MIN_NIGHTS", DEFAULT_COVERAGE_MIN_NIGHTS);
+    let max_suggestions = params.max_suggestions.unwrap_or(DEFAULT_MAX_SUGGESTIONS);
+
+    let connection = db::get_rushee_client().await;
+    let mut rushees = Vec::<RusheeModel>::new();
+
+    match connection.find(doc! {}).await {
+        Ok(mut cursor) => {
+            while let Some(rushee) = cursor.next().await {
+                match rushee {
+                    Ok(doc) => rushees.push(doc),
+                    Err(_err) => {
+                        return Ok(Json(json!({
+                            "status": "error",
+                            "message": "there was an error reading a rushee"
+                        })))
+                    }
+                }
+            }
+        }
+
+        Err(_err) => {
+            return Ok(Json(json!({
+                "status": "error",
+                "message": "some network error occurred"
+            })))
+        }
+    }
+
+    // which nights each brother was out commenting, and how many comments they've left overall
+    let mut brother_nights = HashMap::<String, HashSet<String>>::new();
+    let mut brother_comment_counts = HashMap::<String, usize>::new();
+
+    for rushee in rushees.iter() {
+        for comment in rushee.comments.iter() {
+            brother_nights
+                .entry(comment.brother_name.clone())
+                .or_default()
+                .insert(night_key(&comment.night));
+
+            *brother_comment_counts
+                .entry(comment.brother_name.clone())
+                .or_insert(0) += 1;
+        }
+    }
+
+    let mut report = Vec::<RusheeCoverage>::new();
+
+    for rushee in rushees.iter() {
+        let commenters: HashSet<&String> = rushee
+            .comments
+            .iter()
+            .map(|comment| &comment.brother_name)
+            .collect();
+
+        let nights_attended: HashSet<String> = rushee
+            .attendance
+            .iter()
+            .map(|record| night_key(&record.night))
+            .collect();
+
+        let mut reasons = Vec::<String>::new();
+
+        if rushee.comments.len() < min_comments {
+            reasons.push(format!("only {} comments (minimum {})", rushee.comments.len(), min_comments));
+        }
+
+        if commenters.len() < min_brothers {
+            reasons.push(format!("only {} brothers have commented (minimum {})", commenters.len(), min_brothers));
+        }
+
+        if nights_attended.len() < min_nights {
+            reasons.push(format!("attended {} nights (minimum {})", nights_attended.len(), min_nights));
+        }
+
+        if reasons.is_empty() {
+            continue;
+        }
+
+        let mut candidates: Vec<(&String, usize, usize)> = brother_nights
+            .iter()
+            .filter(|(brother_name, _)| !commenters.contains(brother_name))
+            .map(|(brother_name, nights)| {
+                let shared_nights = nights.intersection(&nights_attended).count();
+                let total_comments = *brother_comment_counts.get(brother_name).unwrap_or(&0);
+                (brother_name, shared_nights, total_comments)
+            })
+            .collect();
+
+        candidates.sort_by(|a, b| b.1.cmp(&a.1).then(b.2.cmp(&a.2)).then(a.0.cmp(b.0)));
+
+        report.push(RusheeCoverage {
+            gtid: rushee.gtid.clone(),
+            name: format!("{} {}", rushee.first_name, rushee.last_name),
+            comments: rushee.comments.len(),
+            brothers: commenters.len(),
+            nights_attended: nights_attended.len(),
+            reasons: reasons,
+            suggested_brothers: candidates
+                .into_iter()
+                .take(max_suggestions)
+                .map(|(brother_name, _, _)| brother_name.clone())
+                .collect(),
+        });
+    }
+
+    // least covered rushees first
+    report.sort_by(|a, b| a.comments.cmp(&b.comments).then(a.brothers.cmp(&b.brothers)));
+
+    // the next rush night, so the report can be pulled up before it starts
+    let now = bson::DateTime::now();
+    let next_night = match attendance::get_rush_nights().await {
+        Ok(nights) => nights
+            .into_iter()
+            .filter(|night| night.time >= now)
+            .min_by_key(|night| night.time),
+        Err(_err) => None,
+    };
+
+    Ok(Json(json!({
+        "status": "success",
+        "payload": {
+            "thresholds": {
+                "min_comments": min_comments,
+                "min_brothers": min_brothers,
+                "min_nights": min_nights,
+            },
+            "next_night": next_night,
+            "rushees": report
+        }
+    })))
+}
+
+/**
+ * Attendance per rush night: totals, first-time vs returning attendees, class and major breakdowns
+ * and how many people came back from the night before
+ */
+pub async fn get_attendance_report(headers: HeaderMap) -> Result<Json<Value>, StatusCode> {
+    if !is_admin(&headers) {
+        return Err(StatusCode::UNAUTHORIZED);
+    }
+
+    let mut rush_nights;
+
+    match attendance::get_rush_nights().await {
+        Ok(x) => {
+            rush_nights = x;
+        }
+
+        Err(_err) => {
+            return Ok(Json(json!({
+                "status": "error",
+                "message": "couldn't fetch the rush nights"
+            })))
+        }
+    }
+
+    rush_nights.sort_by_key(|night| night.time);
+
+    let mut report: Vec<NightAttendance> = rush_nights
+        .iter()
+        .map(|night| NightAttendance {
+            night: night.clone(),
+            total: 0,
+            first_time: 0,
+            returning: 0,
+            by_class: BTreeMap::new(),
+            by_major: BTreeMap::new(),
+            retained_from_previous: 0,
+            retention_rate: 0.0,
+        })
+        .collect();
+
+    let connection = db::get_rushee_client().await;
+
+    match connection.find(doc! {}).await {
+        Ok(mut cursor) => {
+            while let Some(rushee) = cursor.next().await {
+                match rushee {
+                    Ok(doc) => {
+                        // positions of the nights this rushee attended, in night order
+                        let attended: Vec<usize> = rush_nights
+                            .iter()
+                            .enumerate()
+                            .filter(|(_, night)| {
+                                doc.attendance.iter().any(|record| {
+                                    attendance::match_night(&record.night, &rush_nights)
+                                        .map(|matched| matched.id == night.id)
+                                        .unwrap_or(false)
+                                })
+                            })
+                            .map(|(position, _)| position)
+                            .collect();
+
+                        for (nth, position) in attended.iter().cloned().enumerate() {
+                            let entry = &mut report[position];
+
+                            entry.total += 1;
+
+                            if nth == 0 {
+                                entry.first_time += 1;
+                            } else {
+                                entry.returning += 1;
+                            }
+
+                            if position > 0 && attended.contains(&(position - 1)) {
+                                entry.retained_from_previous += 1;
+                            }
+
+                            *entry.by_class.entry(doc.class.clone()).or_insert(0) += 1;
+                            *entry.by_major.entry(doc.major.clone()).or_insert(0) += 1;
+                        }
+                    }
+
+                    Err(_err) => {
+                        return Ok(Json(json!({
+                            "status": "error",
+                            "message": "there was an error reading a rushee"
+                        })))
+                    }
+                }
+            }
+        }
+
+        Err(_err) => {
+            return Ok(Json(json!({
+                "status": "error",
+                "message": "some network error occurred"
+            })))
+        }
+    }
+
+    for position in 1..report.len() {
+        let previous_total = report[position - 1].total;
+
+        if previous_total > 0 {
+            report[position].retention_rate =
+                report[position].retained_from_previous as f32 / previous_total as f32;
+        }
+    }
+
+    Ok(Json(json!({
+        "status": "success",
+        "payload": report
+    })))
+}
+
+/**
+ * RSVP vs attendance for a rush night: who said they'd come and did, who didn't show,
+ * who came anyway after declining or never answering, and who came without an invitation
+ */
+pub async fn get_invitation_report(
+    Path(night_id): Path<String>,
+    headers: HeaderMap,
+) -> Result<Json<Value>, StatusCode> {
+    if !is_admin(&headers) {
+        return Err(StatusCode::UNAUTHORIZED);
+    }
+
+    let night;
+
+    match attendance::get_rush_night(&night_id).await {
+        Ok(x) => {
+            night = x;
+        }
+
+        Err(err) => {
+            return Ok(Json(json!({
+                "status": "error",
+                "message": err.to_string()
+            })))
+        }
+    }
+
+    let invitation_list;
+
+    match invitations::get_invitations(&night_id).await {
+        Ok(x) => {
+            invitation_list = x;
+        }
+
+        Err(err) => {
+            return Ok(Json(json!({
+                "status": "error",
+                "message": err.to_string()
+            })))
+        }
+    }
+
+    let invited_gtids: Vec<String> = invitation_list.iter().map(|invitation| invitation.gtid.clone()).collect();
+
+    let filter = doc! {"$or": [
+        {"attendance._id": night.id},
+        {"gtid": {"$in": invited_gtids}},
+    ]};
+
+    // gtid -> (name, attended)
+    let mut rushees = HashMap::<String, (String, bool)>::new();
+
+    let connection = db::get_rushee_client().await;
+
+    match connection.find(filter).await {
+        Ok(mut cursor) => {
+            while let Some(rushee) = cursor.next().await {
+                match rushee {
+                    Ok(doc) => {
+                        let attended = doc.attendance.iter().any(|record| record.night.id == night.id);
+
+                        rushees.insert(
+                            doc.gtid.clone(),
+                            (format!("{} {}", doc.first_name, doc.last_name), attended),
+                        );
+                    }
+
+                    Err(_err) => {
+                        return Ok(Json(json!({
+                            "status": "error",
+                            "message": "there was an error reading a rushee"
+                        })))
+                    }
+                }
+            }
+        }
+
+        Err(_err) => {
+            return Ok(Json(json!({
+                "status": "error",
+                "message": "some network error occurred"
+            })))
+        }
+    }
+
+    let mut counts = RsvpCounts::default();
+    let mut report_rows = Vec::<InviteeAttendance>::new();
+
+    for invitation in invitation_list.iter() {
+        let (name, attended) = rushees
+            .remove(&invitation.gtid)
+            .unwrap_or((String::new(), false));
+
+        counts.invited += 1;
+
+        match (&invitation.rsvp, attended) {
+            (RsvpStatus::Attending, true) => {
+                counts.attending += 1;
+                counts.attending_and_came += 1;
+            }
+            (RsvpStatus::Attending, false) => {
+                counts.attending += 1;
+                counts.attending_no_show += 1;
+            }
+            (RsvpStatus::Declined, came) => {
+                counts.declined += 1;
+                counts.declined_but_came += came as usize;
+            }
+            (RsvpStatus::Pending, came) => {
+                counts.pending += 1;
+                counts.pending_and_came += came as usize;
+            }
+        }
+
+        report_rows.push(InviteeAttendance {
+            gtid: invitation.gtid.clone(),
+            name: name,
+            rsvp: Some(invitation.rsvp.clone()),
+            attended: attended,
+        });
+    }
+
+    // whoever is left attended without being invited
+    for (gtid, (name, _attended)) in rushees.into_iter() {
+        counts.uninvited_and_came += 1;
+
+        report_rows.push(InviteeAttendance {
+            gtid: gtid,
+            name: name,
+            rsvp: None,
+            attended: true,
+        });
+    }
+
+    report_rows.sort_by(|a, b| a.name.cmp(&b.name));
+
+    Ok(Json(json!({
+        "status": "success",
+        "payload": InvitationReconciliation {
+            night: night,
+            counts: counts,
+            rushees: report_rows,
+        }
+    })))
+}
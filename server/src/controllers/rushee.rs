@@ -1,11 +1,12 @@
 use axum::extract::Query;
 use axum::{
     extract::Path,
-    http::StatusCode,
-    response::{IntoResponse, Json},
+    http::{HeaderMap, StatusCode},
+    response::{IntoResponse, Json, Response},
     routing::{get, post},
     Router,
 };
+use bson::oid::ObjectId;
 use bson::DateTime;
 use futures::stream::StreamExt;
 use lambda_http::{run, Error};
@@ -16,17 +17,31 @@ use mongodb::{
 use rand::{distributions::Alphanumeric, Rng};
 use serde::{Deserialize, Serialize};
 use serde_json::{json, Value};
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
 use std::env::set_var;
 
 use super::db;
-use crate::middlewares::timeHelpers::same_day;
-use crate::middlewares::valid::check_valid_comment;
-use crate::middlewares::{attendance, pis, timeHelpers, valid};
-use crate::models::misc::RushNight;
-use crate::models::pis::PISSignup;
+use crate::middlewares::auth::{get_role, Role};
+use crate::middlewares::checkin::DOOR_TOKEN;
+use crate::middlewares::face;
+use crate::middlewares::questions::AnswerSaveError;
+use crate::middlewares::moderation::moderate;
+use crate::middlewares::rushee::{anonymize_comments, fetch_rushee, get_pseudonyms};
+use crate::middlewares::valid::{
+    check_valid_comment, check_valid_reply_delete, check_valid_reply_edit, comment_match, find_comment,
+    find_reply, is_comment_on_night,
+};
+use crate::middlewares::{
+    assignment,
+    attendance::{self, CheckinOutcome},
+    flex, locations, notifications, pis, questions, scorecards, timeHelpers, valid, waitlist,
+};
+use crate::models::misc::{AttendanceRecord, CheckinSource, IncomingCheckin};
+use crate::models::pis::{IncomingReschedule, PISReschedule, PISSignup};
 use crate::models::Rushee::{
-    Comment, IncomingComment, IncomingRushee, PisResponse, Rating, RusheeEdit, RusheeModel,
+    Comment, CommentReaction, CommentReply, IncomingComment, IncomingFaceCheckin,
+    IncomingFaceEmbedding, IncomingReaction, IncomingReply, IncomingReplyDelete, IncomingReplyEdit,
+    IncomingPISAnswer, IncomingPISAnswers, IncomingRushee, ModerationStatus, PisResponse, Rating, ReactionOption, RusheeEdit, RusheeModel,
     StrippedRushee,
 };
 
@@ -43,7 +58,39 @@ pub async fn signup(Json(payload): Json<IncomingRushee>) -> Result<Json<Value>,
     let collection: Collection<RusheeModel> = db::get_rushee_client().await;
 
     // convert incoming timeslot to a bson DateTime type
-    let date_converstion = timeHelpers::string_to_bson_datetime(&payload.pis_timeslot.to_string());
+    // flex-window rushees don't book one, an admin places them later (see middlewares::flex)
+    let date_converstion;
+    let mut flex_times = Vec::new();
+
+    if payload.flex_window {
+        date_converstion = flex::UNSCHEDULED;
+
+        match assignment::parse_times(&payload.flex_times) {
+            Ok(x) => {
+                flex_times = x;
+            }
+
+            Err(err) => {
+                return Ok(Json(json!({
+                    "status": "error",
+                    "message": err.to_string()
+                })))
+            }
+        }
+    } else {
+        match timeHelpers::string_to_bson_datetime(&payload.pis_timeslot) {
+            Ok(x) => {
+                date_converstion = x;
+            }
+
+            Err(err) => {
+                return Ok(Json(json!({
+                    "status": "error",
+                    "message": err.to_string()
+                })))
+            }
+        }
+    }
 
     // TODO: verify all fields
 
@@ -74,19 +121,26 @@ pub async fn signup(Json(payload): Json<IncomingRushee>) -> Result<Json<Value>,
         }
     }
 
-    // take PIS timeslot
-    let take_timeslot_result = pis::take_pis_timeslot(date_converstion).await;
-
-    match take_timeslot_result {
-        Ok(_x) => {
-            // do nothing
+    if !payload.flex_window {
+        // seats held for waitlisted rushees that have run out can be booked again
+        if let Err(err) = waitlist::expire_holds().await {
+            println!("Couldn't expire PIS holds: {}", err);
         }
 
-        Err(err) => {
-            return Ok(Json(json!({
-                "status": "error",
-                "message": err.to_string()
-            })))
+        // take PIS timeslot
+        let take_timeslot_result = pis::take_pis_timeslot(date_converstion).await;
+
+        match take_timeslot_result {
+            Ok(_x) => {
+                // do nothing
+            }
+
+            Err(err) => {
+                return Ok(Json(json!({
+                    "status": "error",
+                    "message": err.to_string()
+                })))
+            }
         }
     }
 
@@ -108,13 +162,14 @@ pub async fn signup(Json(payload): Json<IncomingRushee>) -> Result<Json<Value>,
         pronouns: payload.pronouns.to_string(),
         image_url: payload.image_url.to_string(),
         exposure: payload.exposure.to_string(),
-        pis_meeting_id: payload.pis_meeting_id.to_string(),
+        // filled in when the PIS is given a location, see middlewares::locations
+        pis_meeting_id: String::new(),
         pis_timeslot: date_converstion,
-        pis_link: payload.pis_link.to_string(),
+        pis_link: String::new(),
         cloud: "none".to_string(),
         pis: Vec::<PisResponse>::new(),
         comments: Vec::<Comment>::new(),
-        attendance: Vec::<RushNight>::new(),
+        attendance: Vec::<AttendanceRecord>::new(),
         ratings: Vec::<Rating>::new(),
         access_code: access_code.clone(),
         pis_signup: PISSignup {
@@ -122,24 +177,65 @@ pub async fn signup(Json(payload): Json<IncomingRushee>) -> Result<Json<Value>,
             rushee_first_name: payload.first_name.to_string(),
             rushee_last_name: payload.last_name.to_string(),
             rushee_gtid: payload.gtid.to_string(),
-            first_brother_first_name: "none".to_string(),
-            first_brother_last_name: "none".to_string(),
-            second_brother_first_name: "none".to_string(),
-            second_brother_last_name: "none".to_string(),
-            third_brother_first_name: "none".to_string(),
-            third_brother_last_name: "none".to_string(),
+            panel: Vec::new(),
             flex_window: payload.flex_window,
         },
         flex_window: payload.flex_window,
+        pis_history: Vec::new(),
+        pis_status: None,
+        pis_status_history: Vec::new(),
+        pis_location: None,
+        pis_revision: 0,
+        pis_scorecards: Vec::new(),
+        pis_summary: None,
     };
 
     let result = collection.insert_one(new_rushee).await;
 
     match result {
         Ok(_insert_result) => {
+            // a flex-window rushee that isn't in the queue could never be placed, so take the signup back
+            if payload.flex_window {
+                let rushee_name = format!("{} {}", payload.first_name, payload.last_name);
+
+                if let Err(err) = flex::queue_flex_rushee(&payload.gtid, &rushee_name, flex_times).await {
+                    println!("{}", err.to_string());
+
+                    if let Err(_err) = collection.delete_one(doc! {"gtid": payload.gtid.clone()}).await {
+                        println!("Couldn't undo the signup for {}", payload.gtid);
+                    }
+
+                    return Ok(Json(json!({
+                        "status": "error",
+                        "message": "couldn't add you to the flex-window queue, please try signing up again"
+                    })));
+                }
+            }
+
+            let mut face_registered = false;
+
+            if let Some(embedding) = payload.face_embedding.as_ref() {
+                match face::store_face_embedding(&payload.gtid, embedding).await {
+                    Ok(_x) => {
+                        face_registered = true;
+                    }
+                    Err(err) => {
+                        println!("{}", err.to_string());
+                    }
+                }
+            }
+
+            if !payload.flex_window {
+                if let Err(err) = locations::allocate_location(&payload.gtid).await {
+                    println!("Couldn't give the PIS a location: {}", err);
+                }
+            }
+
             return Ok(Json(json!({
                 "status": "success",
                 "payload": access_code,
+                "face_registered": face_registered,
+                "flex_queued": payload.flex_window,
             })))
         }
 
@@ -152,18 +248,36 @@ pub async fn signup(Json(payload): Json<IncomingRushee>) -> Result<Json<Value>,
     }
 }
 
+#[derive(Deserialize)]
+pub struct RusheeListParams {
+    pis_status: Option<String>, // completed, no-show, cancelled or pending
+}
+
 /**
  * gets all rushees in the following form: {"id", "name", "picture", "ratings" ...} (only the info needed for the homepage)
- * filters are passed in through the header
+ * filters are passed in through the query
  */
-pub async fn get_rushees() -> Result<Json<Value>, StatusCode> {
+pub async fn get_rushees(Query(params): Query<RusheeListParams>) -> Result<Json<Value>, StatusCode> {
     let collection: Collection<RusheeModel> = db::get_rushee_client().await;
 
-    let result = collection
-        .find({
-            doc! {}
-        })
-        .await;
+    let mut filter = doc! {};
+
+    if let Some(pis_status) = &params.pis_status {
+        match pis::pis_status_filter(pis_status) {
+            Ok(x) => {
+                filter = x;
+            }
+
+            Err(err) => {
+                return Ok(Json(json!({
+                    "status": "error",
+                    "message": err.to_string()
+                })))
+            }
+        }
+    }
+
+    let result = collection.find(filter).await;
 
     match result {
         Ok(mut cursor) => {
@@ -182,6 +296,7 @@ pub async fn get_rushees() -> Result<Json<Value>, StatusCode> {
                         email: doc.email,
                         pronouns: doc.pronouns,
                         attendance: doc.attendance,
+                        pis_status: doc.pis_status,
                     }),
                     Err(err) => {
                         println!("{}", err.to_string());
@@ -206,20 +321,68 @@ pub async fn get_rushees() -> Result<Json<Value>, StatusCode> {
     }
 }
 
+#[derive(Deserialize)]
+pub struct RusheeReadParams {
+    anonymous: Option<bool>,
+}
+
 // returns comments, ratings, etc..
-pub async fn get_rushee(Path(id): Path<String>) -> Result<Json<Value>, StatusCode> {
+// bid committee always gets the blind view (brothers replaced by pseudonyms), anyone else can ask for it with ?anonymous=true
+pub async fn get_rushee(
+    Path(id): Path<String>,
+    Query(params): Query<RusheeReadParams>,
+    headers: HeaderMap,
+) -> Result<Json<Value>, StatusCode> {
     let connection = db::get_rushee_client().await;
 
     let result = connection.find_one(doc! {"gtid": id.clone()}).await;
 
+    let role = get_role(&headers);
+    let blind_review = role == Role::BidCommittee || params.anonymous.unwrap_or(false);
+
     match result {
         Ok(insert_result) => match insert_result {
-            Some(rushee) => Ok(Json(
-                (json!({
+            Some(mut rushee) => {
+                // pseudonyms come from every comment, hidden ones included, so they match de-anonymize
+                let mut pseudonyms = HashMap::new();
+
+                if blind_review {
+                    match get_pseudonyms(&rushee.gtid, &rushee.comments).await {
+                        Ok(x) => {
+                            pseudonyms = x;
+                        }
+
+                        Err(err) => {
+                            return Ok(Json(json!({
+                                "status": "error",
+                                "message": err.message
+                            })))
+                        }
+                    }
+                }
+
+                // hidden comments are only visible to admins (their ratings still count)
+                if role != Role::Admin {
+                    rushee.comments.retain(|comment| comment.moderation != ModerationStatus::Hidden);
+                }
+
+                // scorecards are only for admins and bid committee, same as /admin/pis-scorecards
+                if role == Role::Brother {
+                    rushee.pis_scorecards = Vec::new();
+                    rushee.pis_summary = None;
+                }
+
+                if blind_review {
+                    anonymize_comments(&mut rushee.comments, &pseudonyms);
+                    scorecards::anonymize_scorecards(&mut rushee.pis_scorecards);
+                    pis::anonymize_pis_status(&mut rushee.pis_status, &mut rushee.pis_status_history);
+                }
+
+                Ok(Json(json!({
                     "status": "success",
                     "payload": rushee
-                })),
-            )),
+                })))
+            }
 
             None => Ok(Json(json!({
                 "status": "error",
@@ -236,379 +399,378 @@ pub async fn get_rushee(Path(id): Path<String>) -> Result<Json<Value>, StatusCod
 
 /**
  * Post a new comment to some rushee
- * Uses timestamp to record date
+ * Attaches the comment to tonight's rush night unless the brother picks a past night (within the grace window)
  */
 pub async fn post_comment(
     Path(id): Path<String>,
     Json(payload): Json<IncomingComment>,
 ) -> Result<Json<Value>, StatusCode> {
     let connection = db::get_rushee_client().await;
-    let fetch_rush_nights = attendance::get_rush_nights().await;
+    let resolve_night = attendance::resolve_comment_night(payload.night_id.clone()).await;
 
-    match fetch_rush_nights {
-        Ok(rush_nights) => {
-            for rush_night in rush_nights.iter() {
-                if true {//same_day(&rush_night.time, &bson::DateTime::now()) {
-                    // found rush night
-                    let attempt_bson_night = to_bson(&rush_night);
-                    let mut bson_night;
+    let my_rush_night;
 
-                    match attempt_bson_night {
-                        Ok(x) => {
-                            bson_night = x;
-                        }
+    match resolve_night {
+        Ok(night) => {
+            my_rush_night = night;
+        }
 
-                        Err(_err) => {
-                            return Ok(Json(json!({
-                                "status": "error",
-                                "message": "some issue occurred when serializing the rush night"
-                            })))
-                        }
-                    }
+        Err(err) => {
+            return Ok(Json(json!({
+                "status": "error",
+                "message": err.to_string()
+            })))
+        }
+    }
 
-                    let my_rush_night = RushNight {
-                        name: rush_night.name.clone(),
-                        time: rush_night.time,
-                    };
+    // flagged comments still get posted, they just show up in the admin moderation queue
+    let flags = moderate(&payload.comment);
+    let moderation_status = if flags.is_empty() {
+        ModerationStatus::Clear
+    } else {
+        ModerationStatus::Flagged
+    };
 
-                    let new_comment = Comment {
-                        brother_id: payload.brother_id.clone(),
-                        brother_name: payload.brother_name.clone(),
-                        comment: payload.comment.clone(),
-                        ratings: payload.ratings.clone(),
-                        night: my_rush_night.clone(),
-                    };
+    let new_comment = Comment {
+        brother_id: payload.brother_id.clone(),
+        brother_name: payload.brother_name.clone(),
+        comment: payload.comment.clone(),
+        ratings: payload.ratings.clone(),
+        night: my_rush_night.clone(),
+        night_id: my_rush_night.id.map(|oid| oid.to_hex()).unwrap_or_default(),
+        replies: Vec::<CommentReply>::new(),
+        reactions: Vec::<CommentReaction>::new(),
+        flags: flags.clone(),
+        moderation: moderation_status,
+    };
 
-                    // fetch the rushee
-                    let get_rushee_result = connection.find_one(doc! {"gtid": id.clone()}).await;
-
-                    match get_rushee_result {
-                        Ok(rushee_option) => {
-                            let mut rushee;
-
-                            match rushee_option {
-                                Some(x) => {
-                                    rushee = x;
-                                }
-                                None => {
-                                    return Ok(Json(json!({
-                                        "status": "error",
-                                        "message": "some error occurred"
-                                    })))
-                                }
-                            }
+    // fetch the rushee
+    let get_rushee_result = connection.find_one(doc! {"gtid": id.clone()}).await;
 
-                            // check if brother has already made a comment
-                            let is_valid = check_valid_comment(
-                                &payload.brother_name,
-                                &my_rush_night,
-                                &rushee.comments,
-                            )
-                            .await;
-
-                            match is_valid {
-                                Ok(_result) => {
-                                    // do nothing
-                                }
-
-                                Err(_err) => {
-                                    return Ok(Json(json!({
-                                        "status": "error",
-                                        "message": "you have already made a comment for this rush night"
-                                    })))
-                                }
-                            }
+    match get_rushee_result {
+        Ok(rushee_option) => {
+            let rushee;
 
-                            // update ratings
-                            for rating in payload.ratings.iter() {
-                                // Collect all ratings for this category from all comments
-                                let mut values = Vec::new();
-
-                                // Existing comments
-                                for comment in &rushee.comments {
-                                    if let Some(existing_rating) = comment.ratings.iter().find(|r| r.name == rating.name) {
-                                        if existing_rating.value == 0.0 || existing_rating.value == 5.0 {
-                                            values.push(existing_rating.value);
-                                        }
-                                    }
-                                }
-
-                                // Add the new rating (from the current payload)
-                                if rating.value == 0.0 || rating.value == 5.0 {
-                                    values.push(rating.value);
-                                }
-
-                                // Calculate the average (out of 5)
-                                let new_value = if !values.is_empty() {
-                                    values.iter().sum::<f32>() / values.len() as f32
-                                } else {
-                                    0.0
-                                };
-
-                                let search_rating = rushee
-                                    .ratings
-                                    .iter()
-                                    .find(|r: &&Rating| rating.name == r.name);
-
-                                match search_rating {
-                                    Some(_y) => {
-                                        // Update existing rating
-                                        let filter = doc! {
-                                            "gtid": id.clone(),
-                                            "ratings.name": rating.name.clone(),
-                                        };
-                                        let update = doc! {
-                                            "$set": {
-                                                "ratings.$.value": new_value,
-                                            },
-                                        };
-                                        let update_result_try = connection.update_one(filter, update).await;
-
-                                        match update_result_try {
-                                            Ok(_update_result) => {
-                                                // do nothing
-                                            }
-                                            Err(_err) => {
-                                                return Ok(Json(json!({
-                                                    "status": "error",
-                                                    "message": "there was an error updating the rushee's global ratings"
-                                                })))
-                                            }
-                                        }
-                                    }
-                                    None => {
-                                        // **This is the important part for an empty array or new category**
-                                        let filter = doc! {"gtid": id.clone()};
-                                        let update = doc! {"$push": {"ratings": {"name": rating.name.clone(), "value": new_value}}};
-                                        let update_result_try = connection.update_one(filter, update).await;
-
-                                        match update_result_try {
-                                            Ok(_update_result) => {
-                                                // do nothing
-                                            }
-                                            Err(_err) => {
-                                                return Ok(Json(json!({
-                                                    "status": "error",
-                                                    "message": "there was an error updating the rushee's global ratings"
-                                                })))
-                                            }
-                                        }
-                                    }
-                                }
-                            }
+            match rushee_option {
+                Some(x) => {
+                    rushee = x;
+                }
+                None => {
+                    return Ok(Json(json!({
+                        "status": "error",
+                        "message": "some error occurred"
+                    })))
+                }
+            }
 
-                            let mut bson_comment;
-                            let mut bson_comment_try = to_bson(&new_comment);
-
-                            match bson_comment_try {
-                                Ok(x) => {
-                                    bson_comment = x;
-                                }
-                                Err(err) => {
-                                    return Ok(Json(json!({
-                                        "status": "error",
-                                        "message": "some error occurred"
-                                    })))
-                                }
-                            }
+            // check if brother has already made a comment
+            let is_valid = check_valid_comment(
+                &payload.brother_name,
+                &my_rush_night,
+                &rushee.comments,
+            )
+            .await;
 
-                            let filter = doc! {"gtid": id};
-                            let update = doc! {"$push": {
-                                "comments": bson_comment,
-                            }};
-
-                            let result = connection.update_one(filter, update).await;
-
-                            match result {
-                                Ok(_update_result) => {
-                                    return Ok(Json(json!({
-                                        "status": "success",
-                                        "message": "successfully updated rushee"
-                                    })))
-                                }
-
-                                Err(_err) => {
-                                    return Ok(Json(json!({
-                                        "status": "error",
-                                        "message": "something wrong occurred"
-                                    })))
-                                }
-                            }
+            match is_valid {
+                Ok(_result) => {
+                    // do nothing
+                }
+
+                Err(_err) => {
+                    return Ok(Json(json!({
+                        "status": "error",
+                        "message": "you have already made a comment for this rush night"
+                    })))
+                }
+            }
+
+            // update ratings
+            for rating in payload.ratings.iter() {
+                // Collect all ratings for this category from all comments
+                let mut values = Vec::new();
+
+                // Existing comments
+                for comment in &rushee.comments {
+                    if let Some(existing_rating) = comment.ratings.iter().find(|r| r.name == rating.name) {
+                        if existing_rating.value == 0.0 || existing_rating.value == 5.0 {
+                            values.push(existing_rating.value);
                         }
+                    }
+                }
 
-                        Err(_err) => {
-                            return Ok(Json(json!({
-                                "status": "error",
-                                "message": "something wrong occurred"
-                            })))
+                // Add the new rating (from the current payload)
+                if rating.value == 0.0 || rating.value == 5.0 {
+                    values.push(rating.value);
+                }
+
+                // Calculate the average (out of 5)
+                let new_value = if !values.is_empty() {
+                    values.iter().sum::<f32>() / values.len() as f32
+                } else {
+                    0.0
+                };
+
+                let search_rating = rushee
+                    .ratings
+                    .iter()
+                    .find(|r: &&Rating| rating.name == r.name);
+
+                match search_rating {
+                    Some(_y) => {
+                        // Update existing rating
+                        let filter = doc! {
+                            "gtid": id.clone(),
+                            "ratings.name": rating.name.clone(),
+                        };
+                        let update = doc! {
+                            "$set": {
+                                "ratings.$.value": new_value,
+                            },
+                        };
+                        let update_result_try = connection.update_one(filter, update).await;
+
+                        match update_result_try {
+                            Ok(_update_result) => {
+                                // do nothing
+                            }
+                            Err(_err) => {
+                                return Ok(Json(json!({
+                                    "status": "error",
+                                    "message": "there was an error updating the rushee's global ratings"
+                                })))
+                            }
+                        }
+                    }
+                    None => {
+                        // **This is the important part for an empty array or new category**
+                        let filter = doc! {"gtid": id.clone()};
+                        let update = doc! {"$push": {"ratings": {"name": rating.name.clone(), "value": new_value}}};
+                        let update_result_try = connection.update_one(filter, update).await;
+
+                        match update_result_try {
+                            Ok(_update_result) => {
+                                // do nothing
+                            }
+                            Err(_err) => {
+                                return Ok(Json(json!({
+                                    "status": "error",
+                                    "message": "there was an error updating the rushee's global ratings"
+                                })))
+                            }
                         }
                     }
                 }
             }
 
-            return Ok(Json(json!({
-                "status": "error",
-                "message": "couldn't match a rush night"
-            })));
+            let bson_comment;
+            let bson_comment_try = to_bson(&new_comment);
+
+            match bson_comment_try {
+                Ok(x) => {
+                    bson_comment = x;
+                }
+                Err(_err) => {
+                    return Ok(Json(json!({
+                        "status": "error",
+                        "message": "some error occurred"
+                    })))
+                }
+            }
+
+            let filter = doc! {"gtid": id};
+            let update = doc! {"$push": {
+                "comments": bson_comment,
+            }};
+
+            let result = connection.update_one(filter, update).await;
+
+            match result {
+                Ok(_update_result) => {
+                    return Ok(Json(json!({
+                        "status": "success",
+                        "message": "successfully updated rushee",
+                        "flags": flags
+                    })))
+                }
+
+                Err(_err) => {
+                    return Ok(Json(json!({
+                        "status": "error",
+                        "message": "something wrong occurred"
+                    })))
+                }
+            }
         }
 
         Err(_err) => {
             return Ok(Json(json!({
                 "status": "error",
-                "message": "there was some error while matching the rush night"
+                "message": "something wrong occurred"
             })))
         }
     }
 }
 
+fn answer_save_response(result: Result<Vec<PisResponse>, AnswerSaveError>) -> Response {
+    match result {
+        Ok(responses) => Json(json!({
+            "status": "success",
+            "message": "succesfully stored rushee's pis",
+            "payload": responses
+        }))
+        .into_response(),
+
+        Err(err) if err.code == "PIS_CONFLICT" => (
+            StatusCode::CONFLICT,
+            Json(json!({
+                "status": "error",
+                "message": err.message,
+                "payload": err.current
+            })),
+        )
+            .into_response(),
+
+        Err(err) => Json(json!({
+            "status": "error",
+            "message": err.message
+        }))
+        .into_response(),
+    }
+}
+
 /**
- * Post a Rushee's PIS
+ * Post a Rushee's PIS, every required question has to be answered.
+ * Saved in one write on top of whatever else has been saved, answers that carry a revision get a 409 if
+ * someone else changed them first
  */
 pub async fn post_pis(
     Path(id): Path<String>,
-    Json(payload): Json<Vec<PisResponse>>,
-) -> Result<Json<Value>, StatusCode> {
-    let connection = db::get_rushee_client().await;
-
-    let mut filter;
-    let mut update;
-
-    // null out current entries
-    filter = doc! {"gtid": id.clone()};
-    update = doc! {"$set": doc! { "pis" : [] }};
+    Json(payload): Json<Vec<IncomingPISAnswer>>,
+) -> Result<Response, StatusCode> {
+    Ok(answer_save_response(questions::save_answers(&id, &payload, &None, true).await))
+}
 
-    let clear_out_result = connection.update_one(filter, update).await;
+/**
+ * Updates some of a rushee's PIS answers, leaving the others alone. Each answer says which revision it
+ * started from, and the save is rejected with a 409 (and the current answers) if any of them changed since
+ */
+pub async fn update_pis_answers(
+    Path(id): Path<String>,
+    Json(payload): Json<IncomingPISAnswers>,
+) -> Result<Response, StatusCode> {
+    if payload.answers.is_empty() {
+        return Ok(Json(json!({
+            "status": "error",
+            "message": "no answers to save"
+        }))
+        .into_response());
+    }
 
-    match clear_out_result {
+    Ok(answer_save_response(
+        questions::save_answers(&id, &payload.answers, &payload.updated_by, false).await,
+    ))
+}
 
-        Ok(_clear_out) => {
+/**
+ * Stamps attendance for tonight's rush night, at most once per night
+ * Needs a valid check-in token for tonight: a rushee token along with the rushee's access code,
+ * or a door token from a brother checking the rushee in
+ */
+pub async fn update_attendance(
+    Path(id): Path<String>,
+    Json(payload): Json<IncomingCheckin>,
+) -> Result<Json<Value>, StatusCode> {
+    let token;
+    let current_night;
 
+    match attendance::verify_token_for_tonight(&payload.token).await {
+        Ok((x, y)) => {
+            token = x;
+            current_night = y;
         }
 
-        Err(_err) => {
+        Err(err) => {
             return Ok(Json(json!({
-                "status": "success",
-                "message": "There was an error clearing out the current PIS responses"
+                "status": "error",
+                "message": err.to_string()
             })))
         }
-
     }
 
-    let mut pis_bson_try;
-    let mut pis_bson;
+    let rushee;
 
-    for response in payload.iter() {
-        // push the pis response
-        filter = doc! {"gtid": id.clone()};
-
-        pis_bson_try = to_bson(&response);
+    match fetch_rushee(id.clone()).await {
+        Ok(x) => {
+            rushee = x;
+        }
 
-        match pis_bson_try {
-            Ok(x) => {
-                pis_bson = x;
-            }
-            Err(err) => {
-                return Ok(Json(json!({
-                    "status": "error",
-                    "message": "couldn't make the pis response into a bson file"
-                })))
-            }
+        Err(err) => {
+            return Ok(Json(json!({
+                "status": "error",
+                "message": err.message
+            })))
         }
+    }
 
-        update = doc! {
-            "$push" : {
-                "pis": pis_bson,
-            }
-        };
+    let source;
+    let checked_in_by;
 
-        let result = connection.update_one(filter, update).await;
+    if token.purpose == DOOR_TOKEN {
+        let brother_name = payload.brother_name.unwrap_or_default();
 
-        match result {
-            Ok(update_result) => {
-                // do nothing
-            }
+        if brother_name.trim().is_empty() {
+            return Ok(Json(json!({
+                "status": "error",
+                "message": "brother name is required to check a rushee in"
+            })));
+        }
 
-            Err(err) => {
+        match payload.source.as_deref().unwrap_or("brother") {
+            "brother" => source = CheckinSource::Brother,
+            "kiosk" => source = CheckinSource::Kiosk,
+            "face" => source = CheckinSource::Face,
+            other => {
                 return Ok(Json(json!({
                     "status": "error",
-                    "message": "failed to push a pis response"
+                    "message": format!("Invalid check-in source: {}", other)
                 })))
             }
         }
+
+        checked_in_by = Some(brother_name);
+    } else if payload.access_code.as_deref() == Some(rushee.access_code.as_str()) {
+        source = CheckinSource::SelfCheckin;
+        checked_in_by = None;
+    } else {
+        return Ok(Json(json!({
+            "status": "error",
+            "message": "invalid access code"
+        })));
     }
 
-    Ok(Json(json!({
-        "status": "success",
-        "message": "succesfully stored rushee's pis"
-    })))
-}
-
-/**
- * Uses current time to stamp attendance
- */
-pub async fn update_attendance(Path(id): Path<String>) -> Result<Json<Value>, StatusCode> {
-    let fetch_rush_nights = attendance::get_rush_nights().await;
-    let connection = db::get_rushee_client().await;
-
-    match fetch_rush_nights {
-        Ok(rush_nights) => {
-            for candidate_night in rush_nights.iter() {
-                if same_day(&candidate_night.time, &bson::DateTime::now()) {
-                    // found rush night
-
-                    let attempt_bson_night = to_bson(&candidate_night);
-                    let mut bson_night;
-
-                    match attempt_bson_night {
-                        Ok(x) => {
-                            bson_night = x;
-                        }
-
-                        Err(_err) => {
-                            return Ok(Json(json!({
-                                "status": "error",
-                                "message": "some issue occurred when serializing the rush night"
-                            })))
-                        }
-                    }
-
-                    let filter = doc! {"gtid": id.clone()};
-                    let update = doc! {"$push": {
-                        "attendance": bson_night,
-                    }};
+    let result = attendance::record_attendance(&id, &current_night, source, checked_in_by).await;
 
-                    let result = connection.update_one(filter, update).await;
-
-                    match result {
-                        Ok(_update_result) => {
-                            return Ok(Json(json!({
-                                "status": "success",
-                                "message": "updated rushee attendance"
-                            })))
-                        }
+    match result {
+        Ok(CheckinOutcome::CheckedIn) => Ok(Json(json!({
+            "status": "success",
+            "message": "updated rushee attendance"
+        }))),
 
-                        Err(_err) => {
-                            return Ok(Json(json!({
-                                "status": "error",
-                                "message": "couldn't update rushee attendance"
-                            })))
-                        }
-                    }
-                }
-            }
+        Ok(CheckinOutcome::CheckedInUninvited) => Ok(Json(json!({
+            "status": "success",
+            "message": "updated rushee attendance, but this rushee isn't on tonight's invitation list",
+            "payload": {"uninvited": true}
+        }))),
 
-            return Ok(Json(json!({
-                "status": "error",
-                "message": "rush night does not exist"
-            })));
-        }
+        Ok(CheckinOutcome::AlreadyCheckedIn) => Ok(Json(json!({
+            "status": "success",
+            "message": "rushee is already checked in for tonight"
+        }))),
 
-        Err(err) => {
-            return Ok(Json(json!({
-                "status": "error",
-                "message": "some error occurred"
-            })))
-        }
+        Err(err) => Ok(Json(json!({
+            "status": "error",
+            "message": err.to_string()
+        }))),
     }
 }
 
@@ -709,21 +871,18 @@ pub async fn update_rushee(
 }
 
 /**
- * Reschdule Rushee PIS
- * accepts a string in the BSON DateTime format
+ * Reschedule a rushee's PIS. The old timeslot is given back, the new one is taken,
+ * the brothers on the panel are told and the move is kept in the rushee's PIS history
  */
 pub async fn reschedule_pis(
     Path(id): Path<String>,
-    Json(payload): Json<String>,
+    Json(payload): Json<IncomingReschedule>,
 ) -> Result<Json<Value>, StatusCode> {
-    let time = timeHelpers::string_to_bson_datetime(&payload);
-    let connection = db::get_rushee_client().await;
-
-    let vacate_result = pis::vacate_pis_timeslot(time).await;
+    let time;
 
-    match vacate_result {
-        Ok(_x) => {
-            // do nothing
+    match timeHelpers::string_to_bson_datetime(&payload.time) {
+        Ok(x) => {
+            time = x;
         }
 
         Err(err) => {
@@ -734,41 +893,80 @@ pub async fn reschedule_pis(
         }
     }
 
-    let take_result = pis::take_pis_timeslot(time).await;
+    let rushee;
 
-    match take_result {
-        Ok(_y) => {
-            // do nothing
+    match fetch_rushee(id.clone()).await {
+        Ok(x) => {
+            rushee = x;
         }
 
         Err(err) => {
             return Ok(Json(json!({
                 "status": "error",
-                "message": err.to_string()
+                "message": err.message
             })))
         }
     }
 
-    let query = doc! {"gtid": "id"};
-    let update = doc! {"$set": doc! {"pis_timeslot": time }};
+    if rushee.pis_timeslot == flex::UNSCHEDULED {
+        return Ok(Json(json!({
+            "status": "error",
+            "message": "this rushee is in the flex-window queue and hasn't been given a PIS time yet"
+        })));
+    }
 
-    let update_result = connection.update_one(query, update).await;
+    if rushee.pis_timeslot == time {
+        return Ok(Json(json!({
+            "status": "error",
+            "message": "the PIS is already at this time"
+        })));
+    }
 
-    match update_result {
-        Ok(_z) => {
-            return Ok(Json(json!({
-                "status": "success",
-                "message": "successfully rescheduled pis"
-            })))
-        }
+    let entry = PISReschedule {
+        from: rushee.pis_timeslot,
+        to: time,
+        rescheduled_at: bson::DateTime::now(),
+        requested_by: payload.requested_by,
+        reason: payload.reason,
+    };
 
-        Err(_err) => {
+    match pis::move_pis_timeslot(&id, &entry, false).await {
+        Ok(_) => {}
+
+        Err(err) => {
             return Ok(Json(json!({
                 "status": "error",
-                "message": "failed to update rushee"
+                "message": err.to_string()
             })))
         }
     }
+
+    if let Err(err) = locations::allocate_location(&id).await {
+        println!("Couldn't give the PIS a location: {}", err);
+    }
+
+    // the seat that was just given back goes to whoever is waiting for it
+    if let Err(err) = waitlist::offer_open_seats(entry.from).await {
+        println!("Couldn't offer the released PIS seat: {}", err);
+    }
+
+    let message = format!(
+        "{} {}'s PIS moved from {} to {}",
+        rushee.first_name,
+        rushee.last_name,
+        entry.from.try_to_rfc3339_string().unwrap_or_default(),
+        entry.to.try_to_rfc3339_string().unwrap_or_default()
+    );
+
+    if let Err(err) = notifications::notify_brothers(&rushee.pis_signup.panel, &id, &message).await {
+        println!("Couldn't notify the PIS panel: {}", err);
+    }
+
+    Ok(Json(json!({
+        "status": "success",
+        "message": "successfully rescheduled pis",
+        "payload": entry
+    })))
 }
 
 pub async fn delete_comment(
@@ -804,17 +1002,16 @@ pub async fn delete_comment(
         }
     }
 
-    let mut bson_night: bson::Bson;
-    let bson_night_attempt = to_bson(&payload.night);
+    let comment_filter;
 
-    match bson_night_attempt {
-        Ok(x) => {
-            bson_night = x;
+    match find_comment(&payload.brother_name, &payload.night, &rushee.comments) {
+        Some(comment) => {
+            comment_filter = comment_match(comment);
         }
-        Err(_error) => {
+        None => {
             return Ok(Json(json!({
                 "status": "error",
-                "message": "there was an error bsonifying the night"
+                "message": "comment does not exist"
             })))
         }
     }
@@ -823,22 +1020,27 @@ pub async fn delete_comment(
     let filter = doc! {"gtid": id.clone()};
     let update = doc! {
         "$pull": {
-            "comments": {
-                "brother_name": &payload.brother_name,
-                "night": bson_night
-            }
+            "comments": comment_filter
         }
     };
     let update_result = connection.update_one(filter, update).await;
 
     match update_result {
+        // nothing was removed, so the ratings stay as they are
+        Ok(result) if result.modified_count == 0 => {
+            return Ok(Json(json!({
+                "status": "error",
+                "message": "comment does not exist"
+            })))
+        }
+
         Ok(_result) => {
             // Now recalculate ratings based on remaining comments
             // Filter out the deleted comment from our local copy
             let remaining_comments: Vec<Comment> = rushee.comments.into_iter()
                 .filter(|comment| {
-                    !(comment.brother_name == payload.brother_name && 
-                      same_day(&comment.night.time, &payload.night.time))
+                    !(comment.brother_name == payload.brother_name &&
+                      is_comment_on_night(comment, &payload.night))
                 })
                 .collect();
 
@@ -933,52 +1135,124 @@ pub async fn edit_comment(
 ) -> Result<Json<Value>, StatusCode> {
     let connection = db::get_rushee_client().await;
 
-    let mut bson_night: bson::Bson;
-    let bson_night_attempt = to_bson(&payload.night);
+    let rushee;
 
-    match bson_night_attempt {
+    match fetch_rushee(id.clone()).await {
         Ok(x) => {
-            bson_night = x;
+            rushee = x;
         }
 
-        Err(_error) => {
+        Err(err) => {
             return Ok(Json(json!({
                 "status": "error",
-                "message": "there was an error bsonifying the night"
+                "message": err.message
+            })))
+        }
+    }
+
+    let comment_filter;
+
+    match find_comment(&payload.brother_name, &payload.night, &rushee.comments) {
+        Some(comment) => {
+            comment_filter = comment_match(comment);
+        }
+
+        None => {
+            return Ok(Json(json!({
+                "status": "error",
+                "message": "comment does not exist"
+            })))
+        }
+    }
+
+    let flags = moderate(&payload.comment);
+    let moderation_status = if flags.is_empty() {
+        ModerationStatus::Clear
+    } else {
+        ModerationStatus::Flagged
+    };
+
+    let bson_flags;
+    let bson_status;
+
+    match (to_bson(&flags), to_bson(&moderation_status)) {
+        (Ok(x), Ok(y)) => {
+            bson_flags = x;
+            bson_status = y;
+        }
+
+        _ => {
+            return Ok(Json(json!({
+                "status": "error",
+                "message": "there was an error bsonifying the moderation flags"
             })))
         }
     }
 
     let filter = doc! {
         "gtid": id.clone(),
-        "comments": {
-            "$elemMatch": {
-                "brother_name": payload.brother_name,
-                "night": bson_night,
-            }
-        }
+        "comments": {"$elemMatch": comment_filter.clone()}
     };
 
     let update = doc! {
         "$set": {
-            "comments.$.comment": payload.comment
+            "comments.$.comment": payload.comment,
+            "comments.$.flags": bson_flags,
         }
     };
 
     let edit_result = connection.update_one(filter, update).await;
 
     match edit_result {
+        Ok(edit) if edit.matched_count == 0 => {
+            return Ok(Json(json!({
+                "status": "error",
+                "message": "comment does not exist"
+            })))
+        }
+
         Ok(_edit) => {
+            // do nothing
+        }
+
+        Err(_err) => {
+            return Ok(Json(json!({
+                "status": "error",
+                "message": "there was an error pushing the update to the database"
+            })))
+        }
+    }
+
+    // an edit re-runs moderation, but never un-hides a comment an admin hid
+    let mut visible_comment = comment_filter;
+    visible_comment.insert("moderation", doc! {"$ne": "Hidden"});
+
+    let status_filter = doc! {
+        "gtid": id.clone(),
+        "comments": {"$elemMatch": visible_comment}
+    };
+
+    let status_update = doc! {
+        "$set": {
+            "comments.$.moderation": bson_status
+        }
+    };
+
+    let status_result = connection.update_one(status_filter, status_update).await;
+
+    match status_result {
+        Ok(_status) => {
             return Ok(Json(json!({
                 "status": "success",
-                "message": "updated comment successfully"
+                "message": "updated comment successfully",
+                "flags": flags
             })))
         }
 
         Err(_err) => {
             return Ok(Json(json!({
                 "status": "error",
-                "message": "there was an error pushing the update to the database"
+                "message": "there was an error updating the comment's moderation status"
             })))
         }
     }
@@ -1051,8 +1325,20 @@ pub async fn get_signup_timeslots() -> Result<Json<Value>, StatusCode> {
     }
 }
 
-/// Returns all rushees where the given brother_name has commented, with rushee info and the brother's comment(s)
-pub async fn get_brother_comments(Path(brother_name): Path<String>) -> Result<Json<Value>, StatusCode> {
+/// Returns all rushees where the given brother_name has commented or replied, with rushee info and the brother's comment(s) and replies
+/// Not available in blind review (bid committee or ?anonymous=true): looking up a brother by name would tie him to his pseudonym,
+/// only the audited admin de-anonymize endpoint may do that
+pub async fn get_brother_comments(
+    Path(brother_name): Path<String>,
+    Query(params): Query<RusheeReadParams>,
+    headers: HeaderMap,
+) -> Result<Json<Value>, StatusCode> {
+    let role = get_role(&headers);
+
+    if role == Role::BidCommittee || params.anonymous.unwrap_or(false) {
+        return Err(StatusCode::UNAUTHORIZED);
+    }
+
     let collection = db::get_rushee_client().await;
     let result = collection.find(doc! {}).await;
 
@@ -1060,13 +1346,27 @@ pub async fn get_brother_comments(Path(brother_name): Path<String>) -> Result<Js
         Ok(mut cursor) => {
             let mut commented_rushees = Vec::new();
             while let Some(rushee_res) = cursor.next().await {
-                if let Ok(rushee) = rushee_res {
+                if let Ok(mut rushee) = rushee_res {
+                    if role != Role::Admin {
+                        rushee.comments.retain(|comment| comment.moderation != ModerationStatus::Hidden);
+                    }
+
                     // Find all comments by this brother on this rushee
                     let brother_comments: Vec<_> = rushee.comments.iter()
                         .filter(|c| c.brother_name == brother_name)
                         .cloned()
                         .collect();
-                    if !brother_comments.is_empty() {
+                    // Find all replies by this brother, along with the comment they answer
+                    let brother_replies: Vec<_> = rushee.comments.iter()
+                        .flat_map(|c| c.replies.iter()
+                            .filter(|r| r.brother_name == brother_name)
+                            .map(move |r| serde_json::json!({
+                                "comment_brother_name": c.brother_name,
+                                "night": c.night,
+                                "reply": r
+                            })))
+                        .collect();
+                    if !brother_comments.is_empty() || !brother_replies.is_empty() {
                         commented_rushees.push(serde_json::json!({
                             "rushee": {
                                 "gtid": rushee.gtid,
@@ -1074,7 +1374,8 @@ pub async fn get_brother_comments(Path(brother_name): Path<String>) -> Result<Js
                                 "last_name": rushee.last_name,
                                 "image_url": rushee.image_url,
                             },
-                            "comments": brother_comments
+                            "comments": brother_comments,
+                            "replies": brother_replies
                         }));
                     }
                 }
@@ -1090,3 +1391,527 @@ pub async fn get_brother_comments(Path(brother_name): Path<String>) -> Result<Js
         }))),
     }
 }
+
+/**
+ * Reply to another brother's comment on a rushee
+ */
+pub async fn reply_to_comment(
+    Path(id): Path<String>,
+    Json(payload): Json<IncomingReply>,
+) -> Result<Json<Value>, StatusCode> {
+    let connection = db::get_rushee_client().await;
+
+    let new_reply = CommentReply {
+        reply_id: ObjectId::new().to_hex(),
+        brother_id: payload.brother_id,
+        brother_name: payload.brother_name,
+        reply: payload.reply,
+        time: bson::DateTime::now(),
+        edited: false,
+    };
+
+    let rushee;
+
+    match fetch_rushee(id.clone()).await {
+        Ok(x) => {
+            rushee = x;
+        }
+
+        Err(err) => {
+            return Ok(Json(json!({
+                "status": "error",
+                "message": err.message
+            })))
+        }
+    }
+
+    let comment;
+
+    match find_comment(&payload.comment_brother_name, &payload.night, &rushee.comments) {
+        Some(x) => {
+            comment = x;
+        }
+
+        None => {
+            return Ok(Json(json!({
+                "status": "error",
+                "message": "couldn't find the comment being replied to"
+            })))
+        }
+    }
+
+    let bson_reply;
+
+    match to_bson(&new_reply) {
+        Ok(x) => {
+            bson_reply = x;
+        }
+
+        Err(_err) => {
+            return Ok(Json(json!({
+                "status": "error",
+                "message": "there was an error bsonifying the reply"
+            })))
+        }
+    }
+
+    let filter = doc! {
+        "gtid": id.clone(),
+        "comments": {"$elemMatch": comment_match(comment)}
+    };
+
+    let update = doc! {
+        "$push": {
+            "comments.$.replies": bson_reply
+        }
+    };
+
+    let result = connection.update_one(filter, update).await;
+
+    match result {
+        Ok(update_result) => {
+            if update_result.matched_count == 0 {
+                return Ok(Json(json!({
+                    "status": "error",
+                    "message": "couldn't find the comment being replied to"
+                })));
+            }
+
+            Ok(Json(json!({
+                "status": "success",
+                "message": "successfully posted reply",
+                "payload": new_reply
+            })))
+        }
+
+        Err(_err) => Ok(Json(json!({
+            "status": "error",
+            "message": "there was an error pushing the reply to the database"
+        }))),
+    }
+}
+
+/**
+ * Edit a reply (only the author can, and only within the edit window)
+ */
+pub async fn edit_reply(
+    Path(id): Path<String>,
+    Json(payload): Json<IncomingReplyEdit>,
+) -> Result<Json<Value>, StatusCode> {
+    let connection = db::get_rushee_client().await;
+
+    let rushee;
+
+    match fetch_rushee(id.clone()).await {
+        Ok(x) => {
+            rushee = x;
+        }
+
+        Err(err) => {
+            return Ok(Json(json!({
+                "status": "error",
+                "message": err.message
+            })))
+        }
+    }
+
+    match find_reply(&payload.reply_id, &rushee.comments) {
+        Some(reply) => {
+            if let Err(err) = check_valid_reply_edit(&payload.brother_name, reply) {
+                return Ok(Json(json!({
+                    "status": "error",
+                    "message": err.to_string()
+                })));
+            }
+        }
+
+        None => {
+            return Ok(Json(json!({
+                "status": "error",
+                "message": "reply does not exist"
+            })))
+        }
+    }
+
+    let filter = doc! {"gtid": id.clone()};
+    let update = doc! {
+        "$set": {
+            "comments.$[].replies.$[r].reply": payload.reply,
+            "comments.$[].replies.$[r].edited": true,
+        }
+    };
+
+    let edit_result = connection
+        .update_one(filter, update)
+        .array_filters(vec![doc! {"r.reply_id": payload.reply_id}])
+        .await;
+
+    match edit_result {
+        Ok(_edit) => Ok(Json(json!({
+            "status": "success",
+            "message": "updated reply successfully"
+        }))),
+
+        Err(_err) => Ok(Json(json!({
+            "status": "error",
+            "message": "there was an error pushing the update to the database"
+        }))),
+    }
+}
+
+/**
+ * Delete a reply (only the author can)
+ */
+pub async fn delete_reply(
+    Path(id): Path<String>,
+    Json(payload): Json<IncomingReplyDelete>,
+) -> Result<Json<Value>, StatusCode> {
+    let connection = db::get_rushee_client().await;
+
+    let rushee;
+
+    match fetch_rushee(id.clone()).await {
+        Ok(x) => {
+            rushee = x;
+        }
+
+        Err(err) => {
+            return Ok(Json(json!({
+                "status": "error",
+                "message": err.message
+            })))
+        }
+    }
+
+    match find_reply(&payload.reply_id, &rushee.comments) {
+        Some(reply) => {
+            if let Err(err) = check_valid_reply_delete(&payload.brother_name, reply) {
+                return Ok(Json(json!({
+                    "status": "error",
+                    "message": err.to_string()
+                })));
+            }
+        }
+
+        None => {
+            return Ok(Json(json!({
+                "status": "error",
+                "message": "reply does not exist"
+            })))
+        }
+    }
+
+    let filter = doc! {"gtid": id.clone()};
+    let update = doc! {
+        "$pull": {
+            "comments.$[].replies": {
+                "reply_id": payload.reply_id
+            }
+        }
+    };
+
+    let delete_result = connection.update_one(filter, update).await;
+
+    match delete_result {
+        Ok(_delete) => Ok(Json(json!({
+            "status": "success",
+            "message": "successfully deleted reply"
+        }))),
+
+        Err(_err) => Ok(Json(json!({
+            "status": "error",
+            "message": "couldn't delete the reply from the database"
+        }))),
+    }
+}
+
+/**
+ * Helper function to map a reaction to the proper enum
+ */
+fn map_reaction(reaction: &str) -> Option<ReactionOption> {
+    match reaction.to_lowercase().as_str() {
+        "agree" => Some(ReactionOption::Agree),
+        "disagree" => Some(ReactionOption::Disagree),
+        _ => None,
+    }
+}
+
+/**
+ * React to a comment. Each brother gets one reaction per comment,
+ * sending the same reaction again takes it back
+ */
+pub async fn react_to_comment(
+    Path(id): Path<String>,
+    Json(payload): Json<IncomingReaction>,
+) -> Result<Json<Value>, StatusCode> {
+    let connection = db::get_rushee_client().await;
+
+    let reaction;
+
+    match map_reaction(&payload.reaction) {
+        Some(x) => {
+            reaction = x;
+        }
+
+        None => {
+            return Ok(Json(json!({
+                "status": "error",
+                "message": format!("Invalid reaction: {}", payload.reaction)
+            })))
+        }
+    }
+
+    let rushee;
+
+    match fetch_rushee(id.clone()).await {
+        Ok(x) => {
+            rushee = x;
+        }
+
+        Err(err) => {
+            return Ok(Json(json!({
+                "status": "error",
+                "message": err.message
+            })))
+        }
+    }
+
+    let comment;
+
+    match find_comment(&payload.comment_brother_name, &payload.night, &rushee.comments) {
+        Some(x) => {
+            comment = x;
+        }
+
+        None => {
+            return Ok(Json(json!({
+                "status": "error",
+                "message": "couldn't find the comment being reacted to"
+            })))
+        }
+    }
+
+    let already_reacted = comment
+        .reactions
+        .iter()
+        .any(|r| r.brother_name == payload.brother_name && r.reaction == reaction);
+
+    let filter = doc! {
+        "gtid": id.clone(),
+        "comments": {"$elemMatch": comment_match(comment)}
+    };
+
+    // clear out the brother's previous reaction first
+    let pull = doc! {
+        "$pull": {
+            "comments.$.reactions": {
+                "brother_name": payload.brother_name.clone()
+            }
+        }
+    };
+
+    match connection.update_one(filter.clone(), pull).await {
+        Ok(update_result) if update_result.matched_count == 0 => {
+            return Ok(Json(json!({
+                "status": "error",
+                "message": "couldn't find the comment being reacted to"
+            })))
+        }
+
+        Ok(_update_result) => {}
+
+        Err(_err) => {
+            return Ok(Json(json!({
+                "status": "error",
+                "message": "couldn't update the reaction"
+            })))
+        }
+    }
+
+    if already_reacted {
+        return Ok(Json(json!({
+            "status": "success",
+            "message": "removed reaction"
+        })));
+    }
+
+    let bson_reaction;
+
+    match to_bson(&CommentReaction {
+        brother_name: payload.brother_name,
+        reaction: reaction,
+    }) {
+        Ok(x) => {
+            bson_reaction = x;
+        }
+
+        Err(_err) => {
+            return Ok(Json(json!({
+                "status": "error",
+                "message": "there was an error bsonifying the reaction"
+            })))
+        }
+    }
+
+    let push = doc! {
+        "$push": {
+            "comments.$.reactions": bson_reaction
+        }
+    };
+
+    match connection.update_one(filter, push).await {
+        Ok(update_result) if update_result.matched_count == 0 => Ok(Json(json!({
+            "status": "error",
+            "message": "couldn't find the comment being reacted to"
+        }))),
+
+        Ok(_update) => Ok(Json(json!({
+            "status": "success",
+            "message": "added reaction"
+        }))),
+
+        Err(_err) => Ok(Json(json!({
+            "status": "error",
+            "message": "couldn't update the reaction"
+        }))),
+    }
+}
+
+/**
+ * Replace a rushee's face embedding (e.g. after they upload a new photo)
+ * Only the rushee (with their access code) or an admin can, otherwise anyone could check a rushee in with their own face
+ */
+pub async fn update_face(
+    Path(id): Path<String>,
+    headers: HeaderMap,
+    Json(payload): Json<IncomingFaceEmbedding>,
+) -> Result<Json<Value>, StatusCode> {
+    let rushee;
+
+    match fetch_rushee(id.clone()).await {
+        Ok(x) => {
+            rushee = x;
+        }
+
+        Err(err) => {
+            return Ok(Json(json!({
+                "status": "error",
+                "message": err.message
+            })))
+        }
+    }
+
+    if get_role(&headers) != Role::Admin && payload.access_code.as_deref() != Some(rushee.access_code.as_str()) {
+        return Ok(Json(json!({
+            "status": "error",
+            "message": "invalid access code"
+        })));
+    }
+
+    match face::store_face_embedding(&id, &payload.embedding).await {
+        Ok(_x) => Ok(Json(json!({
+            "status": "success",
+            "message": "updated rushee face"
+        }))),
+
+        Err(err) => Ok(Json(json!({
+            "status": "error",
+            "message": err.to_string()
+        }))),
+    }
+}
+
+/**
+ * Face check-in from the kiosk
+ * A confident match is checked in right away. A close but not confident match comes back with
+ * status "confirm" and the candidates, and whoever is at the kiosk confirms through update-attendance
+ * with the door token and source "face"
+ */
+pub async fn get_rushee_face(
+    Json(payload): Json<IncomingFaceCheckin>,
+) -> Result<Json<Value>, StatusCode> {
+    let token;
+    let current_night;
+
+    match attendance::verify_token_for_tonight(&payload.token).await {
+        Ok((x, y)) => {
+            token = x;
+            current_night = y;
+        }
+
+        Err(err) => {
+            return Ok(Json(json!({
+                "status": "error",
+                "message": err.to_string()
+            })))
+        }
+    }
+
+    if token.purpose != DOOR_TOKEN {
+        return Ok(Json(json!({
+            "status": "error",
+            "message": "face check-in needs a door check-in code"
+        })));
+    }
+
+    let matches;
+
+    match face::find_face_matches(&payload.embedding).await {
+        Ok(x) => {
+            matches = x;
+        }
+
+        Err(err) => {
+            return Ok(Json(json!({
+                "status": "error",
+                "message": err.to_string()
+            })))
+        }
+    }
+
+    let best_score = matches.first().map(|m| m.score).unwrap_or(0.0);
+
+    if best_score >= face::face_match_threshold() {
+        let best = &matches[0];
+        let checked_in_by = payload.kiosk_name.unwrap_or("kiosk".to_string());
+
+        let result = attendance::record_attendance(
+            &best.gtid,
+            &current_night,
+            CheckinSource::Face,
+            Some(checked_in_by),
+        )
+        .await;
+
+        return match result {
+            Ok(outcome) => Ok(Json(json!({
+                "status": "success",
+                "payload": best,
+                "uninvited": outcome == CheckinOutcome::CheckedInUninvited
+            }))),
+
+            Err(err) => Ok(Json(json!({
+                "status": "error",
+                "message": err.to_string()
+            }))),
+        };
+    }
+
+    if best_score >= face::face_confirm_threshold() {
+        let candidates: Vec<_> = matches
+            .into_iter()
+            .filter(|m| m.score >= face::face_confirm_threshold())
+            .collect();
+
+        return Ok(Json(json!({
+            "status": "confirm",
+            "message": "we're not sure who this is, please confirm",
+            "payload": candidates
+        })));
+    }
+
+    Ok(Json(json!({
+        "status": "error",
+        "message": "couldn't recognize this face, please check in with your access code"
+    })))
+}
@@ -47,6 +47,7 @@ pub struct QuestionAndRushee {
 }
 
 const INEGLIBLE_BROTHERS: &str = "ineligible_brothers";
+pub const VOTE_COUNTS: &str = "vote_counts"; // running count of votes per brother ("first last"), never cleared, only has votes cast since it was added
 const PLACEHOLDER_QUESTION: &str = "NO_QUESTION";
 
 /**
@@ -184,6 +185,16 @@ pub async fn handle_rushee_vote(
         .await
         .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
 
+    // Step 2: Count the vote towards the brother's participation
+    let _: i64 = conn
+        .hincr(
+            VOTE_COUNTS,
+            format!("{} {}", rusheeVote.first_name, rusheeVote.last_name),
+            1,
+        )
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
     // Publish update to notify listeners
     let _: () = conn
         .publish("vote_channel", "updated")
@@ -65,27 +65,84 @@ async fn main() -> Result<(), Error> {
         .route("/rushee/:id", get(controllers::rushee::get_rushee).options(|| async { StatusCode::OK }))
         .route("/rushee/post-comment/:id",post(controllers::rushee::post_comment).options(|| async { StatusCode::OK }))
         .route("/rushee/post-pis/:id", post(controllers::rushee::post_pis).options(|| async { StatusCode::OK }))
+        .route("/rushee/pis-answers/:id", post(controllers::rushee::update_pis_answers).options(|| async { StatusCode::OK }))
         .route("/rushee/update-attendance/:id",post(controllers::rushee::update_attendance).options(|| async { StatusCode::OK }))
+        .route("/rushee/get-rushee-face", post(controllers::rushee::get_rushee_face).options(|| async { StatusCode::OK }))
+        .route("/rushee/update-face/:id", post(controllers::rushee::update_face).options(|| async { StatusCode::OK }))
         .route("/rushee/update-cloud/:id", post(controllers::rushee::update_cloud).options(|| async { StatusCode::OK }))
         .route("/rushee/update-rushee/:id", post(controllers::rushee::update_rushee).options(|| async { StatusCode::OK }))
         .route("/rushee/reschedule-pis/:id", post(controllers::rushee::reschedule_pis).options(|| async { StatusCode::OK }))
         .route("/rushee/edit-comment/:id", post(controllers::rushee::edit_comment).options(|| async { StatusCode::OK }))
         .route("/rushee/delete-comment/:id", post(controllers::rushee::delete_comment).options(|| async { StatusCode::OK }))
+        .route("/rushee/reply-comment/:id", post(controllers::rushee::reply_to_comment).options(|| async { StatusCode::OK }))
+        .route("/rushee/edit-reply/:id", post(controllers::rushee::edit_reply).options(|| async { StatusCode::OK }))
+        .route("/rushee/delete-reply/:id", post(controllers::rushee::delete_reply).options(|| async { StatusCode::OK }))
+        .route("/rushee/react-comment/:id", post(controllers::rushee::react_to_comment).options(|| async { StatusCode::OK }))
         .route("/rushee/does-rushee-exist/:id", get(controllers::rushee::does_rushee_exist))
         .route("/rushee/get-timeslots", get(controllers::rushee::get_signup_timeslots))
         .route("/brother/comments/:brother_name", get(controllers::rushee::get_brother_comments).options(|| async { StatusCode::OK }))
 
         .route("/admin/add_pis_question", post(controllers::admin::add_pis_question).options(|| async { StatusCode::OK }))
         .route("/admin/delete_pis_question", post(controllers::admin::delete_pis_question).options(|| async { StatusCode::OK }))
+        .route("/admin/edit-pis-question/:id", post(controllers::admin::edit_pis_question).options(|| async { StatusCode::OK }))
+        .route("/admin/reorder-pis-questions", post(controllers::admin::reorder_pis_questions).options(|| async { StatusCode::OK }))
+        .route("/admin/import-pis-questions", post(controllers::admin::import_pis_questions).options(|| async { StatusCode::OK }))
         .route("/admin/get_pis_questions", get(controllers::admin::get_pis_questions).options(|| async { StatusCode::OK }))
         .route("/admin/add_pis_timeslot", post(controllers::admin::add_pis_timeslot).options(|| async { StatusCode::OK }))
         .route("/admin/delete_pis_timeslot", post(controllers::admin::delete_pis_timeslot).options(|| async { StatusCode::OK }))
         .route("/admin/get_pis_timeslots", get(controllers::admin::get_pis_timeslots).options(|| async { StatusCode::OK }))
         .route("/admin/add-rush-night", post(controllers::admin::add_rush_night).options(|| async { StatusCode::OK }))
-        .route("/admin/delete_rush_night", post(controllers::admin::delete_rush_night).options(|| async { StatusCode::OK }))
+        .route("/admin/rush-nights", get(controllers::admin::get_rush_nights).options(|| async { StatusCode::OK }))
+        .route("/admin/rush-night/:id", get(controllers::admin::get_rush_night).options(|| async { StatusCode::OK }))
+        .route("/admin/update-rush-night/:id", post(controllers::admin::update_rush_night).options(|| async { StatusCode::OK }))
+        .route("/admin/delete-rush-night/:id", post(controllers::admin::delete_rush_night).options(|| async { StatusCode::OK }))
         .route("/admin/pis-signup/:id", post(controllers::admin::brother_pis_sign_up).options(|| async { StatusCode::OK }))
+        .route("/admin/pis-withdraw/:id", post(controllers::admin::brother_pis_withdraw).options(|| async { StatusCode::OK }))
         .route("/admin/get-brother-pis", post(controllers::admin::get_brother_pis).options(|| async { StatusCode::OK }))
+        .route("/admin/deanonymize-comment/:id", post(controllers::admin::deanonymize_comment).options(|| async { StatusCode::OK }))
+        .route("/admin/checkin-token", get(controllers::admin::get_checkin_token).options(|| async { StatusCode::OK }))
+        .route("/admin/checkin-qr", get(controllers::admin::get_checkin_qr).options(|| async { StatusCode::OK }))
+        .route("/admin/moderation/flagged", get(controllers::admin::get_flagged_comments).options(|| async { StatusCode::OK }))
+        .route("/admin/moderate-comment/:id", post(controllers::admin::moderate_comment).options(|| async { StatusCode::OK }))
         
+        .route("/admin/reports/brother-participation", get(controllers::reports::get_brother_participation).options(|| async { StatusCode::OK }))
+        .route("/admin/reports/coverage", get(controllers::reports::get_coverage_report).options(|| async { StatusCode::OK }))
+        .route("/admin/reports/attendance", get(controllers::reports::get_attendance_report).options(|| async { StatusCode::OK }))
+        .route("/admin/reports/invitations/:night_id", get(controllers::reports::get_invitation_report).options(|| async { StatusCode::OK }))
+        .route("/admin/invitations/:night_id", get(controllers::invitations::get_night_invitations).options(|| async { StatusCode::OK }))
+        .route("/admin/invite/:night_id", post(controllers::invitations::invite_to_night).options(|| async { StatusCode::OK }))
+        .route("/admin/uninvite/:night_id", post(controllers::invitations::uninvite_from_night).options(|| async { StatusCode::OK }))
+        .route("/rushee/invitations/:id", post(controllers::invitations::get_rushee_invitations).options(|| async { StatusCode::OK }))
+        .route("/rushee/rsvp/:id", post(controllers::invitations::rsvp).options(|| async { StatusCode::OK }))
+        .route("/admin/pis-assignment", get(controllers::pis::get_pis_assignment).options(|| async { StatusCode::OK }))
+        .route("/admin/pis-assignment/preview", post(controllers::pis::preview_pis_assignment).options(|| async { StatusCode::OK }))
+        .route("/admin/pis-assignment/tweak", post(controllers::pis::tweak_pis_assignment).options(|| async { StatusCode::OK }))
+        .route("/admin/pis-availability", get(controllers::pis::get_timeslot_availability).options(|| async { StatusCode::OK }))
+        .route("/brother/availability", post(controllers::pis::submit_availability).options(|| async { StatusCode::OK }))
+        .route("/brother/edit-availability", post(controllers::pis::edit_availability).options(|| async { StatusCode::OK }))
+        .route("/admin/pis-timeslots/generate", post(controllers::pis::generate_pis_timeslots).options(|| async { StatusCode::OK }))
+        .route("/admin/pis-calendar", get(controllers::pis::get_pis_calendar).options(|| async { StatusCode::OK }))
+        .route("/admin/pis-flex-queue", get(controllers::pis::get_flex_queue).options(|| async { StatusCode::OK }))
+        .route("/admin/pis-flex-place/:id", post(controllers::pis::place_flex_rushee).options(|| async { StatusCode::OK }))
+        .route("/rushee/pis-flex-status/:id", post(controllers::pis::get_flex_status).options(|| async { StatusCode::OK }))
+        .route("/brother/pis-scorecard/:id", post(controllers::pis::submit_scorecard).options(|| async { StatusCode::OK }))
+        .route("/brother/get-pis-scorecard/:id", post(controllers::pis::get_scorecard).options(|| async { StatusCode::OK }))
+        .route("/admin/pis-scorecards/:id", get(controllers::pis::get_pis_scorecards).options(|| async { StatusCode::OK }))
+        .route("/admin/pis-locations", get(controllers::pis::get_pis_locations).options(|| async { StatusCode::OK }))
+        .route("/admin/pis-location", post(controllers::pis::add_pis_location).options(|| async { StatusCode::OK }))
+        .route("/admin/pis-location/:id", post(controllers::pis::update_pis_location).options(|| async { StatusCode::OK }))
+        .route("/admin/retire-pis-location/:id", post(controllers::pis::retire_pis_location).options(|| async { StatusCode::OK }))
+        .route("/admin/pis-locations/allocate", post(controllers::pis::allocate_pis_locations).options(|| async { StatusCode::OK }))
+        .route("/brother/pis-status/:id", post(controllers::pis::mark_pis_status).options(|| async { StatusCode::OK }))
+        .route("/admin/pis-waitlist", get(controllers::pis::get_waitlist_depth).options(|| async { StatusCode::OK }))
+        .route("/rushee/pis-waitlist/:id", post(controllers::pis::join_pis_waitlist).options(|| async { StatusCode::OK }))
+        .route("/rushee/get-pis-waitlist/:id", post(controllers::pis::get_rushee_waitlist).options(|| async { StatusCode::OK }))
+        .route("/rushee/pis-waitlist/accept/:id", post(controllers::pis::accept_waitlist_offer).options(|| async { StatusCode::OK }))
+        .route("/rushee/pis-waitlist/leave/:id", post(controllers::pis::leave_pis_waitlist).options(|| async { StatusCode::OK }))
+        .route("/brother/pis-notifications", post(controllers::pis::get_pis_notifications).options(|| async { StatusCode::OK }))
+        .route("/brother/get-availability", post(controllers::pis::get_availability).options(|| async { StatusCode::OK }))
+        .route("/admin/pis-assignment/commit", post(controllers::pis::commit_pis_assignment).options(|| async { StatusCode::OK }))
+
         .route("/rushee/vote", post(controllers::voting::handle_rushee_vote).options(|| async { StatusCode::OK }))
         .route("/admin/voting/change-rushee", post(controllers::voting::change_rushee).options(|| async { StatusCode::OK }))
         .route("/admin/voting/clear-votes", post(controllers::voting::clear_votes).options(|| async { StatusCode::OK }))
@@ -0,0 +1,254 @@
+/**
+ * PIS interviewer assignment. Puts a full panel (see panel_size) on every rushee's PIS, spreading the load
+ * as evenly as possible while respecting availability, each brother's load cap (PIS_MAX_LOAD by default)
+ * and the conflict rules: brothers don't interview rushees they referred, nobody is on two PIS at once,
+ * and a panel is never all freshmen
+ */
+
+use std::collections::{HashMap, HashSet};
+use std::env;
+
+use bson::DateTime;
+
+use crate::middlewares::timeHelpers::string_to_bson_datetime;
+use crate::models::pis::{AssignmentBrother, PISAssignment, PISBrother};
+use crate::models::Rushee::RusheeModel;
+
+const DEFAULT_PIS_PANEL_SIZE: usize = 3;
+const DEFAULT_PIS_MAX_LOAD: u32 = 4;
+
+/**
+ * How many brothers interview each rushee, set through PIS_PANEL_SIZE
+ */
+pub fn panel_size() -> usize {
+    env::var("PIS_PANEL_SIZE")
+        .ok()
+        .and_then(|size| size.parse::<usize>().ok())
+        .filter(|size| *size > 0)
+        .unwrap_or(DEFAULT_PIS_PANEL_SIZE)
+}
+
+/**
+ * How many PIS a brother is put on unless they say otherwise, set through PIS_MAX_LOAD
+ */
+pub fn default_max_load() -> u32 {
+    env::var("PIS_MAX_LOAD")
+        .ok()
+        .and_then(|load| load.parse::<u32>().ok())
+        .unwrap_or(DEFAULT_PIS_MAX_LOAD)
+}
+
+pub fn full_name(first_name: &str, last_name: &str) -> String {
+    format!("{} {}", first_name, last_name)
+}
+
+fn brother_key(brother: &PISBrother) -> String {
+    full_name(&brother.first_name, &brother.last_name).to_lowercase()
+}
+
+fn assignment_brother_key(brother: &AssignmentBrother) -> String {
+    full_name(&brother.first_name, &brother.last_name).to_lowercase()
+}
+
+/**
+ * Whether a brother referred the rushee, either listed explicitly or named in the rushee's exposure answer
+ */
+pub fn referred(brother: &AssignmentBrother, rushee: &RusheeModel) -> bool {
+    brother.referrals.contains(&rushee.gtid)
+        || rushee.exposure.to_lowercase().contains(&assignment_brother_key(brother))
+}
+
+/**
+ * Builds a balanced assignment. The most constrained PIS (fewest brothers who could take it) are filled
+ * first, and each seat goes to the eligible brother with the lightest load so far
+ */
+pub fn build_assignment(
+    rushees: &Vec<RusheeModel>,
+    brothers: &Vec<AssignmentBrother>,
+    keep_existing: bool,
+) -> Vec<PISAssignment> {
+    let mut loads = HashMap::<String, u32>::new();
+    let mut busy = HashSet::<(String, i64)>::new();
+
+    let eligible_count = |rushee: &RusheeModel| {
+        brothers
+            .iter()
+            .filter(|brother| brother.available.contains(&rushee.pis_timeslot) && !referred(brother, rushee))
+            .count()
+    };
+
+    let mut order: Vec<&RusheeModel> = rushees.iter().collect();
+    order.sort_by_key(|rushee| (eligible_count(rushee), rushee.pis_timeslot));
+
+    // brothers who signed up themselves count against their load before anything else is placed
+    let mut panels = HashMap::<String, Vec<PISBrother>>::new();
+
+    for rushee in order.iter() {
+        let panel = if keep_existing { rushee.pis_signup.panel.clone() } else { Vec::new() };
+
+        for brother in panel.iter() {
+            *loads.entry(brother_key(brother)).or_insert(0) += 1;
+            busy.insert((brother_key(brother), rushee.pis_timeslot.timestamp_millis()));
+        }
+
+        panels.insert(rushee.gtid.clone(), panel);
+    }
+
+    let panel_size = panel_size();
+    let mut assignments = Vec::<PISAssignment>::new();
+
+    for rushee in order.iter() {
+        let mut panel = panels.remove(&rushee.gtid).unwrap_or_default();
+        let time = rushee.pis_timeslot.timestamp_millis();
+
+        while panel.len() < panel_size {
+            let on_panel: HashSet<String> = panel.iter().map(brother_key).collect();
+
+            // the last seat can't go to a freshman if everyone else on the panel is one
+            let needs_upperclassman = panel.len() == panel_size - 1
+                && panel.iter().all(|member| {
+                    brothers
+                        .iter()
+                        .find(|brother| assignment_brother_key(brother) == brother_key(member))
+                        .map(|brother| brother.freshman)
+                        .unwrap_or(false)
+                });
+
+            let pick = brothers
+                .iter()
+                .filter(|brother| {
+                    let key = assignment_brother_key(brother);
+
+                    brother.available.contains(&rushee.pis_timeslot)
+                        && !on_panel.contains(&key)
+                        && !busy.contains(&(key.clone(), time))
+                        && *loads.get(&key).unwrap_or(&0) < brother.max_load
+                        && !referred(brother, rushee)
+                        && !(needs_upperclassman && brother.freshman)
+                })
+                .min_by_key(|brother| {
+                    let key = assignment_brother_key(brother);
+                    (*loads.get(&key).unwrap_or(&0), key)
+                });
+
+            match pick {
+                Some(brother) => {
+                    let key = assignment_brother_key(brother);
+
+                    *loads.entry(key.clone()).or_insert(0) += 1;
+                    busy.insert((key, time));
+
+                    panel.push(PISBrother {
+                        first_name: brother.first_name.clone(),
+                        last_name: brother.last_name.clone(),
+                        role: None,
+                    });
+                }
+
+                None => break,
+            }
+        }
+
+        assignments.push(PISAssignment {
+            rushee_gtid: rushee.gtid.clone(),
+            rushee_name: full_name(&rushee.first_name, &rushee.last_name),
+            time: rushee.pis_timeslot,
+            brothers: panel,
+            warnings: Vec::new(),
+        });
+    }
+
+    assignments.sort_by_key(|assignment| (assignment.time, assignment.rushee_name.clone()));
+
+    check_assignments(&mut assignments, rushees, brothers);
+
+    assignments
+}
+
+/**
+ * Fills in the warnings on every assignment: short panels, brothers who aren't available, are over their
+ * load cap, are on two PIS at once or referred the rushee, and all-freshmen panels.
+ * Run again after every tweak since moving one brother can change the warnings on other PIS
+ */
+pub fn check_assignments(
+    assignments: &mut Vec<PISAssignment>,
+    rushees: &Vec<RusheeModel>,
+    brothers: &Vec<AssignmentBrother>,
+) {
+    let panel_size = panel_size();
+    let mut loads = HashMap::<String, u32>::new();
+    let mut booked = HashMap::<(String, i64), u32>::new();
+
+    for assignment in assignments.iter() {
+        for member in assignment.brothers.iter() {
+            *loads.entry(brother_key(member)).or_insert(0) += 1;
+            *booked.entry((brother_key(member), assignment.time.timestamp_millis())).or_insert(0) += 1;
+        }
+    }
+
+    for assignment in assignments.iter_mut() {
+        let mut warnings = Vec::<String>::new();
+
+        if assignment.brothers.len() < panel_size {
+            warnings.push(format!(
+                "only {} of {} interviewers are assigned",
+                assignment.brothers.len(),
+                panel_size
+            ));
+        }
+
+        let rushee = rushees.iter().find(|rushee| rushee.gtid == assignment.rushee_gtid);
+        let mut all_freshmen = !assignment.brothers.is_empty();
+
+        for member in assignment.brothers.iter() {
+            let name = full_name(&member.first_name, &member.last_name);
+            let key = brother_key(member);
+
+            let brother = brothers
+                .iter()
+                .find(|brother| assignment_brother_key(brother) == key);
+
+            match brother {
+                Some(brother) => {
+                    if !brother.available.contains(&assignment.time) {
+                        warnings.push(format!("{} isn't available at this time", name));
+                    }
+
+                    if *loads.get(&key).unwrap_or(&0) > brother.max_load {
+                        warnings.push(format!("{} is over their limit of {} PIS", name, brother.max_load));
+                    }
+
+                    if let Some(rushee) = rushee {
+                        if referred(brother, rushee) {
+                            warnings.push(format!("{} referred this rushee", name));
+                        }
+                    }
+
+                    all_freshmen = all_freshmen && brother.freshman;
+                }
+
+                None => {
+                    warnings.push(format!("{} didn't give their availability", name));
+                    all_freshmen = false;
+                }
+            }
+
+            if *booked.get(&(key, assignment.time.timestamp_millis())).unwrap_or(&0) > 1 {
+                warnings.push(format!("{} is on another PIS at the same time", name));
+            }
+        }
+
+        if all_freshmen {
+            warnings.push("every interviewer on this panel is a freshman".to_string());
+        }
+
+        assignment.warnings = warnings;
+    }
+}
+
+/**
+ * Parses the times a brother said they're free
+ */
+pub fn parse_times(times: &Vec<String>) -> Result<Vec<DateTime>, std::io::Error> {
+    times.iter().map(|time| string_to_bson_datetime(time)).collect()
+}
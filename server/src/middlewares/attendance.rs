@@ -1,13 +1,31 @@
 use std::collections::HashSet;
+use std::env;
 use std::io::Error;
 use futures::stream::StreamExt;
+use redis::AsyncCommands;
 use mongodb::{
-    bson::{doc, to_bson, Document},
+    bson::{doc, oid::ObjectId, to_bson, Document},
     Client, Collection,
 };
 
-use crate::models::misc::RushNight;
+use crate::models::misc::{AttendanceRecord, CheckinSource, IncomingRushNight, NightType, RushNight};
 use crate::controllers::db;
+use crate::middlewares::checkin::{verify_checkin_token, CheckinToken};
+use crate::middlewares::invitations::check_invitation;
+use crate::middlewares::timeHelpers::{
+    is_during_night, local_date, night_end, night_start, same_local_day, string_to_bson_datetime,
+};
+
+const DEFAULT_COMMENT_GRACE_HOURS: i64 = 72;
+const HEAD_COUNT_KEY: &str = "head_count";
+const ATTENDANCE_CHANNEL: &str = "attendance";
+
+#[derive(Debug, PartialEq)]
+pub enum CheckinOutcome {
+    CheckedIn,
+    CheckedInUninvited, // invite-only night and the rushee isn't on the list
+    AlreadyCheckedIn,
+}
 
 pub async fn get_rush_nights() -> Result<Vec<RushNight>, Error> {
 
@@ -40,4 +58,372 @@ pub async fn get_rush_nights() -> Result<Vec<RushNight>, Error> {
         
     }
 
-}
\ No newline at end of file
+}
+
+/**
+ * Fetches a single rush night by its id
+ */
+pub async fn get_rush_night(night_id: &str) -> Result<RushNight, Error> {
+
+    let night_oid = ObjectId::parse_str(night_id)
+        .map_err(|_| Error::new(std::io::ErrorKind::InvalidInput, "invalid rush night id"))?;
+
+    let connection = db::get_rush_nights_client().await;
+
+    match connection.find_one(doc! {"_id": night_oid}).await {
+        Ok(Some(night)) => Ok(night),
+        Ok(None) => Err(Error::new(std::io::ErrorKind::NotFound, "rush night does not exist")),
+        Err(_err) => Err(Error::new(std::io::ErrorKind::Other, "some error occurred")),
+    }
+
+}
+
+/**
+ * Parses "open" or "invite-only", nights are open if no type is given
+ */
+pub fn parse_night_type(night_type: Option<String>) -> Result<NightType, Error> {
+
+    match night_type.as_deref().map(|kind| kind.trim().to_lowercase()) {
+        None => Ok(NightType::Open),
+        Some(kind) => match kind.as_str() {
+            "" | "open" => Ok(NightType::Open),
+            "invite-only" | "invite_only" | "inviteonly" => Ok(NightType::InviteOnly),
+            _ => Err(Error::new(std::io::ErrorKind::InvalidInput, format!("unknown rush night type: {}", kind))),
+        },
+    }
+
+}
+
+/**
+ * Builds a rush night from what an admin sent, parsing its times and checking the night ends after it starts
+ */
+pub fn build_rush_night(payload: IncomingRushNight, id: Option<ObjectId>) -> Result<RushNight, Error> {
+
+    let time = string_to_bson_datetime(&payload.time)?;
+    let start_time = payload.start_time.as_deref().map(string_to_bson_datetime).transpose()?;
+    let end_time = payload.end_time.as_deref().map(string_to_bson_datetime).transpose()?;
+
+    if let (Some(start), Some(end)) = (start_time, end_time) {
+        if end <= start {
+            return Err(Error::new(std::io::ErrorKind::InvalidInput, "a rush night has to end after it starts"));
+        }
+    }
+
+    if payload.capacity == Some(0) {
+        return Err(Error::new(std::io::ErrorKind::InvalidInput, "a rush night's capacity has to be more than 0"));
+    }
+
+    Ok(RushNight {
+        id: id,
+        time: time,
+        name: payload.name,
+        start_time: start_time,
+        end_time: end_time,
+        location: payload.location.unwrap_or_default(),
+        description: payload.description.unwrap_or_default(),
+        capacity: payload.capacity,
+        night_type: parse_night_type(payload.night_type)?,
+    })
+
+}
+
+/**
+ * Rushees and comments keep their own copy of a rush night, this brings every copy
+ * that points at the night's id up to date after the night is edited
+ */
+pub async fn sync_night_references(night: &RushNight) -> Result<(), Error> {
+
+    let night_oid = night
+        .id
+        .ok_or(Error::new(std::io::ErrorKind::Other, "rush night does not have an id"))?;
+
+    let night_type = to_bson(&night.night_type)
+        .map_err(|_| Error::new(std::io::ErrorKind::Other, "some issue occurred when serializing the rush night"))?;
+    let bson_night = to_bson(night)
+        .map_err(|_| Error::new(std::io::ErrorKind::Other, "some issue occurred when serializing the rush night"))?;
+
+    let connection = db::get_rushee_client().await;
+
+    // attendance records are the night's fields flattened next to the check-in details
+    let attendance_update = doc! {"$set": {
+        "attendance.$[record].name": night.name.clone(),
+        "attendance.$[record].time": night.time,
+        "attendance.$[record].start_time": night.start_time,
+        "attendance.$[record].end_time": night.end_time,
+        "attendance.$[record].location": night.location.clone(),
+        "attendance.$[record].description": night.description.clone(),
+        "attendance.$[record].capacity": night.capacity.map(|capacity| capacity as i64),
+        "attendance.$[record].night_type": night_type,
+    }};
+
+    connection
+        .update_many(doc! {"attendance._id": night_oid}, attendance_update)
+        .array_filters(vec![doc! {"record._id": night_oid}])
+        .await
+        .map_err(|_| Error::new(std::io::ErrorKind::Other, "couldn't update attendance for the rush night"))?;
+
+    let comment_update = doc! {"$set": {
+        "comments.$[comment].night": bson_night,
+    }};
+
+    connection
+        .update_many(doc! {"comments.night_id": night_oid.to_hex()}, comment_update)
+        .array_filters(vec![doc! {"comment.night_id": night_oid.to_hex()}])
+        .await
+        .map_err(|_| Error::new(std::io::ErrorKind::Other, "couldn't update comments for the rush night"))?;
+
+    Ok(())
+
+}
+
+/**
+ * How many rushees have attendance or comments pointing at a rush night
+ */
+pub async fn count_night_references(night_oid: ObjectId) -> Result<u64, Error> {
+
+    let connection = db::get_rushee_client().await;
+
+    let filter = doc! {"$or": [
+        {"attendance._id": night_oid},
+        {"comments.night_id": night_oid.to_hex()},
+    ]};
+
+    connection
+        .count_documents(filter)
+        .await
+        .map_err(|_| Error::new(std::io::ErrorKind::Other, "couldn't count references to the rush night"))
+
+}
+
+/**
+ * The rush night happening right now, using each night's start and end times
+ * (or its whole local day in the chapter's timezone if it doesn't have them)
+ */
+pub async fn get_current_rush_night() -> Result<RushNight, Error> {
+
+    let now = bson::DateTime::now();
+
+    get_rush_nights()
+        .await?
+        .into_iter()
+        .find(|night| is_during_night(night, &now))
+        .ok_or(Error::new(std::io::ErrorKind::Other, "couldn't match a rush night"))
+
+}
+
+/**
+ * Checks a check-in token and makes sure it was issued for the rush night happening right now
+ */
+pub async fn verify_token_for_tonight(token: &str) -> Result<(CheckinToken, RushNight), Error> {
+
+    let token = verify_checkin_token(token)?;
+
+    let current_night = get_current_rush_night()
+        .await
+        .map_err(|_| Error::new(std::io::ErrorKind::Other, "rush night does not exist"))?;
+
+    if current_night.id.map(|oid| oid.to_hex()) != Some(token.night_id.clone()) {
+        return Err(Error::new(std::io::ErrorKind::Other, "this check-in code isn't for tonight's rush night"));
+    }
+
+    Ok((token, current_night))
+
+}
+
+/**
+ * How many hours after a rush night ends brothers can still leave comments for it,
+ * set through COMMENT_GRACE_HOURS
+ */
+pub fn comment_grace_hours() -> i64 {
+    env::var("COMMENT_GRACE_HOURS")
+        .ok()
+        .and_then(|hours| hours.parse::<i64>().ok())
+        .unwrap_or(DEFAULT_COMMENT_GRACE_HOURS)
+}
+
+/**
+ * Figures out which rush night a comment belongs to.
+ * If no night is given, the rush night happening right now is used,
+ * otherwise the given night is used as long as it has started and its grace window hasn't closed
+ */
+pub async fn resolve_comment_night(night_id: Option<String>) -> Result<RushNight, Error> {
+
+    let rush_nights = get_rush_nights().await?;
+    let now = bson::DateTime::now();
+
+    match night_id {
+
+        Some(id) => {
+
+            let night = rush_nights
+                .into_iter()
+                .find(|night| night.id.map(|oid| oid.to_hex()) == Some(id.clone()));
+
+            match night {
+
+                Some(night) => {
+
+                    if night_start(&night) > now {
+                        return Err(Error::new(std::io::ErrorKind::Other, "this rush night hasn't happened yet"));
+                    }
+
+                    let grace_millis = comment_grace_hours() * 60 * 60 * 1000;
+
+                    if now.timestamp_millis() - night_end(&night).timestamp_millis() > grace_millis {
+                        return Err(Error::new(std::io::ErrorKind::Other, "the comment window for this rush night has closed"));
+                    }
+
+                    Ok(night)
+
+                }
+
+                None => {
+                    Err(Error::new(std::io::ErrorKind::Other, "rush night does not exist"))
+                }
+
+            }
+
+        }
+
+        None => {
+
+            get_current_rush_night().await
+
+        }
+
+    }
+
+}
+
+
+/**
+ * Finds which of the rush nights a stored copy of a night refers to, by id or, for older copies, by local day
+ */
+pub fn match_night<'a>(night: &RushNight, rush_nights: &'a Vec<RushNight>) -> Option<&'a RushNight> {
+
+    match night.id {
+        Some(id) => rush_nights.iter().find(|candidate| candidate.id == Some(id)),
+        None => rush_nights.iter().find(|candidate| same_local_day(&candidate.time, &night.time)),
+    }
+
+}
+
+/**
+ * Something to group rush nights by. Uses the night's id, older copies without one fall back to the local date
+ */
+pub fn night_key(night: &RushNight) -> String {
+
+    match night.id {
+        Some(id) => id.to_hex(),
+        None => local_date(&night.time).to_string(),
+    }
+
+}
+
+
+/**
+ * Records a rushee's attendance for a rush night. Checking in twice for the same night does nothing.
+ * Rushees who aren't invited to an invite-only night are turned away or flagged, see invitations::check_invitation
+ */
+pub async fn record_attendance(gtid: &str, night: &RushNight, source: CheckinSource, checked_in_by: Option<String>) -> Result<CheckinOutcome, Error> {
+
+    let night_oid: ObjectId = night
+        .id
+        .ok_or(Error::new(std::io::ErrorKind::Other, "rush night does not have an id"))?;
+
+    let uninvited = check_invitation(gtid, night).await?;
+
+    let record = AttendanceRecord {
+        night: night.clone(),
+        checked_in_at: Some(bson::DateTime::now()),
+        source: source,
+        checked_in_by: checked_in_by,
+        uninvited: uninvited,
+    };
+
+    let bson_record = to_bson(&record)
+        .map_err(|_| Error::new(std::io::ErrorKind::Other, "some issue occurred when serializing the attendance record"))?;
+
+    let connection = db::get_rushee_client().await;
+
+    // only push if the rushee hasn't already been checked in for this night,
+    // records from before rush nights had ids only have the night's time to go on
+    let filter = doc! {
+        "gtid": gtid,
+        "attendance": {"$not": {"$elemMatch": {"$or": [
+            {"_id": night_oid},
+            {"_id": {"$exists": false}, "time": night.time},
+        ]}}},
+    };
+    let update = doc! {"$push": {
+        "attendance": bson_record,
+    }};
+
+    match connection.update_one(filter, update).await {
+
+        Ok(update_result) => {
+
+            if update_result.modified_count == 0 {
+                return Ok(CheckinOutcome::AlreadyCheckedIn);
+            }
+
+            if let Err(err) = publish_head_count(night).await {
+                println!("Couldn't publish head count: {}", err);
+            }
+
+            if uninvited {
+                Ok(CheckinOutcome::CheckedInUninvited)
+            } else {
+                Ok(CheckinOutcome::CheckedIn)
+            }
+
+        }
+
+        Err(_err) => {
+            Err(Error::new(std::io::ErrorKind::Other, "couldn't update rushee attendance"))
+        }
+
+    }
+
+}
+
+/**
+ * Counts everyone checked in for a rush night and publishes it on the "attendance" channel
+ * so the broadcaster can push a live head count to the admin dashboard
+ */
+pub async fn publish_head_count(night: &RushNight) -> Result<u64, Error> {
+
+    let night_oid = night
+        .id
+        .ok_or(Error::new(std::io::ErrorKind::Other, "rush night does not have an id"))?;
+
+    let connection = db::get_rushee_client().await;
+
+    let count = connection
+        .count_documents(doc! {"attendance._id": night_oid})
+        .await
+        .map_err(|_| Error::new(std::io::ErrorKind::Other, "couldn't count attendance"))?;
+
+    let head_count = serde_json::json!({
+        "night_id": night_oid.to_hex(),
+        "night_name": night.name,
+        "count": count,
+    })
+    .to_string();
+
+    let mut redis = db::get_redis_conn().await.as_ref().clone();
+
+    // latest count is kept so newly connected dashboards get it right away
+    let _: () = redis
+        .set(HEAD_COUNT_KEY, &head_count)
+        .await
+        .map_err(|_| Error::new(std::io::ErrorKind::Other, "couldn't store the head count"))?;
+
+    let _: () = redis
+        .publish(ATTENDANCE_CHANNEL, &head_count)
+        .await
+        .map_err(|_| Error::new(std::io::ErrorKind::Other, "couldn't publish the head count"))?;
+
+    Ok(count)
+
+}
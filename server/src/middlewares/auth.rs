@@ -0,0 +1,49 @@
+/**
+ * Figures out who is making a request.
+ * Admins and bid committee members send their shared key in the x-rush-key header,
+ * everyone else is treated as a regular brother
+ */
+
+use axum::http::HeaderMap;
+use std::env;
+
+const ROLE_KEY_HEADER: &str = "x-rush-key";
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Role {
+    Brother,
+    BidCommittee,
+    Admin,
+}
+
+fn key_matches(provided: &str, env_var: &str) -> bool {
+
+    match env::var(env_var) {
+        Ok(key) => !key.is_empty() && key == provided,
+        Err(_err) => false,
+    }
+
+}
+
+pub fn get_role(headers: &HeaderMap) -> Role {
+
+    let provided = headers
+        .get(ROLE_KEY_HEADER)
+        .and_then(|value| value.to_str().ok())
+        .unwrap_or("");
+
+    if key_matches(provided, "ADMIN_KEY") {
+        return Role::Admin;
+    }
+
+    if key_matches(provided, "BID_COMMITTEE_KEY") {
+        return Role::BidCommittee;
+    }
+
+    Role::Brother
+
+}
+
+pub fn is_admin(headers: &HeaderMap) -> bool {
+    get_role(headers) == Role::Admin
+}
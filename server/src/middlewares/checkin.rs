@@ -0,0 +1,135 @@
+/**
+ * Signed, short lived check-in tokens for rush night attendance.
+ * A token looks like <night id>.<purpose>.<expiry millis>.<hmac>, signed with CHECKIN_SECRET.
+ * "rushee" tokens are shown as a QR code at the door and are redeemed with the rushee's access code,
+ * "door" tokens are handed to the brothers checking people in
+ */
+
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+use std::{env, io::Error};
+
+type HmacSha256 = Hmac<Sha256>;
+
+pub const RUSHEE_TOKEN: &str = "rushee";
+pub const DOOR_TOKEN: &str = "door";
+
+const DEFAULT_RUSHEE_TOKEN_TTL_SECONDS: i64 = 120;
+const DEFAULT_DOOR_TOKEN_TTL_SECONDS: i64 = 6 * 60 * 60;
+
+pub struct CheckinToken {
+    pub night_id: String,
+    pub purpose: String,
+}
+
+fn get_mac() -> Result<HmacSha256, Error> {
+
+    let secret = env::var("CHECKIN_SECRET")
+        .map_err(|_| Error::new(std::io::ErrorKind::Other, "CHECKIN_SECRET is not set"))?;
+
+    HmacSha256::new_from_slice(secret.as_bytes())
+        .map_err(|_| Error::new(std::io::ErrorKind::Other, "invalid CHECKIN_SECRET"))
+
+}
+
+fn token_ttl_seconds(purpose: &str) -> i64 {
+
+    let (env_var, default) = if purpose == DOOR_TOKEN {
+        ("CHECKIN_DOOR_TOKEN_TTL_SECONDS", DEFAULT_DOOR_TOKEN_TTL_SECONDS)
+    } else {
+        ("CHECKIN_TOKEN_TTL_SECONDS", DEFAULT_RUSHEE_TOKEN_TTL_SECONDS)
+    };
+
+    env::var(env_var)
+        .ok()
+        .and_then(|seconds| seconds.parse::<i64>().ok())
+        .unwrap_or(default)
+
+}
+
+fn sign(payload: &str) -> Result<String, Error> {
+
+    let mut mac = get_mac()?;
+    mac.update(payload.as_bytes());
+
+    Ok(hex::encode(mac.finalize().into_bytes()))
+
+}
+
+/**
+ * Issues a token for the given rush night, returns the token and when it expires
+ */
+pub fn issue_checkin_token(night_id: &str, purpose: &str) -> Result<(String, bson::DateTime), Error> {
+
+    if purpose != RUSHEE_TOKEN && purpose != DOOR_TOKEN {
+        return Err(Error::new(std::io::ErrorKind::Other, format!("Invalid token purpose: {}", purpose)));
+    }
+
+    let expires = bson::DateTime::from_millis(
+        bson::DateTime::now().timestamp_millis() + token_ttl_seconds(purpose) * 1000,
+    );
+
+    let payload = format!("{}.{}.{}", night_id, purpose, expires.timestamp_millis());
+    let signature = sign(&payload)?;
+
+    Ok((format!("{}.{}", payload, signature), expires))
+
+}
+
+pub fn verify_checkin_token(token: &str) -> Result<CheckinToken, Error> {
+
+    let invalid = || Error::new(std::io::ErrorKind::Other, "invalid check-in code");
+
+    let parts: Vec<&str> = token.trim().split('.').collect();
+
+    if parts.len() != 4 {
+        return Err(invalid());
+    }
+
+    let payload = format!("{}.{}.{}", parts[0], parts[1], parts[2]);
+    let signature = hex::decode(parts[3]).map_err(|_| invalid())?;
+
+    let mut mac = get_mac()?;
+    mac.update(payload.as_bytes());
+    mac.verify_slice(&signature).map_err(|_| invalid())?;
+
+    let expires_millis = parts[2].parse::<i64>().map_err(|_| invalid())?;
+
+    if expires_millis < bson::DateTime::now().timestamp_millis() {
+        return Err(Error::new(std::io::ErrorKind::Other, "this check-in code has expired"));
+    }
+
+    Ok(CheckinToken {
+        night_id: parts[0].to_string(),
+        purpose: parts[1].to_string(),
+    })
+
+}
+
+/**
+ * What the QR code at the door holds: a link to the check-in page with the token filled in when
+ * CHECKIN_PAGE_URL is set (e.g. https://rush.example.com/attendance), otherwise just the token
+ */
+pub fn checkin_qr_contents(token: &str) -> String {
+
+    match env::var("CHECKIN_PAGE_URL") {
+        Ok(url) if !url.trim().is_empty() => format!("{}?token={}", url.trim(), token),
+        _ => token.to_string(),
+    }
+
+}
+
+/**
+ * Renders a token as an SVG QR code
+ */
+pub fn render_qr_svg(token: &str) -> Result<String, Error> {
+
+    let code = qrcode::QrCode::new(token.as_bytes())
+        .map_err(|_| Error::new(std::io::ErrorKind::Other, "couldn't render the QR code"))?;
+
+    Ok(code
+        .render::<qrcode::render::svg::Color>()
+        .min_dimensions(256, 256)
+        .build())
+
+}
@@ -0,0 +1,134 @@
+/**
+ * Face check-in. Embeddings are stored in the face-embeddings collection and matched with
+ * an Atlas vector search index (FACE_VECTOR_INDEX, cosine similarity on "embedding")
+ */
+
+use futures::stream::StreamExt;
+use mongodb::bson::{doc, Document};
+use std::{env, io::Error};
+
+use crate::controllers::db;
+use crate::models::Rushee::{FaceEmbedding, FaceMatch};
+
+const DEFAULT_FACE_VECTOR_INDEX: &str = "face_embedding_index";
+const DEFAULT_FACE_MATCH_THRESHOLD: f64 = 0.92;
+const DEFAULT_FACE_CONFIRM_THRESHOLD: f64 = 0.85;
+const FACE_CANDIDATES: i64 = 3;
+
+fn env_threshold(env_var: &str, default: f64) -> f64 {
+
+    env::var(env_var)
+        .ok()
+        .and_then(|value| value.parse::<f64>().ok())
+        .unwrap_or(default)
+
+}
+
+/**
+ * Scores at or above this are checked in right away
+ */
+pub fn face_match_threshold() -> f64 {
+    env_threshold("FACE_MATCH_THRESHOLD", DEFAULT_FACE_MATCH_THRESHOLD)
+}
+
+/**
+ * Scores between this and the match threshold need someone to confirm the rushee
+ */
+pub fn face_confirm_threshold() -> f64 {
+    env_threshold("FACE_CONFIRM_THRESHOLD", DEFAULT_FACE_CONFIRM_THRESHOLD)
+}
+
+pub async fn store_face_embedding(gtid: &str, embedding: &Vec<f32>) -> Result<bool, Error> {
+
+    if embedding.is_empty() {
+        return Err(Error::new(std::io::ErrorKind::Other, "face embedding is empty"));
+    }
+
+    let connection = db::get_face_embeddings_client().await;
+
+    let new_embedding = FaceEmbedding {
+        gtid: gtid.to_string(),
+        embedding: embedding.clone(),
+        updated_at: bson::DateTime::now(),
+    };
+
+    let result = connection
+        .replace_one(doc! {"gtid": gtid}, new_embedding)
+        .upsert(true)
+        .await;
+
+    match result {
+
+        Ok(_replace_result) => {
+            Ok(true)
+        }
+
+        Err(_err) => {
+            Err(Error::new(std::io::ErrorKind::Other, "couldn't store the face embedding"))
+        }
+
+    }
+
+}
+
+/**
+ * Closest rushees to an embedding, best match first
+ */
+pub async fn find_face_matches(embedding: &Vec<f32>) -> Result<Vec<FaceMatch>, Error> {
+
+    let index = env::var("FACE_VECTOR_INDEX").unwrap_or(DEFAULT_FACE_VECTOR_INDEX.to_string());
+    let connection = db::get_face_embeddings_client().await;
+
+    let pipeline = vec![
+        doc! {"$vectorSearch": {
+            "index": index,
+            "path": "embedding",
+            "queryVector": embedding.iter().map(|x| *x as f64).collect::<Vec<f64>>(),
+            "numCandidates": FACE_CANDIDATES * 20,
+            "limit": FACE_CANDIDATES,
+        }},
+        doc! {"$project": {
+            "gtid": 1,
+            "score": {"$meta": "vectorSearchScore"},
+        }},
+        doc! {"$lookup": {
+            "from": "rushees",
+            "localField": "gtid",
+            "foreignField": "gtid",
+            "as": "rushee",
+        }},
+        doc! {"$unwind": "$rushee"},
+    ];
+
+    let mut cursor = connection
+        .aggregate(pipeline)
+        .await
+        .map_err(|_| Error::new(std::io::ErrorKind::Other, "couldn't run the face search"))?;
+
+    let mut matches = Vec::<FaceMatch>::new();
+
+    while let Some(result) = cursor.next().await {
+
+        let found: Document = result
+            .map_err(|_| Error::new(std::io::ErrorKind::Other, "couldn't read a face match"))?;
+
+        let rushee = found.get_document("rushee").ok();
+        let field = |name: &str| {
+            rushee
+                .and_then(|r| r.get_str(name).ok())
+                .unwrap_or("")
+                .to_string()
+        };
+
+        matches.push(FaceMatch {
+            gtid: found.get_str("gtid").unwrap_or("").to_string(),
+            name: format!("{} {}", field("first_name"), field("last_name")),
+            image_url: field("image_url"),
+            score: found.get_f64("score").unwrap_or(0.0),
+        });
+
+    }
+
+    Ok(matches)
+
+}
@@ -0,0 +1,168 @@
+/**
+ * Flex-window rushees don't book a PIS timeslot when they sign up. They wait in a queue until an admin
+ * places them into a timeslot with seats left over and enough free interviewers.
+ * Until then their pis_timeslot is UNSCHEDULED
+ */
+
+use std::collections::HashSet;
+use std::io::Error;
+
+use bson::{doc, to_bson, DateTime};
+use futures::stream::StreamExt;
+
+use crate::controllers::db;
+use crate::middlewares::assignment::{full_name, panel_size};
+use crate::models::pis::{BrotherAvailability, FlexPlacementOption, FlexStatus, PISFlexEntry, PISTimeslot};
+use crate::models::Rushee::RusheeModel;
+
+pub const UNSCHEDULED: DateTime = DateTime::MIN;
+
+fn status_bson(status: FlexStatus) -> bson::Bson {
+    to_bson(&status).unwrap_or(bson::Bson::Null)
+}
+
+pub async fn get_flex_queue(filter: bson::Document) -> Result<Vec<PISFlexEntry>, Error> {
+
+    let mut answer = Vec::<PISFlexEntry>::new();
+
+    let connection = db::get_pis_flex_queue_client().await;
+
+    match connection.find(filter).sort(doc! {"joined_at": 1}).await {
+        Ok(mut cursor) => {
+            while let Some(entry) = cursor.next().await {
+                match entry {
+                    Ok(x) => answer.push(x),
+                    Err(_err) => {
+                        return Err(Error::new(std::io::ErrorKind::Other, "there was an error reading the flex queue"))
+                    }
+                }
+            }
+
+            Ok(answer)
+        }
+
+        Err(_err) => Err(Error::new(std::io::ErrorKind::Other, "couldn't fetch the flex queue")),
+    }
+
+}
+
+pub async fn get_flex_entry(gtid: &str) -> Result<Option<PISFlexEntry>, Error> {
+
+    let connection = db::get_pis_flex_queue_client().await;
+
+    connection
+        .find_one(doc! {"gtid": gtid})
+        .await
+        .map_err(|_| Error::new(std::io::ErrorKind::Other, "couldn't fetch the flex queue"))
+
+}
+
+/**
+ * Adds a flex-window rushee to the queue
+ */
+pub async fn queue_flex_rushee(gtid: &str, rushee_name: &str, available: Vec<DateTime>) -> Result<(), Error> {
+
+    let connection = db::get_pis_flex_queue_client().await;
+
+    let entry = PISFlexEntry {
+        gtid: gtid.to_string(),
+        rushee_name: rushee_name.to_string(),
+        joined_at: DateTime::now(),
+        available: available,
+        status: FlexStatus::Queued,
+        time: None,
+        placed_at: None,
+        placed_by: None,
+        confirmation: None,
+    };
+
+    // a rushee who was placed before and needs a new time (e.g. after a no-show) goes back in the queue
+    connection
+        .replace_one(doc! {"gtid": gtid}, entry)
+        .upsert(true)
+        .await
+        .map(|_| ())
+        .map_err(|_| Error::new(std::io::ErrorKind::Other, "couldn't add the rushee to the flex queue"))
+
+}
+
+/**
+ * Records where a flex-window rushee was placed, along with the confirmation they'll see
+ */
+pub async fn mark_placed(gtid: &str, time: DateTime, placed_by: &str, confirmation: &str) -> Result<(), Error> {
+
+    let connection = db::get_pis_flex_queue_client().await;
+
+    connection
+        .update_one(
+            doc! {"gtid": gtid, "status": status_bson(FlexStatus::Queued)},
+            doc! {"$set": {
+                "status": status_bson(FlexStatus::Placed),
+                "time": time,
+                "placed_at": DateTime::now(),
+                "placed_by": placed_by,
+                "confirmation": confirmation,
+            }},
+        )
+        .await
+        .map(|_| ())
+        .map_err(|_| Error::new(std::io::ErrorKind::Other, "couldn't update the flex queue"))
+
+}
+
+/**
+ * The timeslots a queued rushee could be placed into: ones with seats left over, at a time the rushee can do,
+ * where enough brothers are left to make up a full panel. Brothers already on a PIS at that time aren't free,
+ * and the free brothers still have to cover the empty seats on panels already booked there
+ */
+pub fn placement_options(
+    entry: &PISFlexEntry,
+    timeslots: &Vec<PISTimeslot>,
+    availability: &Vec<BrotherAvailability>,
+    rushees: &Vec<RusheeModel>,
+) -> Vec<FlexPlacementOption> {
+
+    let panel_size = panel_size();
+
+    timeslots
+        .iter()
+        .filter(|timeslot| timeslot.num_available > 0)
+        .filter(|timeslot| entry.available.is_empty() || entry.available.contains(&timeslot.time))
+        .filter_map(|timeslot| {
+            let booked: Vec<&RusheeModel> = rushees
+                .iter()
+                .filter(|rushee| rushee.pis_timeslot == timeslot.time)
+                .collect();
+
+            let busy: HashSet<String> = booked
+                .iter()
+                .flat_map(|rushee| rushee.pis_signup.panel.iter())
+                .map(|member| full_name(&member.first_name, &member.last_name).to_lowercase())
+                .collect();
+
+            let brothers_free: Vec<String> = availability
+                .iter()
+                .filter(|brother| brother.available.contains(&timeslot.time))
+                .map(|brother| full_name(&brother.first_name, &brother.last_name))
+                .filter(|name| !busy.contains(&name.to_lowercase()))
+                .collect();
+
+            // rushees already booked here still need their panels filled from the same brothers
+            let seats_to_fill: usize = booked
+                .iter()
+                .map(|rushee| panel_size.saturating_sub(rushee.pis_signup.panel.len()))
+                .sum();
+
+            if brothers_free.len() < seats_to_fill + panel_size {
+                return None
+            }
+
+            Some(FlexPlacementOption {
+                time: timeslot.time,
+                num_available: timeslot.num_available,
+                brothers_free: brothers_free,
+            })
+        })
+        .collect()
+
+}
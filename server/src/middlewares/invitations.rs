@@ -0,0 +1,179 @@
+/**
+ * Invitation lists for invite-only rush nights. Whether uninvited rushees get turned away at check-in
+ * or checked in and flagged is set through UNINVITED_CHECKIN ("flag" by default, or "reject")
+ */
+
+use futures::stream::StreamExt;
+use mongodb::bson::{doc, oid::ObjectId, Document};
+use std::{env, io::Error};
+
+use crate::controllers::db;
+use crate::middlewares::pis;
+use crate::models::misc::{Invitation, NightType, RushNight, RusheeFilter};
+
+const DEFAULT_UNINVITED_CHECKIN: &str = "flag";
+
+/**
+ * Whether rushees who aren't invited should be turned away instead of flagged
+ */
+pub fn reject_uninvited() -> bool {
+    env::var("UNINVITED_CHECKIN")
+        .unwrap_or(DEFAULT_UNINVITED_CHECKIN.to_string())
+        .trim()
+        .eq_ignore_ascii_case("reject")
+}
+
+pub async fn get_invitation(night_id: &str, gtid: &str) -> Result<Option<Invitation>, Error> {
+
+    let connection = db::get_invitations_client().await;
+
+    connection
+        .find_one(doc! {"night_id": night_id, "gtid": gtid})
+        .await
+        .map_err(|_| Error::new(std::io::ErrorKind::Other, "couldn't fetch the invitation"))
+
+}
+
+pub async fn get_invitations(night_id: &str) -> Result<Vec<Invitation>, Error> {
+
+    let mut answer = Vec::<Invitation>::new();
+
+    let connection = db::get_invitations_client().await;
+
+    match connection.find(doc! {"night_id": night_id}).await {
+        Ok(mut cursor) => {
+            while let Some(invitation) = cursor.next().await {
+                match invitation {
+                    Ok(x) => answer.push(x),
+                    Err(_err) => {
+                        return Err(Error::new(std::io::ErrorKind::Other, "there was an error reading an invitation"))
+                    }
+                }
+            }
+
+            Ok(answer)
+        }
+
+        Err(_err) => Err(Error::new(std::io::ErrorKind::Other, "couldn't fetch the invitations")),
+    }
+
+}
+
+/**
+ * Invites rushees to a rush night, rushees who are already invited keep their RSVP.
+ * Returns how many new invitations were made
+ */
+pub async fn invite_rushees(night_id: &str, gtids: &Vec<String>, invited_by: &str) -> Result<u64, Error> {
+
+    let connection = db::get_invitations_client().await;
+    let mut invited = 0;
+
+    for gtid in gtids {
+        // night_id and gtid come from the filter when the invitation is inserted
+        let update = doc! {"$setOnInsert": {
+            "invited_by": invited_by,
+            "invited_at": bson::DateTime::now(),
+            "rsvp": "Pending",
+            "responded_at": null,
+        }};
+
+        let result = connection
+            .update_one(doc! {"night_id": night_id, "gtid": gtid}, update)
+            .upsert(true)
+            .await
+            .map_err(|_| Error::new(std::io::ErrorKind::Other, format!("couldn't invite {}", gtid)))?;
+
+        if result.upserted_id.is_some() {
+            invited += 1;
+        }
+    }
+
+    Ok(invited)
+
+}
+
+/**
+ * Gtids of every rushee matching a filter
+ */
+pub async fn find_matching_rushees(filter: &RusheeFilter) -> Result<Vec<String>, Error> {
+
+    let mut query = Document::new();
+
+    if let Some(class) = &filter.class {
+        query.insert("class", class);
+    }
+
+    if let Some(major) = &filter.major {
+        query.insert("major", major);
+    }
+
+    if let Some(cloud) = &filter.cloud {
+        query.insert("cloud", cloud);
+    }
+
+    if let Some(housing) = &filter.housing {
+        query.insert("housing", housing);
+    }
+
+    if let Some(night_id) = &filter.attended_night_id {
+        let night_oid = ObjectId::parse_str(night_id)
+            .map_err(|_| Error::new(std::io::ErrorKind::InvalidInput, "invalid rush night id"))?;
+
+        query.insert("attendance._id", night_oid);
+    }
+
+    if let Some(min_nights) = filter.min_nights_attended {
+        query.insert("$expr", doc! {"$gte": [{"$size": "$attendance"}, min_nights as i64]});
+    }
+
+    if let Some(pis_status) = &filter.pis_status {
+        query.extend(pis::pis_status_filter(pis_status)?);
+    }
+
+    let mut answer = Vec::<String>::new();
+
+    let connection = db::get_rushee_client().await;
+
+    match connection.find(query).await {
+        Ok(mut cursor) => {
+            while let Some(rushee) = cursor.next().await {
+                match rushee {
+                    Ok(x) => answer.push(x.gtid),
+                    Err(_err) => {
+                        return Err(Error::new(std::io::ErrorKind::Other, "there was an error reading a rushee"))
+                    }
+                }
+            }
+
+            Ok(answer)
+        }
+
+        Err(_err) => Err(Error::new(std::io::ErrorKind::Other, "couldn't fetch the rushees")),
+    }
+
+}
+
+/**
+ * Checks a rushee against a rush night's invitation list before they're checked in.
+ * Open nights let everyone in, returns whether the rushee should be flagged as uninvited
+ */
+pub async fn check_invitation(gtid: &str, night: &RushNight) -> Result<bool, Error> {
+
+    if night.night_type != NightType::InviteOnly {
+        return Ok(false);
+    }
+
+    let night_id = night
+        .id
+        .map(|oid| oid.to_hex())
+        .ok_or(Error::new(std::io::ErrorKind::Other, "rush night does not have an id"))?;
+
+    match get_invitation(&night_id, gtid).await? {
+        Some(_invitation) => Ok(false),
+        None if reject_uninvited() => {
+            Err(Error::new(std::io::ErrorKind::PermissionDenied, "this rushee isn't invited to tonight's rush night"))
+        }
+        None => Ok(true),
+    }
+
+}
@@ -0,0 +1,243 @@
+/**
+ * PIS rooms and meeting links. Admins keep a pool of physical rooms and virtual meeting links, and every
+ * scheduled PIS is given one. A location never hosts more PIS at once than its capacity, where two PIS
+ * overlap if they start less than PIS_LENGTH_MINUTES apart
+ */
+
+use std::env;
+use std::io::Error;
+
+use bson::{doc, oid::ObjectId, to_bson, DateTime};
+use futures::stream::StreamExt;
+
+use crate::controllers::db;
+use crate::middlewares::flex::UNSCHEDULED;
+use crate::models::pis::{LocationKind, PISLocation, PISLocationAssignment};
+
+const DEFAULT_PIS_LENGTH_MINUTES: i64 = 30;
+const MAX_BOOKING_ATTEMPTS: usize = 3;
+
+/**
+ * How long a PIS runs, set through PIS_LENGTH_MINUTES
+ */
+pub fn pis_length_minutes() -> i64 {
+    env::var("PIS_LENGTH_MINUTES")
+        .ok()
+        .and_then(|minutes| minutes.parse::<i64>().ok())
+        .filter(|minutes| *minutes > 0)
+        .unwrap_or(DEFAULT_PIS_LENGTH_MINUTES)
+}
+
+pub fn parse_location_kind(kind: &str) -> Result<LocationKind, Error> {
+    match kind.trim().to_lowercase().as_str() {
+        "room" => Ok(LocationKind::Room),
+        "virtual" => Ok(LocationKind::Virtual),
+        _ => Err(Error::new(std::io::ErrorKind::InvalidInput, format!("unknown PIS location kind: {}", kind))),
+    }
+}
+
+pub async fn get_locations(include_retired: bool) -> Result<Vec<PISLocation>, Error> {
+
+    let mut answer = Vec::<PISLocation>::new();
+
+    let connection = db::get_pis_locations_client().await;
+
+    let filter = if include_retired { doc! {} } else { doc! {"retired": {"$ne": true}} };
+
+    match connection.find(filter).sort(doc! {"kind": 1, "name": 1}).await {
+        Ok(mut cursor) => {
+            while let Some(location) = cursor.next().await {
+                match location {
+                    Ok(x) => answer.push(x),
+                    Err(_err) => {
+                        return Err(Error::new(std::io::ErrorKind::Other, "there was an error reading a PIS location"))
+                    }
+                }
+            }
+
+            Ok(answer)
+        }
+
+        Err(_err) => Err(Error::new(std::io::ErrorKind::Other, "couldn't fetch the PIS locations")),
+    }
+
+}
+
+fn assignment_for(location: &PISLocation) -> PISLocationAssignment {
+    PISLocationAssignment {
+        location_id: location.id.map(|oid| oid.to_hex()).unwrap_or_default(),
+        name: location.name.clone(),
+        kind: location.kind.clone(),
+        link: location.link.clone(),
+        meeting_id: location.meeting_id.clone(),
+    }
+}
+
+/**
+ * Takes the rushee's PIS location away, e.g. when their PIS isn't scheduled anymore
+ */
+pub async fn clear_location(gtid: &str) -> Result<(), Error> {
+
+    let connection = db::get_rushee_client().await;
+
+    connection
+        .update_one(
+            doc! {"gtid": gtid},
+            doc! {"$set": {"pis_location": null, "pis_link": "", "pis_meeting_id": ""}},
+        )
+        .await
+        .map(|_| ())
+        .map_err(|_| Error::new(std::io::ErrorKind::Other, "failed to update rushee"))
+
+}
+
+/**
+ * Tries to book one location for the rushee's PIS in a transaction. Every booking bumps the location's revision,
+ * so two bookings of the same location at once conflict and only one of them goes through.
+ * Ok(false) means the location is full at that time
+ */
+async fn book_location(gtid: &str, time: DateTime, location: &PISLocation) -> Result<bool, Error> {
+
+    let assignment = assignment_for(location);
+
+    let assignment_bson = to_bson(&assignment)
+        .map_err(|_| Error::new(std::io::ErrorKind::Other, "some issue occurred when serializing the PIS location"))?;
+
+    let client = db::get_mongo_client().await;
+    let rushees = db::get_rushee_client().await;
+    let locations = db::get_pis_locations_client().await;
+
+    let length = pis_length_minutes() * 60 * 1000;
+
+    let mut session = client
+        .start_session()
+        .await
+        .map_err(|_| Error::new(std::io::ErrorKind::Other, "some network occurred"))?;
+
+    session
+        .start_transaction()
+        .await
+        .map_err(|_| Error::new(std::io::ErrorKind::Other, "couldn't start booking the PIS location"))?;
+
+    let overlapping = rushees
+        .count_documents(doc! {
+            "gtid": {"$ne": gtid},
+            "pis_location.location_id": assignment.location_id.clone(),
+            "pis_timeslot": {
+                "$gt": DateTime::from_millis(time.timestamp_millis() - length),
+                "$lt": DateTime::from_millis(time.timestamp_millis() + length),
+            },
+        })
+        .session(&mut session)
+        .await;
+
+    match overlapping {
+        Ok(count) if count < location.capacity as u64 => {}
+
+        Ok(_count) => {
+            let _ = session.abort_transaction().await;
+            return Ok(false)
+        }
+
+        Err(_err) => {
+            let _ = session.abort_transaction().await;
+            return Err(Error::new(std::io::ErrorKind::Other, "couldn't check the PIS location's bookings"))
+        }
+    }
+
+    let claim = locations
+        .update_one(doc! {"_id": location.id}, doc! {"$inc": {"revision": 1}})
+        .session(&mut session)
+        .await;
+
+    let book = match claim {
+        Ok(_update_result) => {
+            rushees
+                .update_one(
+                    doc! {"gtid": gtid, "pis_timeslot": time},
+                    doc! {"$set": {
+                        "pis_location": assignment_bson,
+                        "pis_link": assignment.link.clone(),
+                        "pis_meeting_id": assignment.meeting_id.clone(),
+                    }},
+                )
+                .session(&mut session)
+                .await
+        }
+
+        Err(err) => Err(err),
+    };
+
+    match book {
+        Ok(update_result) if update_result.matched_count > 0 => {}
+
+        Ok(_update_result) => {
+            let _ = session.abort_transaction().await;
+            return Err(Error::new(std::io::ErrorKind::Other, "the rushee's PIS was moved while booking a location, try again"))
+        }
+
+        Err(_err) => {
+            let _ = session.abort_transaction().await;
+            return Err(Error::new(std::io::ErrorKind::WouldBlock, "someone else booked this location at the same time"))
+        }
+    }
+
+    session
+        .commit_transaction()
+        .await
+        .map(|_| true)
+        .map_err(|_| Error::new(std::io::ErrorKind::WouldBlock, "someone else booked this location at the same time"))
+
+}
+
+/**
+ * Gives the rushee's PIS a room or meeting link that's free at their time. Their current location is kept
+ * if it's still free, otherwise rooms are tried before meeting links
+ */
+pub async fn allocate_location(gtid: &str) -> Result<Option<PISLocationAssignment>, Error> {
+
+    let rushees = db::get_rushee_client().await;
+
+    let rushee = rushees
+        .find_one(doc! {"gtid": gtid})
+        .await
+        .map_err(|_| Error::new(std::io::ErrorKind::Other, "some network error occurred when fetching the rushee"))?
+        .ok_or_else(|| Error::new(std::io::ErrorKind::NotFound, "No rushee found with that ID"))?;
+
+    if rushee.pis_timeslot == UNSCHEDULED {
+        clear_location(gtid).await?;
+        return Ok(None)
+    }
+
+    let mut candidates = get_locations(false).await?;
+
+    let current = rushee.pis_location.as_ref().map(|location| location.location_id.clone());
+
+    candidates.sort_by_key(|location| {
+        (
+            location.id.map(|oid| oid.to_hex()) != current,
+            location.kind == LocationKind::Virtual,
+        )
+    });
+
+    for location in candidates.iter().filter(|location| location.capacity > 0) {
+        for _attempt in 0..MAX_BOOKING_ATTEMPTS {
+            match book_location(gtid, rushee.pis_timeslot, location).await {
+                Ok(true) => return Ok(Some(assignment_for(location))),
+                Ok(false) => break,
+                Err(err) if err.kind() == std::io::ErrorKind::WouldBlock => continue,
+                Err(err) => return Err(err),
+            }
+        }
+    }
+
+    clear_location(gtid).await?;
+
+    Err(Error::new(std::io::ErrorKind::Other, "no rooms or meeting links are free at this time"))
+
+}
+
+pub fn parse_location_id(id: &str) -> Result<ObjectId, Error> {
+    ObjectId::parse_str(id)
+        .map_err(|_| Error::new(std::io::ErrorKind::InvalidInput, format!("{} isn't a valid location id", id)))
+}
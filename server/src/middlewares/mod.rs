@@ -4,3 +4,15 @@ pub mod timeHelpers;
 pub mod pis;
 pub mod rushee;
 pub mod voting;
+pub mod auth;
+pub mod moderation;
+pub mod checkin;
+pub mod face;
+pub mod invitations;
+pub mod assignment;
+pub mod notifications;
+pub mod waitlist;
+pub mod flex;
+pub mod questions;
+pub mod scorecards;
+pub mod locations;
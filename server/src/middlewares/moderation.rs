@@ -0,0 +1,109 @@
+/**
+ * Server side comment moderation.
+ * Checks comment text against the local term list (moderation_terms.json, or the file at
+ * MODERATION_TERMS_FILE) and for personal info like phone numbers, emails and street addresses
+ */
+
+use regex::{Regex, RegexBuilder};
+use std::{env, fs, sync::OnceLock};
+
+use crate::models::Rushee::ModerationFlag;
+
+const DEFAULT_TERMS: &str = include_str!("../../moderation_terms.json");
+const REDACTED: &str = "[redacted]";
+
+static TERMS_REGEX: OnceLock<Option<Regex>> = OnceLock::new();
+static PII_PATTERNS: OnceLock<Vec<(&'static str, Regex)>> = OnceLock::new();
+
+fn load_terms() -> Vec<String> {
+
+    let raw = env::var("MODERATION_TERMS_FILE")
+        .ok()
+        .and_then(|path| fs::read_to_string(path).ok())
+        .unwrap_or_else(|| DEFAULT_TERMS.to_string());
+
+    serde_json::from_str::<Vec<String>>(&raw).unwrap_or_else(|err| {
+        println!("Couldn't parse moderation terms, falling back to defaults: {}", err);
+        serde_json::from_str(DEFAULT_TERMS).unwrap_or_default()
+    })
+
+}
+
+fn terms_regex() -> &'static Option<Regex> {
+
+    TERMS_REGEX.get_or_init(|| {
+        let terms: Vec<String> = load_terms()
+            .iter()
+            .filter(|term| !term.trim().is_empty())
+            .map(|term| regex::escape(term.trim()))
+            .collect();
+
+        if terms.is_empty() {
+            return None;
+        }
+
+        RegexBuilder::new(&format!(r"\b(?:{})\b", terms.join("|")))
+            .case_insensitive(true)
+            .build()
+            .ok()
+    })
+
+}
+
+fn pii_patterns() -> &'static Vec<(&'static str, Regex)> {
+
+    PII_PATTERNS.get_or_init(|| {
+        vec![
+            ("phone", Regex::new(r"(?:\+?1[\s.-]?)?\(?\b\d{3}\)?[\s.-]?\d{3}[\s.-]?\d{4}\b").unwrap()),
+            ("email", Regex::new(r"\b[\w.+-]+@[\w-]+\.[\w.-]+\b").unwrap()),
+            ("address", RegexBuilder::new(
+                r"\b\d{1,5}\s+(?:[a-z0-9.]+\s+){1,4}(?:street|st|avenue|ave|road|rd|drive|dr|boulevard|blvd|lane|ln|way|court|ct|place|pl|circle|cir|parkway|pkwy)\b\.?"
+            ).case_insensitive(true).build().unwrap()),
+        ]
+    })
+
+}
+
+/**
+ * Everything in the text that should be looked at by an admin
+ */
+pub fn moderate(text: &str) -> Vec<ModerationFlag> {
+
+    let mut flags = Vec::<ModerationFlag>::new();
+
+    if let Some(terms) = terms_regex() {
+        for found in terms.find_iter(text) {
+            flags.push(ModerationFlag {
+                kind: "term".to_string(),
+                matched: found.as_str().to_string(),
+            });
+        }
+    }
+
+    for (kind, pattern) in pii_patterns().iter() {
+        for found in pattern.find_iter(text) {
+            flags.push(ModerationFlag {
+                kind: kind.to_string(),
+                matched: found.as_str().to_string(),
+            });
+        }
+    }
+
+    flags
+
+}
+
+/**
+ * Blanks out every flagged part of the text
+ */
+pub fn redact(text: &str, flags: &Vec<ModerationFlag>) -> String {
+
+    let mut redacted = text.to_string();
+
+    for flag in flags.iter() {
+        redacted = redacted.replace(&flag.matched, REDACTED);
+    }
+
+    redacted
+
+}
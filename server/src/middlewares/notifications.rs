@@ -0,0 +1,91 @@
+/**
+ * Tells brothers about changes to the PIS they're on. Every notification is kept in the
+ * pis-notifications collection and published on the "pis" channel so the broadcaster can push it live
+ */
+
+use futures::stream::StreamExt;
+use mongodb::bson::doc;
+use redis::AsyncCommands;
+use std::io::Error;
+
+use crate::controllers::db;
+use crate::models::pis::{PISBrother, PISNotification};
+
+const PIS_CHANNEL: &str = "pis";
+
+pub async fn notify_brothers(brothers: &Vec<PISBrother>, rushee_gtid: &str, message: &str) -> Result<(), Error> {
+
+    if brothers.is_empty() {
+        return Ok(());
+    }
+
+    let now = bson::DateTime::now();
+
+    let notifications: Vec<PISNotification> = brothers
+        .iter()
+        .map(|brother| PISNotification {
+            first_name: brother.first_name.clone(),
+            last_name: brother.last_name.clone(),
+            rushee_gtid: rushee_gtid.to_string(),
+            message: message.to_string(),
+            time: now,
+        })
+        .collect();
+
+    let connection = db::get_pis_notifications_client().await;
+
+    connection
+        .insert_many(&notifications)
+        .await
+        .map_err(|_| Error::new(std::io::ErrorKind::Other, "couldn't save the PIS notifications"))?;
+
+    let live_update = serde_json::json!({
+        "rushee_gtid": rushee_gtid,
+        "brothers": brothers,
+        "message": message,
+    })
+    .to_string();
+
+    let mut redis = db::get_redis_conn().await.as_ref().clone();
+
+    let _: () = redis
+        .publish(PIS_CHANNEL, live_update)
+        .await
+        .map_err(|_| Error::new(std::io::ErrorKind::Other, "couldn't publish the PIS notification"))?;
+
+    Ok(())
+
+}
+
+/**
+ * A brother's PIS notifications, newest first
+ */
+pub async fn get_notifications(first_name: &str, last_name: &str) -> Result<Vec<PISNotification>, Error> {
+
+    let mut answer = Vec::<PISNotification>::new();
+
+    let connection = db::get_pis_notifications_client().await;
+
+    let result = connection
+        .find(doc! {"first_name": first_name, "last_name": last_name})
+        .sort(doc! {"time": -1})
+        .await;
+
+    match result {
+        Ok(mut cursor) => {
+            while let Some(notification) = cursor.next().await {
+                match notification {
+                    Ok(x) => answer.push(x),
+                    Err(_err) => {
+                        return Err(Error::new(std::io::ErrorKind::Other, "there was an error reading a notification"))
+                    }
+                }
+            }
+
+            Ok(answer)
+        }
+
+        Err(_err) => Err(Error::new(std::io::ErrorKind::Other, "couldn't fetch the notifications")),
+    }
+
+}
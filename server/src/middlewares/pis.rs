@@ -1,111 +1,446 @@
 use std::io::Error;
 
-use bson::{doc, DateTime};
+use bson::{doc, to_bson, DateTime};
+use chrono::{Duration, NaiveDate, NaiveTime, TimeZone};
+use futures::stream::StreamExt;
 
 use crate::controllers::db;
+use crate::middlewares::timeHelpers::{chapter_timezone, string_to_bson_datetime};
+use crate::middlewares::flex::UNSCHEDULED;
+use crate::models::pis::{
+    BrotherAvailability, IncomingTimeslotGeneration, PISOutcome, PISReschedule, PISStatusRecord, PISTimeslot, PanelRole,
+    WaitlistStatus,
+};
+
+const MAX_GENERATED_TIMESLOTS: usize = 1000;
+const MAX_GENERATED_DAYS: i64 = 366;
 
 pub async fn take_pis_timeslot(time: DateTime) -> Result<bool, Error> {
 
     let connection = db::get_pis_timeslots_client().await;
 
-    let find_query= doc! {"time": time};
-    let find = connection.find_one(find_query).await;
+    // only take a seat if there is one left, in one step so two signups can't both get the last seat
+    let query = doc! {"time": time, "num_available": {"$gt": 0}};
+    let update = doc! {"$inc": {"num_available": -1}};
 
-    match find {
+    match connection.update_one(query, update).await {
 
-        Ok(find_result) => {
+        Ok(update_result) => {
 
-            match find_result {
+            if update_result.matched_count > 0 {
+                return Ok(true)
+            }
 
-                Some(timeslot) => {
+            match connection.find_one(doc! {"time": time}).await {
+                Ok(Some(_timeslot)) => Err(Error::new(std::io::ErrorKind::Other, "All slots for this time are taken")),
+                Ok(None) => Err(Error::new(std::io::ErrorKind::Other, "PIS timeslot does not exist")),
+                Err(_err) => Err(Error::new(std::io::ErrorKind::Other, "some network occurred")),
+            }
 
-                    if timeslot.num_available <= 0 {
-                        return Err(Error::new(std::io::ErrorKind::Other, "All slots for this time are taken"))
-                    }
+        }
 
-                    let query = doc! {"time": time};
-                    let update = doc! {"$set": doc! {
-                        "num_available": timeslot.num_available - 1
-                    }};
+        Err(_err) => {
+            return Err(Error::new(std::io::ErrorKind::Other, "couldn't update PIS timeslot"))
+        }
 
-                    let result = connection.update_one(query, update).await;
+    }
+
+}
 
-                    match result {
+pub async fn get_pis_timeslots() -> Result<Vec<PISTimeslot>, Error> {
+
+    let mut answer = Vec::<PISTimeslot>::new();
+
+    let connection = db::get_pis_timeslots_client().await;
 
-                        Ok(_update_result) => {
-                            return Ok(true)
-                        }
+    match connection.find(doc! {}).await {
 
-                        Err(_err) => {
-                            return Err(Error::new(std::io::ErrorKind::Other, "couldn't update PIS timeslot"))
-                        }
+        Ok(mut cursor) => {
 
+            while let Some(timeslot) = cursor.next().await {
+                match timeslot {
+                    Ok(x) => answer.push(x),
+                    Err(_err) => {
+                        return Err(Error::new(std::io::ErrorKind::Other, "there was an error reading a PIS timeslot"))
                     }
-                    
-                }
-    
-                None => {
-                    return Err(Error::new(std::io::ErrorKind::Other, "PIS timeslot does not exist"))
                 }
             }
 
+            answer.sort_by_key(|timeslot| timeslot.time);
+
+            Ok(answer)
+
         }
 
         Err(_err) => {
-            return Err(Error::new(std::io::ErrorKind::Other, "some network occurred"))
+            Err(Error::new(std::io::ErrorKind::Other, "some network occurred"))
         }
 
     }
 
 }
 
-pub async fn vacate_pis_timeslot(time: DateTime) -> Result<bool, Error> {
+/**
+ * Makes sure every time is one of the PIS timeslots
+ */
+pub async fn check_times_are_timeslots(times: &Vec<DateTime>) -> Result<(), Error> {
 
-    let connection = db::get_pis_timeslots_client().await;
+    let timeslots = get_pis_timeslots().await?;
 
-    let find_query= doc! {"time": time};
-    let find = connection.find_one(find_query).await;
-
-    match find {
-
-        Ok(find_result) => {
+    for time in times.iter() {
+        if !timeslots.iter().any(|timeslot| timeslot.time == *time) {
+            return Err(Error::new(std::io::ErrorKind::InvalidInput, format!("{} isn't a PIS timeslot", time)))
+        }
+    }
 
-            match find_result {
+    Ok(())
 
-                Some(timeslot) => {
+}
 
-                    let query = doc! {"time": time};
-                    let update = doc! {"$set": doc! {
-                        "num_available": timeslot.num_available + 1
-                    }};
+pub async fn get_brother_availability() -> Result<Vec<BrotherAvailability>, Error> {
 
-                    let result = connection.update_one(query, update).await;
+    let mut answer = Vec::<BrotherAvailability>::new();
 
-                    match result {
+    let connection = db::get_brother_availability_client().await;
 
-                        Ok(_update_result) => {
-                            return Ok(true)
-                        }
+    match connection.find(doc! {}).await {
 
-                        Err(_err) => {
-                            return Err(Error::new(std::io::ErrorKind::Other, "couldn't update PIS timeslot"))
-                        }
+        Ok(mut cursor) => {
 
+            while let Some(availability) = cursor.next().await {
+                match availability {
+                    Ok(x) => answer.push(x),
+                    Err(_err) => {
+                        return Err(Error::new(std::io::ErrorKind::Other, "there was an error reading a brother's availability"))
                     }
-                    
-                }
-    
-                None => {
-                    return Err(Error::new(std::io::ErrorKind::Other, "PIS timeslot does not exist"))
                 }
             }
 
+            Ok(answer)
+
+        }
+
+        Err(_err) => {
+            Err(Error::new(std::io::ErrorKind::Other, "some network occurred"))
+        }
+
+    }
+
+}
+
+/**
+ * Parses a PIS panel role, "lead" or "note-taker"
+ */
+pub fn parse_panel_role(role: Option<String>) -> Result<Option<PanelRole>, Error> {
+
+    match role.as_deref().map(|role| role.trim().to_lowercase()) {
+        None => Ok(None),
+        Some(role) => match role.as_str() {
+            "" => Ok(None),
+            "lead" => Ok(Some(PanelRole::Lead)),
+            "note-taker" | "note_taker" | "notetaker" => Ok(Some(PanelRole::NoteTaker)),
+            _ => Err(Error::new(std::io::ErrorKind::InvalidInput, format!("unknown PIS panel role: {}", role))),
+        },
+    }
+
+}
+
+/**
+ * Moves a rushee's PIS from one timeslot to another in a single transaction: reserves a seat at the new time
+ * (or, with seat_held, claims the waitlist seat being held for the rushee), gives the seat at the old time back,
+ * moves the rushee and records the move in their PIS history. Nothing changes if any step fails
+ */
+pub async fn move_pis_timeslot(gtid: &str, entry: &PISReschedule, seat_held: bool) -> Result<(), Error> {
+
+    let history_bson = to_bson(entry)
+        .map_err(|_| Error::new(std::io::ErrorKind::Other, "some issue occurred when serializing the reschedule"))?;
+
+    let client = db::get_mongo_client().await;
+    let timeslots = db::get_pis_timeslots_client().await;
+    let rushees = db::get_rushee_client().await;
+    let waitlist = db::get_pis_waitlist_client().await;
+
+    let mut session = client
+        .start_session()
+        .await
+        .map_err(|_| Error::new(std::io::ErrorKind::Other, "some network occurred"))?;
+
+    session
+        .start_transaction()
+        .await
+        .map_err(|_| Error::new(std::io::ErrorKind::Other, "couldn't start the reschedule"))?;
+
+    if seat_held {
+        // claiming the hold in the same transaction means expire_holds can't give the seat away mid-move
+        let claim = waitlist
+            .find_one_and_update(
+                doc! {
+                    "gtid": gtid,
+                    "time": entry.to,
+                    "status": to_bson(&WaitlistStatus::Offered).unwrap_or_default(),
+                    "hold_expires_at": {"$gt": DateTime::now()},
+                },
+                doc! {"$set": {"status": to_bson(&WaitlistStatus::Accepted).unwrap_or_default()}},
+            )
+            .session(&mut session)
+            .await;
+
+        if !matches!(claim, Ok(Some(_))) {
+            let _ = session.abort_transaction().await;
+            return Err(Error::new(std::io::ErrorKind::Other, "there's no seat being held for you at this time, it may have expired"))
+        }
+    } else {
+        let reserve = timeslots
+            .update_one(
+                doc! {"time": entry.to, "num_available": {"$gt": 0}},
+                doc! {"$inc": {"num_available": -1}},
+            )
+            .session(&mut session)
+            .await;
+
+        if !matches!(reserve, Ok(ref update_result) if update_result.modified_count > 0) {
+            let _ = session.abort_transaction().await;
+            return Err(Error::new(std::io::ErrorKind::Other, "All slots for this time are taken"))
+        }
+    }
+
+    // the old timeslot may have been deleted since, in which case there's nothing to give back
+    let release = timeslots
+        .update_one(doc! {"time": entry.from}, doc! {"$inc": {"num_available": 1}})
+        .session(&mut session)
+        .await;
+
+    if release.is_err() {
+        let _ = session.abort_transaction().await;
+        return Err(Error::new(std::io::ErrorKind::Other, "couldn't release the old PIS timeslot"))
+    }
+
+    // only move the rushee if nobody else moved them in the meantime
+    let move_rushee = rushees
+        .update_one(
+            doc! {"gtid": gtid, "pis_timeslot": entry.from},
+            doc! {
+                // a rescheduled PIS hasn't happened yet, whatever it was marked before is in pis_status_history
+                "$set": {"pis_timeslot": entry.to, "pis_signup.time": entry.to, "pis_status": null},
+                "$push": {"pis_history": history_bson},
+            },
+        )
+        .session(&mut session)
+        .await;
+
+    match move_rushee {
+        Ok(update_result) if update_result.matched_count > 0 => {}
+
+        Ok(_update_result) => {
+            let _ = session.abort_transaction().await;
+            return Err(Error::new(std::io::ErrorKind::Other, "the rushee's PIS was changed while rescheduling, try again"))
+        }
+
+        Err(_err) => {
+            let _ = session.abort_transaction().await;
+            return Err(Error::new(std::io::ErrorKind::Other, "failed to update rushee"))
+        }
+    }
+
+    session
+        .commit_transaction()
+        .await
+        .map_err(|_| Error::new(std::io::ErrorKind::Other, "couldn't save the reschedule"))
+
+}
+
+/**
+ * Takes a rushee off their PIS timeslot in a single transaction, e.g. after a no-show: gives the seat back,
+ * leaves the rushee without a PIS time and records it in their PIS history
+ */
+pub async fn release_pis_timeslot(gtid: &str, from: DateTime, requested_by: &str, reason: &str) -> Result<(), Error> {
+
+    let entry = PISReschedule {
+        from: from,
+        to: UNSCHEDULED,
+        rescheduled_at: DateTime::now(),
+        requested_by: Some(requested_by.to_string()),
+        reason: Some(reason.to_string()),
+    };
+
+    let history_bson = to_bson(&entry)
+        .map_err(|_| Error::new(std::io::ErrorKind::Other, "some issue occurred when serializing the reschedule"))?;
+
+    let client = db::get_mongo_client().await;
+    let timeslots = db::get_pis_timeslots_client().await;
+    let rushees = db::get_rushee_client().await;
+
+    let mut session = client
+        .start_session()
+        .await
+        .map_err(|_| Error::new(std::io::ErrorKind::Other, "some network occurred"))?;
+
+    session
+        .start_transaction()
+        .await
+        .map_err(|_| Error::new(std::io::ErrorKind::Other, "couldn't start releasing the PIS timeslot"))?;
+
+    let release = timeslots
+        .update_one(doc! {"time": from}, doc! {"$inc": {"num_available": 1}})
+        .session(&mut session)
+        .await;
+
+    if release.is_err() {
+        let _ = session.abort_transaction().await;
+        return Err(Error::new(std::io::ErrorKind::Other, "couldn't release the PIS timeslot"))
+    }
+
+    let move_rushee = rushees
+        .update_one(
+            doc! {"gtid": gtid, "pis_timeslot": from},
+            doc! {
+                "$set": {"pis_timeslot": UNSCHEDULED, "pis_signup.time": UNSCHEDULED},
+                "$push": {"pis_history": history_bson},
+            },
+        )
+        .session(&mut session)
+        .await;
+
+    match move_rushee {
+        Ok(update_result) if update_result.matched_count > 0 => {}
+
+        Ok(_update_result) => {
+            let _ = session.abort_transaction().await;
+            return Err(Error::new(std::io::ErrorKind::Other, "the rushee's PIS was changed in the meantime, try again"))
         }
 
         Err(_err) => {
-            return Err(Error::new(std::io::ErrorKind::Other, "some network occurred"))
+            let _ = session.abort_transaction().await;
+            return Err(Error::new(std::io::ErrorKind::Other, "failed to update rushee"))
+        }
+    }
+
+    session
+        .commit_transaction()
+        .await
+        .map_err(|_| Error::new(std::io::ErrorKind::Other, "couldn't release the PIS timeslot"))
+
+}
+
+/**
+ * Parses whether a PIS happened: "completed", "no-show" or "cancelled"
+ */
+pub fn parse_pis_outcome(status: &str) -> Result<PISOutcome, Error> {
+
+    match status.trim().to_lowercase().replace('_', "-").as_str() {
+        "completed" => Ok(PISOutcome::Completed),
+        "no-show" | "noshow" => Ok(PISOutcome::NoShow),
+        "cancelled" | "canceled" => Ok(PISOutcome::Cancelled),
+        _ => Err(Error::new(std::io::ErrorKind::InvalidInput, format!("unknown PIS status: {}", status))),
+    }
+
+}
+
+/**
+ * Hides who marked a PIS, for blind review
+ */
+pub fn anonymize_pis_status(status: &mut Option<PISStatusRecord>, history: &mut Vec<PISStatusRecord>) {
+    for record in status.iter_mut().chain(history.iter_mut()) {
+        record.marked_by = "Interviewer".to_string();
+    }
+}
+
+/**
+ * Query for rushees whose PIS has a status, or "pending" for ones that haven't been marked yet
+ */
+pub fn pis_status_filter(status: &str) -> Result<bson::Document, Error> {
+
+    if status.trim().to_lowercase() == "pending" {
+        return Ok(doc! {"pis_status": null})
+    }
+
+    let outcome = to_bson(&parse_pis_outcome(status)?)
+        .map_err(|_| Error::new(std::io::ErrorKind::Other, "some issue occurred when serializing the PIS status"))?;
+
+    Ok(doc! {"pis_status.status": outcome})
+
+}
+
+/**
+ * Works out the times of every timeslot a bulk generation asks for. Each day gets timeslots every
+ * interval_minutes from daily_start, as long as the whole PIS fits before daily_end.
+ * Timeslots that overlap a blackout window are skipped
+ */
+pub fn generate_timeslot_times(request: &IncomingTimeslotGeneration) -> Result<Vec<DateTime>, Error> {
+
+    let invalid = |message: String| Error::new(std::io::ErrorKind::InvalidInput, message);
+
+    let start_date = NaiveDate::parse_from_str(request.start_date.trim(), "%Y-%m-%d")
+        .map_err(|_| invalid(format!("Invalid date: {}", request.start_date)))?;
+    let end_date = NaiveDate::parse_from_str(request.end_date.trim(), "%Y-%m-%d")
+        .map_err(|_| invalid(format!("Invalid date: {}", request.end_date)))?;
+    let daily_start = NaiveTime::parse_from_str(request.daily_start.trim(), "%H:%M")
+        .map_err(|_| invalid(format!("Invalid time: {}", request.daily_start)))?;
+    let daily_end = NaiveTime::parse_from_str(request.daily_end.trim(), "%H:%M")
+        .map_err(|_| invalid(format!("Invalid time: {}", request.daily_end)))?;
+
+    if end_date < start_date {
+        return Err(invalid("end_date is before start_date".to_string()))
+    }
+
+    if (end_date - start_date).num_days() >= MAX_GENERATED_DAYS {
+        return Err(invalid(format!("can't generate timeslots for more than {} days at once", MAX_GENERATED_DAYS)))
+    }
+
+    if daily_end <= daily_start {
+        return Err(invalid("daily_end has to be after daily_start".to_string()))
+    }
+
+    if request.interval_minutes == 0 {
+        return Err(invalid("interval_minutes has to be more than 0".to_string()))
+    }
+
+    if request.capacity <= 0 {
+        return Err(invalid("capacity has to be more than 0".to_string()))
+    }
+
+    let mut blackouts = Vec::<(DateTime, DateTime)>::new();
+
+    for blackout in request.blackouts.iter() {
+        let start = string_to_bson_datetime(&blackout.start)?;
+        let end = string_to_bson_datetime(&blackout.end)?;
+
+        if end <= start {
+            return Err(invalid(format!("blackout ending {} ends before it starts", blackout.end)))
+        }
+
+        blackouts.push((start, end));
+    }
+
+    let interval = Duration::minutes(request.interval_minutes as i64);
+    let mut times = Vec::<DateTime>::new();
+
+    for date in start_date.iter_days().take_while(|date| *date <= end_date) {
+
+        let mut slot_start = date.and_time(daily_start);
+        let day_end = date.and_time(daily_end);
+
+        while slot_start + interval <= day_end {
+
+            // times skipped over by a daylight saving change don't get a timeslot
+            if let Some(local) = chapter_timezone().from_local_datetime(&slot_start).earliest() {
+                let time = DateTime::from_millis(local.timestamp_millis());
+                let end = DateTime::from_millis(time.timestamp_millis() + interval.num_milliseconds());
+
+                if !blackouts.iter().any(|(blackout_start, blackout_end)| time < *blackout_end && end > *blackout_start) {
+                    times.push(time);
+                }
+            }
+
+            if times.len() > MAX_GENERATED_TIMESLOTS {
+                return Err(invalid(format!("that would make more than {} timeslots", MAX_GENERATED_TIMESLOTS)))
+            }
+
+            slot_start = slot_start + interval;
         }
 
     }
 
-}
\ No newline at end of file
+    Ok(times)
+
+}
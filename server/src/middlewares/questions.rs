@@ -0,0 +1,333 @@
+/**
+ * The PIS question bank. Questions have stable ids and a display order, and editing one bumps its version
+ * (the old wording goes into its history) so answers always show the wording the rushee was asked.
+ * Answers are checked against their question's type before they're saved, and each answer has a revision
+ * so interviewers editing the same PIS at once can't overwrite each other
+ */
+
+use std::fmt;
+use std::io::Error;
+
+use bson::{doc, oid::ObjectId, to_bson, DateTime};
+use futures::stream::StreamExt;
+
+use crate::controllers::db;
+use crate::models::pis::PISQuestion;
+use crate::models::Rushee::{IncomingPISAnswer, PisResponse};
+
+pub const QUESTION_TYPES: [&str; 4] = ["MC", "Short", "Long", "Number"];
+
+const SHORT_ANSWER_LENGTH: usize = 200;
+const LONG_ANSWER_LENGTH: usize = 5000;
+const MAX_SAVE_ATTEMPTS: usize = 5;
+
+fn invalid(message: String) -> Error {
+    Error::new(std::io::ErrorKind::InvalidInput, message)
+}
+
+pub fn check_question_type(question_type: &str) -> Result<(), Error> {
+
+    if QUESTION_TYPES.contains(&question_type) {
+        return Ok(())
+    }
+
+    Err(invalid(format!(
+        "unknown question type {}, has to be one of {}",
+        question_type,
+        QUESTION_TYPES.join(", ")
+    )))
+
+}
+
+/**
+ * The answers an MC question accepts, yes/no unless the question lists its own
+ */
+pub fn choices(question: &PISQuestion) -> Vec<String> {
+    if question.options.is_empty() {
+        vec!["Yes".to_string(), "No".to_string()]
+    } else {
+        question.options.clone()
+    }
+}
+
+/**
+ * Checks an answer against its question's type. Blank answers are fine unless the question is required
+ */
+pub fn check_answer(question: &PISQuestion, answer: &str) -> Result<(), Error> {
+
+    let answer = answer.trim();
+
+    if answer.is_empty() {
+        if question.required {
+            return Err(invalid(format!("\"{}\" has to be answered", question.question)))
+        }
+
+        return Ok(())
+    }
+
+    match question.question_type.as_str() {
+        "MC" if !choices(question).iter().any(|choice| choice == answer) => Err(invalid(format!(
+            "\"{}\" has to be answered with one of {}",
+            question.question,
+            choices(question).join(", ")
+        ))),
+
+        "Short" if answer.chars().count() > SHORT_ANSWER_LENGTH => Err(invalid(format!(
+            "the answer to \"{}\" can't be longer than {} characters",
+            question.question, SHORT_ANSWER_LENGTH
+        ))),
+
+        "Long" if answer.chars().count() > LONG_ANSWER_LENGTH => Err(invalid(format!(
+            "the answer to \"{}\" can't be longer than {} characters",
+            question.question, LONG_ANSWER_LENGTH
+        ))),
+
+        "Number" if answer.parse::<f64>().is_err() => Err(invalid(format!(
+            "the answer to \"{}\" has to be a number",
+            question.question
+        ))),
+
+        _ => Ok(()),
+    }
+
+}
+
+/**
+ * The question bank in display order, leaving out deleted questions unless asked for
+ */
+pub async fn get_questions(include_archived: bool) -> Result<Vec<PISQuestion>, Error> {
+
+    let mut answer = Vec::<PISQuestion>::new();
+
+    let connection = db::get_pis_questions_client().await;
+
+    let filter = if include_archived { doc! {} } else { doc! {"archived": {"$ne": true}} };
+
+    match connection.find(filter).sort(doc! {"order": 1}).await {
+        Ok(mut cursor) => {
+            while let Some(question) = cursor.next().await {
+                match question {
+                    Ok(x) => answer.push(x),
+                    Err(_err) => {
+                        return Err(Error::new(std::io::ErrorKind::Other, "there was an error reading a PIS question"))
+                    }
+                }
+            }
+
+            Ok(answer)
+        }
+
+        Err(_err) => Err(Error::new(std::io::ErrorKind::Other, "couldn't fetch the PIS questions")),
+    }
+
+}
+
+pub async fn get_question(id: &str) -> Result<PISQuestion, Error> {
+
+    let oid = ObjectId::parse_str(id).map_err(|_| invalid(format!("{} isn't a valid question id", id)))?;
+
+    let connection = db::get_pis_questions_client().await;
+
+    match connection.find_one(doc! {"_id": oid}).await {
+        Ok(Some(question)) => Ok(question),
+        Ok(None) => Err(invalid("PIS question does not exist".to_string())),
+        Err(_err) => Err(Error::new(std::io::ErrorKind::Other, "couldn't fetch the PIS question")),
+    }
+
+}
+
+/**
+ * Where a new question goes when it isn't given an order: after every other question
+ */
+pub async fn next_order() -> Result<u32, Error> {
+    let questions = get_questions(true).await?;
+    Ok(questions.iter().map(|question| question.order + 1).max().unwrap_or(0))
+}
+
+#[derive(Debug)]
+pub struct AnswerSaveError {
+    pub code: String,
+    pub message: String,
+    // for conflicts, the answers as they are now so the writer can merge their edits
+    pub current: Vec<PisResponse>,
+}
+
+impl fmt::Display for AnswerSaveError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}: {}", self.code, self.message)
+    }
+}
+
+impl std::error::Error for AnswerSaveError {}
+
+fn save_error(code: &str, message: String) -> AnswerSaveError {
+    AnswerSaveError {
+        code: code.to_string(),
+        message: message,
+        current: Vec::new(),
+    }
+}
+
+fn question_id(question: &PISQuestion) -> Option<String> {
+    question.id.map(|oid| oid.to_hex())
+}
+
+/**
+ * Applies answers on top of the rushee's current PIS answers. Each answer is tied to its question (by id, or
+ * by its current wording for older clients) and checked against its type. An answer that says which revision
+ * it started from is a conflict if someone else has changed that answer since.
+ * Answers that didn't change keep their revision
+ */
+fn merge_answers(
+    current: &Vec<PisResponse>,
+    answers: &Vec<IncomingPISAnswer>,
+    questions: &Vec<PISQuestion>,
+    updated_by: &Option<String>,
+) -> Result<Vec<PisResponse>, AnswerSaveError> {
+
+    let mut merged = current.clone();
+    let mut conflicts = Vec::<PisResponse>::new();
+
+    for incoming in answers.iter() {
+        let question = questions.iter().find(|question| match &incoming.question_id {
+            Some(id) => question_id(question).as_deref() == Some(id.as_str()),
+            None => question.question == incoming.question,
+        });
+
+        let question = match question {
+            Some(question) => question,
+            None => {
+                return Err(save_error("INVALID_ANSWER", format!("\"{}\" isn't a PIS question", incoming.question)))
+            }
+        };
+
+        check_answer(question, &incoming.answer).map_err(|err| save_error("INVALID_ANSWER", err.to_string()))?;
+
+        let id = question_id(question);
+        let answer = incoming.answer.trim().to_string();
+
+        // answers saved before the question bank had ids are matched on their wording
+        let existing = merged.iter_mut().find(|response| match &response.question_id {
+            Some(_) => response.question_id == id,
+            None => response.question == question.question,
+        });
+
+        match existing {
+            Some(response) => {
+                if incoming.revision.map_or(false, |revision| revision != response.revision) {
+                    conflicts.push(response.clone());
+                    continue;
+                }
+
+                if response.answer == answer && response.question_id == id {
+                    continue;
+                }
+
+                response.question = question.question.clone();
+                response.answer = answer;
+                response.question_id = id;
+                response.version = Some(question.version);
+                response.revision += 1;
+                response.updated_by = updated_by.clone();
+                response.updated_at = Some(DateTime::now());
+            }
+
+            None => {
+                if incoming.revision.map_or(false, |revision| revision != 0) {
+                    return Err(save_error(
+                        "INVALID_ANSWER",
+                        format!("\"{}\" hasn't been answered yet, start from revision 0", question.question),
+                    ))
+                }
+
+                merged.push(PisResponse {
+                    question: question.question.clone(),
+                    answer: answer,
+                    question_id: id,
+                    version: Some(question.version),
+                    revision: 1,
+                    updated_by: updated_by.clone(),
+                    updated_at: Some(DateTime::now()),
+                });
+            }
+        }
+    }
+
+    if !conflicts.is_empty() {
+        return Err(AnswerSaveError {
+            code: "PIS_CONFLICT".to_string(),
+            message: format!("{} answers were changed by someone else since you loaded them", conflicts.len()),
+            current: conflicts,
+        })
+    }
+
+    Ok(merged)
+
+}
+
+/**
+ * Saves answers to a rushee's PIS in one atomic write. The write only goes through if nobody else saved
+ * in the meantime (checked with the rushee's pis_revision), otherwise the answers are merged onto the newer
+ * save and tried again, so people editing different questions never overwrite each other.
+ * With check_required, every required question has to be answered once the answers are applied
+ */
+pub async fn save_answers(
+    gtid: &str,
+    answers: &Vec<IncomingPISAnswer>,
+    updated_by: &Option<String>,
+    check_required: bool,
+) -> Result<Vec<PisResponse>, AnswerSaveError> {
+
+    let questions = get_questions(false).await.map_err(|err| save_error("NETWORK", err.to_string()))?;
+
+    let connection = db::get_rushee_client().await;
+
+    for _attempt in 0..MAX_SAVE_ATTEMPTS {
+
+        let rushee = connection
+            .find_one(doc! {"gtid": gtid})
+            .await
+            .map_err(|_| save_error("NETWORK", "some network error occurred when fetching the rushee".to_string()))?
+            .ok_or_else(|| save_error("RUSHEE_NOT_FOUND", "No rushee found with that ID".to_string()))?;
+
+        let merged = merge_answers(&rushee.pis, answers, &questions, updated_by)?;
+
+        if check_required {
+            for question in questions.iter().filter(|question| question.required) {
+                let answered = merged
+                    .iter()
+                    .any(|response| response.question_id == question_id(question) && !response.answer.is_empty());
+
+                if !answered {
+                    return Err(save_error("INVALID_ANSWER", format!("\"{}\" has to be answered", question.question)))
+                }
+            }
+        }
+
+        let merged_bson = to_bson(&merged)
+            .map_err(|_| save_error("INVALID_ANSWER", "couldn't make the pis responses into a bson file".to_string()))?;
+
+        // rushees saved before PIS answers had revisions don't have the field at all
+        let revision_filter = if rushee.pis_revision == 0 {
+            doc! {"$in": [0, bson::Bson::Null]}
+        } else {
+            doc! {"$eq": rushee.pis_revision}
+        };
+
+        let update = connection
+            .update_one(
+                doc! {"gtid": gtid, "pis_revision": revision_filter},
+                doc! {"$set": {"pis": merged_bson}, "$inc": {"pis_revision": 1}},
+            )
+            .await
+            .map_err(|_| save_error("NETWORK", "failed to save the pis responses".to_string()))?;
+
+        if update.matched_count > 0 {
+            return Ok(merged)
+        }
+
+    }
+
+    Err(save_error("PIS_BUSY", "the PIS is being edited by a lot of people at once, try again".to_string()))
+
+}
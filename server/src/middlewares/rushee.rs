@@ -1,5 +1,6 @@
-use crate::{controllers::db, models::Rushee::RusheeModel};
-use mongodb::bson::doc;
+use crate::{controllers::db, models::Rushee::{Comment, Pseudonym, RusheeModel}};
+use mongodb::bson::{doc, to_bson};
+use std::collections::HashMap;
 use std::fmt;
 
 #[derive(Debug)]
@@ -34,3 +35,132 @@ pub async fn fetch_rushee(gtid: String) -> Result<RusheeModel, RusheeError> {
         }),
     }
 }
+
+/**
+ * Turns 0, 1, ..., 25, 26, ... into "Brother A", "Brother B", ..., "Brother Z", "Brother AA", ...
+ */
+fn pseudonym_label(index: usize) -> String {
+    let mut letters = Vec::new();
+    let mut n = index + 1;
+
+    while n > 0 {
+        n -= 1;
+        letters.push((b'A' + (n % 26) as u8) as char);
+        n /= 26;
+    }
+
+    format!("Brother {}", letters.iter().rev().collect::<String>())
+}
+
+const MAX_PSEUDONYM_ATTEMPTS: usize = 5;
+
+/**
+ * Gets the pseudonyms for every brother who commented, replied or reacted on a rushee.
+ * Pseudonyms are stored per rushee and only ever added to, so deleting a comment never relabels anyone.
+ * Brothers without one yet get the next label, in the order they first show up in the comments
+ */
+pub async fn get_pseudonyms(gtid: &str, comments: &Vec<Comment>) -> Result<HashMap<String, String>, RusheeError> {
+    let connection = db::get_pseudonyms_client().await;
+
+    let authors: Vec<&String> = comments.iter().flat_map(|comment| {
+        std::iter::once(&comment.brother_name)
+            .chain(comment.replies.iter().map(|reply| &reply.brother_name))
+            .chain(comment.reactions.iter().map(|reaction| &reaction.brother_name))
+    }).collect();
+
+    for _attempt in 0..MAX_PSEUDONYM_ATTEMPTS {
+        let stored = match connection.find_one(doc! {"_id": gtid}).await {
+            Ok(x) => x,
+            Err(e) => {
+                return Err(RusheeError {
+                    code: "DATABASE_ERROR".to_string(),
+                    message: format!("Database error: {}", e),
+                })
+            }
+        };
+
+        let exists = stored.is_some();
+        let stored_labels = stored.map(|map| map.labels).unwrap_or_default();
+
+        let mut pseudonyms: HashMap<String, String> = stored_labels
+            .iter()
+            .map(|pseudonym| (pseudonym.brother_name.clone(), pseudonym.label.clone()))
+            .collect();
+
+        let mut new_labels = Vec::<Pseudonym>::new();
+
+        for author in authors.iter() {
+            if !pseudonyms.contains_key(*author) {
+                let label = pseudonym_label(pseudonyms.len());
+                pseudonyms.insert((*author).clone(), label.clone());
+                new_labels.push(Pseudonym {
+                    brother_name: (*author).clone(),
+                    label: label,
+                });
+            }
+        }
+
+        if new_labels.is_empty() {
+            return Ok(pseudonyms);
+        }
+
+        let bson_labels = to_bson(&new_labels).map_err(|_e| RusheeError {
+            code: "DATABASE_ERROR".to_string(),
+            message: "couldn't serialize pseudonyms".to_string(),
+        })?;
+
+        // only add labels if nobody else did since we read them, otherwise two brothers could get the same one
+        let result = if exists {
+            connection
+                .update_one(
+                    doc! {"_id": gtid, "labels": {"$size": stored_labels.len() as i64}},
+                    doc! {"$push": {"labels": {"$each": bson_labels}}},
+                )
+                .await
+                .map(|update| update.matched_count > 0)
+        } else {
+            connection
+                .update_one(doc! {"_id": gtid}, doc! {"$setOnInsert": {"labels": bson_labels}})
+                .upsert(true)
+                .await
+                .map(|update| update.upserted_id.is_some())
+        };
+
+        match result {
+            Ok(true) => return Ok(pseudonyms),
+            Ok(false) => continue,
+            Err(e) => {
+                return Err(RusheeError {
+                    code: "DATABASE_ERROR".to_string(),
+                    message: format!("Database error: {}", e),
+                })
+            }
+        }
+    }
+
+    Err(RusheeError {
+        code: "PSEUDONYMS_BUSY".to_string(),
+        message: "pseudonyms for this rushee kept changing, try again".to_string(),
+    })
+}
+
+/**
+ * Replaces brother names and ids on a rushee's comments, replies and reactions with their pseudonyms
+ */
+pub fn anonymize_comments(comments: &mut Vec<Comment>, pseudonyms: &HashMap<String, String>) {
+    let pseudonym_for = |name: &String| pseudonyms.get(name).cloned().unwrap_or_default();
+
+    for comment in comments.iter_mut() {
+        comment.brother_name = pseudonym_for(&comment.brother_name);
+        comment.brother_id = comment.brother_name.clone();
+
+        for reply in comment.replies.iter_mut() {
+            reply.brother_name = pseudonym_for(&reply.brother_name);
+            reply.brother_id = reply.brother_name.clone();
+        }
+
+        for reaction in comment.reactions.iter_mut() {
+            reaction.brother_name = pseudonym_for(&reaction.brother_name);
+        }
+    }
+}
@@ -0,0 +1,260 @@
+/**
+ * PIS scorecards. Every brother on a rushee's PIS panel scores each question against the rubric
+ * (1 to PIS_RUBRIC_MAX), leaves notes and gives an overall recommendation.
+ * The scorecards are rolled up into the rushee's PIS summary, which is kept apart from rush night ratings
+ */
+
+use std::env;
+use std::io::Error;
+
+use bson::{doc, to_bson, DateTime};
+
+use crate::controllers::db;
+use crate::middlewares::assignment::full_name;
+use crate::models::pis::{
+    IncomingScorecard, PISBrother, PISQuestion, PISQuestionSummary, PISScorecard, PISSummary, Recommendation,
+    RecommendationCounts, ScorecardEntry,
+};
+use crate::models::Rushee::RusheeModel;
+
+const DEFAULT_PIS_RUBRIC_MAX: u32 = 5;
+
+fn invalid(message: String) -> Error {
+    Error::new(std::io::ErrorKind::InvalidInput, message)
+}
+
+/**
+ * The top of the rubric, set through PIS_RUBRIC_MAX
+ */
+pub fn rubric_max() -> u32 {
+    env::var("PIS_RUBRIC_MAX")
+        .ok()
+        .and_then(|max| max.parse::<u32>().ok())
+        .filter(|max| *max > 0)
+        .unwrap_or(DEFAULT_PIS_RUBRIC_MAX)
+}
+
+pub fn parse_recommendation(recommendation: &str) -> Result<Recommendation, Error> {
+    match recommendation.trim().to_lowercase().replace('_', "-").as_str() {
+        "strong-yes" | "strongyes" => Ok(Recommendation::StrongYes),
+        "yes" => Ok(Recommendation::Yes),
+        "neutral" => Ok(Recommendation::Neutral),
+        "no" => Ok(Recommendation::No),
+        "strong-no" | "strongno" => Ok(Recommendation::StrongNo),
+        _ => Err(invalid(format!("unknown recommendation: {}", recommendation))),
+    }
+}
+
+/**
+ * The brother's seat on the rushee's PIS panel, if they're on it
+ */
+pub fn panel_member<'a>(rushee: &'a RusheeModel, first_name: &str, last_name: &str) -> Option<&'a PISBrother> {
+    let name = full_name(first_name.trim(), last_name.trim()).to_lowercase();
+
+    rushee
+        .pis_signup
+        .panel
+        .iter()
+        .find(|member| full_name(&member.first_name, &member.last_name).to_lowercase() == name)
+}
+
+pub fn same_brother(scorecard: &PISScorecard, first_name: &str, last_name: &str) -> bool {
+    full_name(&scorecard.first_name, &scorecard.last_name).to_lowercase()
+        == full_name(first_name.trim(), last_name.trim()).to_lowercase()
+}
+
+/**
+ * Builds a panel member's scorecard from their input. Every entry has to be for a question in the bank
+ * and scores have to be on the rubric. Entries keep the wording of the question they were written against
+ */
+pub fn build_scorecard(
+    member: &PISBrother,
+    payload: IncomingScorecard,
+    questions: &Vec<PISQuestion>,
+    submitted_at: DateTime,
+) -> Result<PISScorecard, Error> {
+
+    let recommendation = parse_recommendation(&payload.recommendation)?;
+    let max = rubric_max();
+
+    let mut entries = Vec::<ScorecardEntry>::new();
+
+    for entry in payload.entries.into_iter() {
+        let question = questions
+            .iter()
+            .find(|question| question.id.map(|oid| oid.to_hex()).as_deref() == Some(entry.question_id.as_str()))
+            .ok_or_else(|| invalid(format!("{} isn't a PIS question", entry.question_id)))?;
+
+        if let Some(score) = entry.score {
+            if score < 1 || score > max {
+                return Err(invalid(format!(
+                    "the score for \"{}\" has to be between 1 and {}",
+                    question.question, max
+                )))
+            }
+        }
+
+        if entries.iter().any(|existing| existing.question_id == entry.question_id) {
+            return Err(invalid(format!("\"{}\" is on the scorecard twice", question.question)))
+        }
+
+        entries.push(ScorecardEntry {
+            question_id: entry.question_id,
+            question: question.question.clone(),
+            version: question.version,
+            notes: entry.notes.trim().to_string(),
+            score: entry.score,
+        });
+    }
+
+    Ok(PISScorecard {
+        first_name: member.first_name.clone(),
+        last_name: member.last_name.clone(),
+        entries: entries,
+        recommendation: recommendation,
+        submitted_at: submitted_at,
+        updated_at: DateTime::now(),
+    })
+
+}
+
+fn average(scores: &Vec<u32>) -> Option<f64> {
+    if scores.is_empty() {
+        return None
+    }
+
+    Some(scores.iter().sum::<u32>() as f64 / scores.len() as f64)
+}
+
+/**
+ * Rolls a rushee's scorecards up into their PIS summary
+ */
+pub fn summarize(rushee: &RusheeModel) -> PISSummary {
+
+    let mut all_scores = Vec::<u32>::new();
+    let mut questions = Vec::<(String, String, Vec<u32>)>::new();
+    let mut recommendations = RecommendationCounts::default();
+
+    for scorecard in rushee.pis_scorecards.iter() {
+        for entry in scorecard.entries.iter() {
+            let position = match questions.iter().position(|(id, _, _)| *id == entry.question_id) {
+                Some(position) => position,
+                None => {
+                    questions.push((entry.question_id.clone(), entry.question.clone(), Vec::new()));
+                    questions.len() - 1
+                }
+            };
+
+            if let Some(score) = entry.score {
+                questions[position].2.push(score);
+                all_scores.push(score);
+            }
+        }
+
+        match scorecard.recommendation {
+            Recommendation::StrongYes => recommendations.strong_yes += 1,
+            Recommendation::Yes => recommendations.yes += 1,
+            Recommendation::Neutral => recommendations.neutral += 1,
+            Recommendation::No => recommendations.no += 1,
+            Recommendation::StrongNo => recommendations.strong_no += 1,
+        }
+    }
+
+    let missing = rushee
+        .pis_signup
+        .panel
+        .iter()
+        .filter(|member| {
+            !rushee
+                .pis_scorecards
+                .iter()
+                .any(|scorecard| same_brother(scorecard, &member.first_name, &member.last_name))
+        })
+        .map(|member| full_name(&member.first_name, &member.last_name))
+        .collect();
+
+    PISSummary {
+        num_scorecards: rushee.pis_scorecards.len() as u32,
+        average_score: average(&all_scores),
+        questions: questions
+            .into_iter()
+            .map(|(question_id, question, scores)| PISQuestionSummary {
+                question_id: question_id,
+                question: question,
+                average_score: average(&scores),
+                num_scores: scores.len() as u32,
+            })
+            .collect(),
+        recommendations: recommendations,
+        missing: missing,
+        updated_at: DateTime::now(),
+    }
+
+}
+
+/**
+ * Saves a brother's scorecard on the rushee, replacing the one they submitted before
+ */
+pub async fn save_scorecard(gtid: &str, scorecard: &PISScorecard) -> Result<(), Error> {
+
+    let scorecard_bson = to_bson(scorecard)
+        .map_err(|_| Error::new(std::io::ErrorKind::Other, "some issue occurred when serializing the scorecard"))?;
+
+    let connection = db::get_rushee_client().await;
+
+    let brother = doc! {"first_name": scorecard.first_name.clone(), "last_name": scorecard.last_name.clone()};
+
+    let replace = connection
+        .update_one(
+            doc! {"gtid": gtid, "pis_scorecards": {"$elemMatch": brother.clone()}},
+            doc! {"$set": {"pis_scorecards.$": scorecard_bson.clone()}},
+        )
+        .await
+        .map_err(|_| Error::new(std::io::ErrorKind::Other, "failed to update rushee"))?;
+
+    if replace.matched_count > 0 {
+        return Ok(())
+    }
+
+    // the filter keeps two submissions at once from leaving the brother with two scorecards
+    connection
+        .update_one(
+            doc! {"gtid": gtid, "pis_scorecards": {"$not": {"$elemMatch": brother}}},
+            doc! {"$push": {"pis_scorecards": scorecard_bson}},
+        )
+        .await
+        .map(|_| ())
+        .map_err(|_| Error::new(std::io::ErrorKind::Other, "failed to update rushee"))
+
+}
+
+/**
+ * Recomputes the rushee's PIS summary from their scorecards
+ */
+pub async fn refresh_summary(rushee: &RusheeModel) -> Result<PISSummary, Error> {
+
+    let summary = summarize(rushee);
+
+    let summary_bson = to_bson(&summary)
+        .map_err(|_| Error::new(std::io::ErrorKind::Other, "some issue occurred when serializing the PIS summary"))?;
+
+    let connection = db::get_rushee_client().await;
+
+    connection
+        .update_one(doc! {"gtid": rushee.gtid.clone()}, doc! {"$set": {"pis_summary": summary_bson}})
+        .await
+        .map_err(|_| Error::new(std::io::ErrorKind::Other, "failed to update rushee"))?;
+
+    Ok(summary)
+
+}
+
+/**
+ * Replaces interviewer names on scorecards for blind review, numbered in the order the scorecards were submitted
+ */
+pub fn anonymize_scorecards(scorecards: &mut Vec<PISScorecard>) {
+    for (index, scorecard) in scorecards.iter_mut().enumerate() {
+        scorecard.first_name = "Interviewer".to_string();
+        scorecard.last_name = (index + 1).to_string();
+    }
+}
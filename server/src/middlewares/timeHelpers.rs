@@ -1,21 +1,111 @@
 use bson::DateTime as BsonDateTime;
+use chrono::{NaiveDate, NaiveDateTime, TimeZone, Utc};
+use chrono_tz::Tz;
+use std::env;
+use std::io::Error;
 
-pub fn string_to_bson_datetime(date_string: &str) -> BsonDateTime {
-    BsonDateTime::parse_rfc3339_str(date_string).unwrap_or_else(|_| {
-        BsonDateTime::parse_rfc3339_str("1970-01-01T00:00:00Z").unwrap()
-    })
+use crate::models::misc::RushNight;
+
+const DEFAULT_CHAPTER_TIMEZONE: Tz = chrono_tz::America::New_York;
+const LOCAL_FORMATS: [&str; 3] = ["%Y-%m-%dT%H:%M:%S", "%Y-%m-%dT%H:%M", "%Y-%m-%d %H:%M"];
+
+/**
+ * Parses an RFC 3339 timestamp ("2025-09-03T23:00:00Z"), or a local time without an offset
+ * ("2025-09-03T19:00") which is read in the chapter's timezone
+ */
+pub fn string_to_bson_datetime(date_string: &str) -> Result<BsonDateTime, Error> {
+    if let Ok(date) = BsonDateTime::parse_rfc3339_str(date_string.trim()) {
+        return Ok(date);
+    }
+
+    for format in LOCAL_FORMATS {
+        if let Ok(naive) = NaiveDateTime::parse_from_str(date_string.trim(), format) {
+            return match chapter_timezone().from_local_datetime(&naive).earliest() {
+                Some(local) => Ok(BsonDateTime::from_millis(local.timestamp_millis())),
+                None => Err(Error::new(
+                    std::io::ErrorKind::InvalidInput,
+                    format!("{} doesn't exist in the chapter's timezone", date_string),
+                )),
+            };
+        }
+    }
+
+    Err(Error::new(
+        std::io::ErrorKind::InvalidInput,
+        format!("Invalid date: {}", date_string),
+    ))
 }
 
-pub fn same_day(date1: &BsonDateTime, date2: &BsonDateTime) -> bool {
-    // Extract the milliseconds since the UNIX epoch
-    let millis1 = date1.timestamp_millis();
-    let millis2 = date2.timestamp_millis();
+/**
+ * The chapter's timezone, set through CHAPTER_TIMEZONE (e.g. "America/New_York")
+ */
+pub fn chapter_timezone() -> Tz {
+    env::var("CHAPTER_TIMEZONE")
+        .ok()
+        .and_then(|tz| tz.parse::<Tz>().ok())
+        .unwrap_or(DEFAULT_CHAPTER_TIMEZONE)
+}
+
+/**
+ * Calendar date of a timestamp in the chapter's timezone
+ */
+pub fn local_date(date: &BsonDateTime) -> NaiveDate {
+    Utc.timestamp_millis_opt(date.timestamp_millis())
+        .unwrap()
+        .with_timezone(&chapter_timezone())
+        .date_naive()
+}
 
-    // Compute the number of days since the UNIX epoch for each date
-    const MILLIS_PER_DAY: i64 = 24 * 60 * 60 * 1000;
-    let days1 = millis1 / MILLIS_PER_DAY;
-    let days2 = millis2 / MILLIS_PER_DAY;
+/**
+ * A timestamp written out in the chapter's timezone, e.g. "Mon Sep 8, 7:30 PM"
+ */
+pub fn format_local(date: &BsonDateTime) -> String {
+    Utc.timestamp_millis_opt(date.timestamp_millis())
+        .unwrap()
+        .with_timezone(&chapter_timezone())
+        .format("%a %b %-d, %-I:%M %p")
+        .to_string()
+}
+
+/**
+ * Whether two timestamps fall on the same day in the chapter's timezone
+ */
+pub fn same_local_day(date1: &BsonDateTime, date2: &BsonDateTime) -> bool {
+    local_date(date1) == local_date(date2)
+}
+
+/**
+ * Whether a timestamp falls inside a rush night.
+ * Uses the night's start and end times, nights without them cover their whole local day
+ */
+pub fn is_during_night(night: &RushNight, date: &BsonDateTime) -> bool {
+    match (night.start_time, night.end_time) {
+        (Some(start), Some(end)) => start <= *date && *date <= end,
+        _ => same_local_day(&night.time, date),
+    }
+}
+
+/**
+ * When a rush night is over, nights without an end time are over at the end of their local day
+ */
+pub fn night_end(night: &RushNight) -> BsonDateTime {
+    match night.end_time {
+        Some(end) => end,
+        None => {
+            let next_day = local_date(&night.time).succ_opt().unwrap_or(local_date(&night.time));
+            let midnight = next_day.and_hms_opt(0, 0, 0).unwrap();
+
+            match chapter_timezone().from_local_datetime(&midnight).earliest() {
+                Some(local) => BsonDateTime::from_millis(local.timestamp_millis()),
+                None => night.time,
+            }
+        }
+    }
+}
 
-    // Compare the days
-    days1 == days2
+/**
+ * When a rush night starts, nights without a start time start at their scheduled time
+ */
+pub fn night_start(night: &RushNight) -> BsonDateTime {
+    night.start_time.unwrap_or(night.time)
 }
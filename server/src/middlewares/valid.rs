@@ -2,13 +2,15 @@
  * Contains a bunch of functions to check if something is valid or not
  */
 
-use std::{collections::HashSet, io::Error};
+use std::{collections::HashSet, env, io::Error};
 
-use bson::doc;
+use bson::{doc, Document};
 
-use crate::{controllers::db, models::{misc::RushNight, Rushee::Comment}};
+use crate::{controllers::db, models::{misc::RushNight, Rushee::{Comment, CommentReply}}};
 
-use super::timeHelpers::same_day;
+use super::timeHelpers::same_local_day;
+
+const DEFAULT_REPLY_EDIT_HOURS: i64 = 24;
 
 /**
  * Changes we have to handle specially
@@ -85,14 +87,52 @@ pub async fn is_gtid_valid(gtid: &str) -> Result<bool, Error> {
 
 }
 
+/**
+ * Whether a comment was left for the given rush night.
+ * Older comments don't carry a night id, so those fall back to comparing the night's local day
+ */
+pub fn is_comment_on_night(comment: &Comment, night: &RushNight) -> bool {
+
+    match night.id {
+        Some(id) if !comment.night_id.is_empty() => comment.night_id == id.to_hex(),
+        _ => same_local_day(&comment.night.time, &night.time),
+    }
+
+}
+
+/**
+ * Finds the comment a brother left for the given rush night
+ */
+pub fn find_comment<'a>(brother_name: &str, night: &RushNight, comments: &'a Vec<Comment>) -> Option<&'a Comment> {
+
+    comments.iter()
+    .find(|comment| comment.brother_name == brother_name && is_comment_on_night(comment, night))
+
+}
+
+/**
+ * Matches one stored comment inside a rushee's comments array, for $elemMatch and $pull.
+ * Goes by the comment's night id, or the night's time for older comments that don't have one,
+ * never by the whole night since older comments store fewer night fields than we serialize now
+ */
+pub fn comment_match(comment: &Comment) -> Document {
+
+    if !comment.night_id.is_empty() {
+        return doc! {"brother_name": comment.brother_name.clone(), "night_id": comment.night_id.clone()};
+    }
+
+    doc! {"brother_name": comment.brother_name.clone(), "night.time": comment.night.time}
+
+}
+
 pub async fn check_valid_comment(brother_name: &str, night: &RushNight, comments: &Vec<Comment>) -> Result<bool, Error> {
 
     let result = comments.iter()
-    .find(|comment| comment.brother_name == brother_name && same_day(&comment.night.time, &night.time));
+    .find(|comment| comment.brother_name == brother_name && is_comment_on_night(comment, night));
 
     match result {
 
-        Some(x) => {
+        Some(_x) => {
             return Err(Error::new(std::io::ErrorKind::Other, "already made a comment"))
         }
 
@@ -102,4 +142,52 @@ pub async fn check_valid_comment(brother_name: &str, night: &RushNight, comments
 
     }
 
-}
\ No newline at end of file
+}
+
+/**
+ * Finds a reply by its id across all of a rushee's comments
+ */
+pub fn find_reply<'a>(reply_id: &str, comments: &'a Vec<Comment>) -> Option<&'a CommentReply> {
+
+    comments.iter()
+    .flat_map(|comment| comment.replies.iter())
+    .find(|reply| reply.reply_id == reply_id)
+
+}
+
+/**
+ * Replies can only be edited by the brother who wrote them, and only within REPLY_EDIT_HOURS of posting
+ */
+pub fn check_valid_reply_edit(brother_name: &str, reply: &CommentReply) -> Result<bool, Error> {
+
+    if reply.brother_name != brother_name {
+        return Err(Error::new(std::io::ErrorKind::Other, "you can only edit your own replies"));
+    }
+
+    let edit_hours = env::var("REPLY_EDIT_HOURS")
+        .ok()
+        .and_then(|hours| hours.parse::<i64>().ok())
+        .unwrap_or(DEFAULT_REPLY_EDIT_HOURS);
+
+    let elapsed_millis = bson::DateTime::now().timestamp_millis() - reply.time.timestamp_millis();
+
+    if elapsed_millis > edit_hours * 60 * 60 * 1000 {
+        return Err(Error::new(std::io::ErrorKind::Other, "this reply can no longer be edited"));
+    }
+
+    Ok(true)
+
+}
+
+/**
+ * Replies can only be deleted by the brother who wrote them
+ */
+pub fn check_valid_reply_delete(brother_name: &str, reply: &CommentReply) -> Result<bool, Error> {
+
+    if reply.brother_name != brother_name {
+        return Err(Error::new(std::io::ErrorKind::Other, "you can only delete your own replies"));
+    }
+
+    Ok(true)
+
+}
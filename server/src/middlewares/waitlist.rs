@@ -0,0 +1,296 @@
+/**
+ * PIS waitlist. When a seat opens up at a timeslot, the rushee who has been waiting longest is offered it
+ * and the seat is held for them for PIS_HOLD_MINUTES. Holds that run out are handed to the next rushee in line.
+ * There's nothing running in the background, so expired holds are cleared whenever the waitlist is touched
+ */
+
+use std::collections::HashSet;
+use std::env;
+use std::io::Error;
+
+use bson::{doc, to_bson, DateTime};
+use futures::stream::StreamExt;
+
+use crate::controllers::db;
+use crate::models::pis::{PISWaitlistEntry, WaitlistStatus};
+
+const DEFAULT_PIS_HOLD_MINUTES: i64 = 120;
+
+/**
+ * How long an offered seat is held, set through PIS_HOLD_MINUTES
+ */
+pub fn hold_minutes() -> i64 {
+    env::var("PIS_HOLD_MINUTES")
+        .ok()
+        .and_then(|minutes| minutes.parse::<i64>().ok())
+        .unwrap_or(DEFAULT_PIS_HOLD_MINUTES)
+}
+
+fn status_bson(status: WaitlistStatus) -> bson::Bson {
+    to_bson(&status).unwrap_or(bson::Bson::Null)
+}
+
+pub async fn get_waitlist(filter: bson::Document) -> Result<Vec<PISWaitlistEntry>, Error> {
+
+    let mut answer = Vec::<PISWaitlistEntry>::new();
+
+    let connection = db::get_pis_waitlist_client().await;
+
+    match connection.find(filter).sort(doc! {"joined_at": 1}).await {
+        Ok(mut cursor) => {
+            while let Some(entry) = cursor.next().await {
+                match entry {
+                    Ok(x) => answer.push(x),
+                    Err(_err) => {
+                        return Err(Error::new(std::io::ErrorKind::Other, "there was an error reading the waitlist"))
+                    }
+                }
+            }
+
+            Ok(answer)
+        }
+
+        Err(_err) => Err(Error::new(std::io::ErrorKind::Other, "couldn't fetch the waitlist")),
+    }
+
+}
+
+/**
+ * Puts a rushee on the waitlist for a timeslot, does nothing if they're already waiting or holding a seat there
+ */
+pub async fn join_waitlist(gtid: &str, time: DateTime) -> Result<(), Error> {
+
+    let connection = db::get_pis_waitlist_client().await;
+
+    let filter = doc! {
+        "gtid": gtid,
+        "time": time,
+        "status": {"$in": [status_bson(WaitlistStatus::Waiting), status_bson(WaitlistStatus::Offered)]},
+    };
+
+    let update = doc! {"$setOnInsert": {
+        "joined_at": DateTime::now(),
+        "status": status_bson(WaitlistStatus::Waiting),
+        "offered_at": null,
+        "hold_expires_at": null,
+    }};
+
+    connection
+        .update_one(filter, update)
+        .upsert(true)
+        .await
+        .map(|_| ())
+        .map_err(|_| Error::new(std::io::ErrorKind::Other, "couldn't join the waitlist"))
+
+}
+
+/**
+ * Hands open seats at a timeslot to the rushees waiting for it, in the order they joined.
+ * Each offered seat is taken out of num_available so nobody else can book it while it's held.
+ * Returns how many seats were offered
+ */
+pub async fn offer_open_seats(time: DateTime) -> Result<u32, Error> {
+
+    let waitlist = db::get_pis_waitlist_client().await;
+    let timeslots = db::get_pis_timeslots_client().await;
+    let rushees = db::get_rushee_client().await;
+
+    let mut offered = 0;
+
+    loop {
+
+        let next = waitlist
+            .find_one(doc! {"time": time, "status": status_bson(WaitlistStatus::Waiting)})
+            .sort(doc! {"joined_at": 1})
+            .await
+            .map_err(|_| Error::new(std::io::ErrorKind::Other, "couldn't fetch the waitlist"))?;
+
+        let entry = match next {
+            Some(entry) => entry,
+            None => break,
+        };
+
+        // rushees who ended up at this time some other way don't need the seat
+        let already_there = rushees
+            .count_documents(doc! {"gtid": entry.gtid.clone(), "pis_timeslot": time})
+            .await
+            .map_err(|_| Error::new(std::io::ErrorKind::Other, "couldn't fetch the rushee"))?;
+
+        if already_there > 0 {
+            waitlist
+                .update_one(doc! {"_id": entry.id}, doc! {"$set": {"status": status_bson(WaitlistStatus::Cancelled)}})
+                .await
+                .map_err(|_| Error::new(std::io::ErrorKind::Other, "couldn't update the waitlist"))?;
+
+            continue;
+        }
+
+        let hold = timeslots
+            .update_one(
+                doc! {"time": time, "num_available": {"$gt": 0}},
+                doc! {"$inc": {"num_available": -1}},
+            )
+            .await
+            .map_err(|_| Error::new(std::io::ErrorKind::Other, "couldn't update PIS timeslot"))?;
+
+        if hold.modified_count == 0 {
+            break;
+        }
+
+        let now = DateTime::now();
+        let expires = DateTime::from_millis(now.timestamp_millis() + hold_minutes() * 60 * 1000);
+
+        let offer = waitlist
+            .update_one(
+                doc! {"_id": entry.id, "status": status_bson(WaitlistStatus::Waiting)},
+                doc! {"$set": {
+                    "status": status_bson(WaitlistStatus::Offered),
+                    "offered_at": now,
+                    "hold_expires_at": expires,
+                }},
+            )
+            .await
+            .map_err(|_| Error::new(std::io::ErrorKind::Other, "couldn't update the waitlist"))?;
+
+        if offer.modified_count == 0 {
+            // someone else got to this entry first, give the seat back
+            release_seat(time).await?;
+            continue;
+        }
+
+        offered += 1;
+
+    }
+
+    Ok(offered)
+
+}
+
+/**
+ * Gives a held seat back to a timeslot
+ */
+pub async fn release_seat(time: DateTime) -> Result<(), Error> {
+
+    let timeslots = db::get_pis_timeslots_client().await;
+
+    timeslots
+        .update_one(doc! {"time": time}, doc! {"$inc": {"num_available": 1}})
+        .await
+        .map(|_| ())
+        .map_err(|_| Error::new(std::io::ErrorKind::Other, "couldn't update PIS timeslot"))
+
+}
+
+/**
+ * Expires holds that have run out and offers their seats to the next rushees in line
+ */
+pub async fn expire_holds() -> Result<(), Error> {
+
+    let expired = get_waitlist(doc! {
+        "status": status_bson(WaitlistStatus::Offered),
+        "hold_expires_at": {"$lt": DateTime::now()},
+    })
+    .await?;
+
+    let waitlist = db::get_pis_waitlist_client().await;
+    let mut reopened = HashSet::<i64>::new();
+
+    for entry in expired.iter() {
+        let update = waitlist
+            .update_one(
+                doc! {"_id": entry.id, "status": status_bson(WaitlistStatus::Offered)},
+                doc! {"$set": {"status": status_bson(WaitlistStatus::Expired)}},
+            )
+            .await
+            .map_err(|_| Error::new(std::io::ErrorKind::Other, "couldn't update the waitlist"))?;
+
+        if update.modified_count > 0 {
+            release_seat(entry.time).await?;
+            reopened.insert(entry.time.timestamp_millis());
+        }
+    }
+
+    for time in reopened {
+        offer_open_seats(DateTime::from_millis(time)).await?;
+    }
+
+    Ok(())
+
+}
+
+/**
+ * Takes a rushee off the waitlist for a timeslot. A seat being held for them goes to the next rushee in line
+ */
+pub async fn leave_waitlist(gtid: &str, time: DateTime) -> Result<bool, Error> {
+
+    let waitlist = db::get_pis_waitlist_client().await;
+
+    let entry = waitlist
+        .find_one_and_update(
+            doc! {
+                "gtid": gtid,
+                "time": time,
+                "status": {"$in": [status_bson(WaitlistStatus::Waiting), status_bson(WaitlistStatus::Offered)]},
+            },
+            doc! {"$set": {"status": status_bson(WaitlistStatus::Cancelled)}},
+        )
+        .await
+        .map_err(|_| Error::new(std::io::ErrorKind::Other, "couldn't update the waitlist"))?;
+
+    match entry {
+        Some(entry) if entry.status == WaitlistStatus::Offered => {
+            release_seat(time).await?;
+            offer_open_seats(time).await?;
+            Ok(true)
+        }
+
+        Some(_entry) => Ok(true),
+
+        None => Ok(false),
+    }
+
+}
+
+/**
+ * Offers a rushee the earliest upcoming timeslot with an open seat, holding the seat for them like any other
+ * waitlist offer. Returns the time offered, or None if every upcoming timeslot is full
+ */
+pub async fn offer_reschedule(gtid: &str) -> Result<Option<DateTime>, Error> {
+
+    let waitlist = db::get_pis_waitlist_client().await;
+    let timeslots = db::get_pis_timeslots_client().await;
+
+    let now = DateTime::now();
+
+    let hold = timeslots
+        .find_one_and_update(
+            doc! {"time": {"$gt": now}, "num_available": {"$gt": 0}},
+            doc! {"$inc": {"num_available": -1}},
+        )
+        .sort(doc! {"time": 1})
+        .await
+        .map_err(|_| Error::new(std::io::ErrorKind::Other, "couldn't update PIS timeslot"))?;
+
+    let timeslot = match hold {
+        Some(timeslot) => timeslot,
+        None => return Ok(None),
+    };
+
+    let entry = PISWaitlistEntry {
+        id: None,
+        gtid: gtid.to_string(),
+        time: timeslot.time,
+        joined_at: now,
+        status: WaitlistStatus::Offered,
+        offered_at: Some(now),
+        hold_expires_at: Some(DateTime::from_millis(now.timestamp_millis() + hold_minutes() * 60 * 1000)),
+    };
+
+    if let Err(_err) = waitlist.insert_one(entry).await {
+        release_seat(timeslot.time).await?;
+        return Err(Error::new(std::io::ErrorKind::Other, "couldn't offer the rushee a new time"))
+    }
+
+    Ok(Some(timeslot.time))
+
+}
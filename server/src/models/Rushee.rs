@@ -1,7 +1,9 @@
 use bson::DateTime;
 use serde::{Deserialize, Serialize};
 
-use super::{misc::RushNight, pis::{PISSignup, PISTimeslot}};
+use super::{misc::{AttendanceRecord, RushNight}, pis::{
+    PISLocationAssignment, PISReschedule, PISScorecard, PISSignup, PISStatusRecord, PISSummary, PISTimeslot,
+}};
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct RusheeEdit {
@@ -25,13 +27,53 @@ pub struct StrippedRushee {
     pub class: String,
     pub email: String,
     pub pronouns: String,
-    pub attendance: Vec<RushNight>
+    pub attendance: Vec<AttendanceRecord>,
+    #[serde(default)]
+    pub pis_status: Option<PISStatusRecord>,
 }
 
+// a PIS on a brother's list, with when and where it is
 #[derive(Debug, Serialize, Deserialize)]
+pub struct BrotherPIS {
+    #[serde(flatten)]
+    pub rushee: StrippedRushee,
+    pub pis_timeslot: DateTime,
+    pub pis_location: Option<PISLocationAssignment>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct PisResponse {
+    // the wording the rushee was asked, kept even if the question is edited later
+    pub question: String,
+    pub answer: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub question_id: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub version: Option<u32>,
+    // bumped every time the answer changes, writers send the revision they started from
+    #[serde(default)]
+    pub revision: u32,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub updated_by: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub updated_at: Option<DateTime>,
+}
+
+// an answer to one PIS question, by id or (for older clients) by wording
+#[derive(Debug, Serialize, Deserialize)]
+pub struct IncomingPISAnswer {
+    pub question_id: Option<String>,
+    #[serde(default)]
     pub question: String,
     pub answer: String,
+    // the revision of the answer this edit started from, leave out to overwrite whatever is there
+    pub revision: Option<u32>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct IncomingPISAnswers {
+    pub updated_by: Option<String>,
+    pub answers: Vec<IncomingPISAnswer>,
 }
 
 #[derive(Clone, Serialize, Deserialize, Debug)]
@@ -41,6 +83,109 @@ pub struct Comment {
     pub comment: String,
     pub ratings: Vec<Rating>,
     pub night: RushNight,
+    #[serde(default)]
+    pub night_id: String,
+    #[serde(default)]
+    pub replies: Vec<CommentReply>,
+    #[serde(default)]
+    pub reactions: Vec<CommentReaction>,
+    #[serde(default)]
+    pub flags: Vec<ModerationFlag>,
+    #[serde(default)]
+    pub moderation: ModerationStatus,
+}
+
+#[derive(Clone, Serialize, Deserialize, Debug)]
+pub struct ModerationFlag {
+    pub kind: String, // term, phone, email or address
+    pub matched: String,
+}
+
+#[derive(Clone, Serialize, Deserialize, Debug, PartialEq, Default)]
+pub enum ModerationStatus {
+    #[default]
+    Clear,
+    Flagged,
+    Approved,
+    Hidden,
+    Redacted,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct IncomingModerationAction {
+    pub admin_name: String,
+    pub brother_name: String,
+    pub night: RushNight,
+    pub action: String, // approve, hide or redact
+}
+
+// replies carry no ratings, so they never touch the rushee's rating aggregates
+#[derive(Clone, Serialize, Deserialize, Debug)]
+pub struct CommentReply {
+    pub reply_id: String,
+    pub brother_id: String,
+    pub brother_name: String,
+    pub reply: String,
+    pub time: DateTime,
+    #[serde(default)]
+    pub edited: bool,
+}
+
+#[derive(Clone, Serialize, Deserialize, Debug, PartialEq)]
+pub enum ReactionOption {
+    Agree,
+    Disagree,
+}
+
+#[derive(Clone, Serialize, Deserialize, Debug)]
+pub struct CommentReaction {
+    pub brother_name: String,
+    pub reaction: ReactionOption,
+}
+
+#[derive(Clone, Serialize, Deserialize, Debug)]
+pub struct Pseudonym {
+    pub brother_name: String,
+    pub label: String, // "Brother A", "Brother B", ...
+}
+
+// one per rushee, labels only ever get appended so a brother keeps his pseudonym on that rushee
+#[derive(Clone, Serialize, Deserialize, Debug)]
+pub struct PseudonymMap {
+    #[serde(rename = "_id")]
+    pub gtid: String,
+    pub labels: Vec<Pseudonym>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct IncomingReply {
+    // the comment being replied to is identified the same way edit/delete comment do it
+    pub comment_brother_name: String,
+    pub night: RushNight,
+    pub brother_id: String,
+    pub brother_name: String,
+    pub reply: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct IncomingReplyEdit {
+    pub reply_id: String,
+    pub brother_name: String,
+    pub reply: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct IncomingReplyDelete {
+    pub reply_id: String,
+    pub brother_name: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct IncomingReaction {
+    pub comment_brother_name: String,
+    pub night: RushNight,
+    pub brother_name: String,
+    pub reaction: String,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -49,6 +194,9 @@ pub struct IncomingComment {
     pub brother_name: String,
     pub comment: String,
     pub ratings: Vec<Rating>,
+    // defaults to tonight's rush night when not given
+    #[serde(default)]
+    pub night_id: Option<String>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -64,10 +212,19 @@ pub struct IncomingRushee {
     pub pronouns: String,
     pub image_url: String,
     pub exposure: String,
+    // ignored, the server gives out PIS rooms and meeting links
+    #[serde(default)]
     pub pis_meeting_id: String,
     pub pis_timeslot: String,
+    #[serde(default)]
     pub pis_link: String,
     pub flex_window: bool,
+    // flex-window rushees can list the times that work for them instead of booking a timeslot
+    #[serde(default)]
+    pub flex_times: Vec<String>,
+    // computed by the client from the uploaded photo, used for face check-in
+    #[serde(default)]
+    pub face_embedding: Option<Vec<f32>>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -89,11 +246,28 @@ pub struct RusheeModel {
     pub cloud: String,
     pub pis: Vec<PisResponse>,
     pub comments: Vec<Comment>,
-    pub attendance: Vec<RushNight>,
+    pub attendance: Vec<AttendanceRecord>,
     pub ratings: Vec<Rating>,
     pub access_code: String,
     pub pis_signup: PISSignup,
     pub flex_window: bool,
+    #[serde(default)]
+    pub pis_history: Vec<PISReschedule>,
+    // unset until the PIS is marked completed, no-show or cancelled, cleared when it's rescheduled
+    #[serde(default)]
+    pub pis_status: Option<PISStatusRecord>,
+    #[serde(default)]
+    pub pis_status_history: Vec<PISStatusRecord>,
+    // the room or meeting link the server allocated, pis_link and pis_meeting_id mirror it
+    #[serde(default)]
+    pub pis_location: Option<PISLocationAssignment>,
+    // bumped on every save of the PIS answers, so saves never overwrite each other
+    #[serde(default)]
+    pub pis_revision: u32,
+    #[serde(default)]
+    pub pis_scorecards: Vec<PISScorecard>,
+    #[serde(default)]
+    pub pis_summary: Option<PISSummary>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -119,3 +293,34 @@ pub struct RusheeVote {
     pub last_name: String,
     pub vote: VoteOption,
 }
+
+// kept in its own collection so the vectors never ride along with the rushee
+#[derive(Debug, Serialize, Deserialize)]
+pub struct FaceEmbedding {
+    pub gtid: String,
+    pub embedding: Vec<f32>,
+    pub updated_at: DateTime,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct FaceMatch {
+    pub gtid: String,
+    pub name: String,
+    pub image_url: String,
+    pub score: f64,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct IncomingFaceEmbedding {
+    pub embedding: Vec<f32>,
+    // the rushee's access code, admins can send their key instead
+    #[serde(default)]
+    pub access_code: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct IncomingFaceCheckin {
+    pub embedding: Vec<f32>,
+    pub token: String, // door token the kiosk was set up with
+    pub kiosk_name: Option<String>,
+}
@@ -1,20 +1,165 @@
 use serde::{Deserialize, Serialize};
-use bson::DateTime;
+use bson::{oid::ObjectId, DateTime};
 
 #[derive(Debug, Deserialize, Serialize, Clone)]
 pub struct RushNight {
+    #[serde(rename = "_id", default, skip_serializing_if = "Option::is_none")]
+    pub id: Option<ObjectId>,
     pub time: DateTime,
     pub name: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub start_time: Option<DateTime>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub end_time: Option<DateTime>,
+    #[serde(default)]
+    pub location: String,
+    #[serde(default)]
+    pub description: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub capacity: Option<u32>,
+    #[serde(default)]
+    pub night_type: NightType,
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq, Default)]
+pub enum NightType {
+    #[default]
+    Open,
+    InviteOnly,
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq, Default)]
+pub enum CheckinSource {
+    SelfCheckin, // the rushee checked themselves in with their access code
+    Brother,
+    Kiosk,
+    Face,
+    #[default]
+    Unknown, // attendance recorded before check-in sources existed
+}
+
+// one per rush night a rushee attended, older records are just the rush night
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct AttendanceRecord {
+    #[serde(flatten)]
+    pub night: RushNight,
+    #[serde(default)]
+    pub checked_in_at: Option<DateTime>,
+    #[serde(default)]
+    pub source: CheckinSource,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub checked_in_by: Option<String>,
+    // checked in to an invite-only night without being on its invitation list
+    #[serde(default)]
+    pub uninvited: bool,
 }
 
 #[derive(Debug, Deserialize, Serialize)]
 pub struct IncomingRushNight {
     pub time: String,
     pub name: String,
+    pub start_time: Option<String>,
+    pub end_time: Option<String>,
+    pub location: Option<String>,
+    pub description: Option<String>,
+    pub capacity: Option<u32>,
+    pub night_type: Option<String>, // open (default) or invite-only
 }
 
 #[derive(Debug, Deserialize, Serialize)]
 pub struct IncomingBrotherName {
     pub first_name: String,
     pub last_name: String,
-}
\ No newline at end of file
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct AuditLogEntry {
+    pub action: String,
+    pub actor: String,
+    pub rushee_gtid: String,
+    pub detail: String,
+    pub time: DateTime,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct DeanonymizeRequest {
+    pub admin_name: String,
+    pub pseudonym: String,
+    pub night: RushNight,
+    pub reason: String,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct IncomingCheckin {
+    pub token: String,
+    // rushees checking themselves in prove who they are with their access code
+    pub access_code: Option<String>,
+    // brothers at the door say who checked the rushee in
+    pub brother_name: Option<String>,
+    // brother (default), kiosk or face, only used with door tokens
+    pub source: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CheckinTokenParams {
+    pub purpose: Option<String>, // rushee (default) or door
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq, Default)]
+pub enum RsvpStatus {
+    #[default]
+    Pending,
+    Attending,
+    Declined,
+}
+
+// one per rushee invited to a rush night
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct Invitation {
+    pub night_id: String,
+    pub gtid: String,
+    pub invited_by: String,
+    pub invited_at: DateTime,
+    #[serde(default)]
+    pub rsvp: RsvpStatus,
+    #[serde(default)]
+    pub responded_at: Option<DateTime>,
+}
+
+// every field that's set has to match
+#[derive(Debug, Deserialize, Serialize, Default)]
+pub struct RusheeFilter {
+    pub class: Option<String>,
+    pub major: Option<String>,
+    pub cloud: Option<String>,
+    pub housing: Option<String>,
+    pub attended_night_id: Option<String>,
+    pub min_nights_attended: Option<u32>,
+    pub pis_status: Option<String>, // completed, no-show, cancelled or pending
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct IncomingInvitations {
+    pub admin_name: String,
+    #[serde(default)]
+    pub gtids: Vec<String>,
+    // invites every rushee matching the filter on top of the gtids
+    pub filter: Option<RusheeFilter>,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct IncomingUninvite {
+    pub gtids: Vec<String>,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct IncomingRsvp {
+    pub night_id: String,
+    pub access_code: String,
+    pub attending: bool,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct IncomingAccessCode {
+    pub access_code: String,
+}
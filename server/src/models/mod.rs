@@ -2,3 +2,4 @@ pub mod Rushee;
 pub mod pis;
 pub mod misc;
 pub mod voting;
+pub mod reports;
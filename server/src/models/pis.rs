@@ -1,11 +1,59 @@
 use chrono::Date;
 use serde::{Deserialize, Serialize};
-use bson::DateTime;
+use bson::{oid::ObjectId, DateTime};
 
-#[derive(Debug, Deserialize, Serialize)]
+fn first_version() -> u32 {
+    1
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone)]
 pub struct PISQuestion {
+    #[serde(rename = "_id", default, skip_serializing_if = "Option::is_none")]
+    pub id: Option<ObjectId>,
+    pub question: String,
+    pub question_type: String, // MC, Short, Long or Number
+    // the choices for an MC question, yes/no if empty
+    #[serde(default)]
+    pub options: Vec<String>,
+    #[serde(default)]
+    pub order: u32,
+    #[serde(default)]
+    pub required: bool,
+    #[serde(default = "first_version")]
+    pub version: u32,
+    // earlier wordings, so answers given to them can still be shown the way they were asked
+    #[serde(default)]
+    pub history: Vec<PISQuestionVersion>,
+    // deleted questions are kept so old answers still point somewhere
+    #[serde(default)]
+    pub archived: bool,
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct PISQuestionVersion {
+    pub version: u32,
     pub question: String,
     pub question_type: String,
+    #[serde(default)]
+    pub options: Vec<String>,
+    pub replaced_at: DateTime,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct IncomingPISQuestion {
+    pub question: String,
+    pub question_type: String,
+    pub options: Option<Vec<String>>,
+    pub order: Option<u32>,
+    pub required: Option<bool>,
+}
+
+// questions are deleted by id, or by their text and type for older clients
+#[derive(Debug, Deserialize, Serialize)]
+pub struct IncomingPISQuestionDelete {
+    pub id: Option<String>,
+    pub question: Option<String>,
+    pub question_type: Option<String>,
 }
 
 #[derive(Debug, Deserialize, Serialize)]
@@ -26,12 +74,9 @@ pub struct PISSignup {
     pub rushee_first_name: String,
     pub rushee_last_name: String,
     pub rushee_gtid: String,
-    pub first_brother_first_name: String,
-    pub first_brother_last_name: String,
-    pub second_brother_first_name: String,
-    pub second_brother_last_name: String,
-    pub third_brother_first_name: String,
-    pub third_brother_last_name: String,
+    // the brothers interviewing the rushee, up to the configured panel size
+    #[serde(default)]
+    pub panel: Vec<PISBrother>,
     pub flex_window: bool,
 }
 
@@ -39,4 +84,446 @@ pub struct PISSignup {
 pub struct IncomingPISSignup {
     pub brother_first_name: String,
     pub brother_last_name: String,
-}
\ No newline at end of file
+    pub role: Option<String>, // lead or note-taker
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq)]
+pub enum PanelRole {
+    Lead,
+    NoteTaker,
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq)]
+pub struct PISBrother {
+    pub first_name: String,
+    pub last_name: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub role: Option<PanelRole>,
+}
+
+// a brother the assignment engine can put on PIS panels
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct AssignmentBrother {
+    pub first_name: String,
+    pub last_name: String,
+    #[serde(default)]
+    pub freshman: bool,
+    pub available: Vec<DateTime>,
+    #[serde(default)]
+    pub referrals: Vec<String>, // gtids of rushees this brother referred
+    pub max_load: u32,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct IncomingAssignmentBrother {
+    pub first_name: String,
+    pub last_name: String,
+    #[serde(default)]
+    pub freshman: bool,
+    pub available: Vec<String>,
+    #[serde(default)]
+    pub referrals: Vec<String>,
+    pub max_load: Option<u32>, // overrides the default load cap for this brother
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct IncomingAssignmentRequest {
+    // leave empty to use the availability brothers submitted themselves
+    #[serde(default)]
+    pub brothers: Vec<IncomingAssignmentBrother>,
+    pub max_load: Option<u32>,
+    // keep brothers who already signed up for a PIS on its panel (default true)
+    pub keep_existing: Option<bool>,
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct PISAssignment {
+    pub rushee_gtid: String,
+    pub rushee_name: String,
+    pub time: DateTime,
+    pub brothers: Vec<PISBrother>,
+    #[serde(default)]
+    pub warnings: Vec<String>,
+}
+
+// the assignment being previewed, there's only ever one
+#[derive(Debug, Deserialize, Serialize)]
+pub struct PISAssignmentDraft {
+    #[serde(rename = "_id")]
+    pub id: String,
+    pub created_at: DateTime,
+    pub brothers: Vec<AssignmentBrother>,
+    pub assignments: Vec<PISAssignment>,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct IncomingAssignmentTweak {
+    pub rushee_gtid: String,
+    pub brothers: Vec<PISBrother>,
+}
+
+// when a brother can conduct interviews, one per brother
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct BrotherAvailability {
+    pub first_name: String,
+    pub last_name: String,
+    #[serde(default)]
+    pub freshman: bool,
+    pub available: Vec<DateTime>,
+    #[serde(default)]
+    pub referrals: Vec<String>,
+    #[serde(default)]
+    pub max_load: Option<u32>,
+    pub updated_at: DateTime,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct IncomingBrotherAvailability {
+    pub first_name: String,
+    pub last_name: String,
+    #[serde(default)]
+    pub freshman: bool,
+    pub available: Vec<String>,
+    #[serde(default)]
+    pub referrals: Vec<String>,
+    pub max_load: Option<u32>,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct IncomingAvailabilityEdit {
+    pub first_name: String,
+    pub last_name: String,
+    #[serde(default)]
+    pub add: Vec<String>,
+    #[serde(default)]
+    pub remove: Vec<String>,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct TimeslotAvailability {
+    pub time: DateTime,
+    pub num_available: i32, // open seats rushees can still book
+    pub booked: u32,
+    pub brothers_free: Vec<String>,
+    // how many PIS the free brothers could cover, and how many seats to add (or remove, if negative)
+    // with add_pis_timeslot / delete_pis_timeslot to match
+    pub supportable: u32,
+    pub suggested_change: i32,
+}
+
+// one per time a rushee's PIS was moved
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct PISReschedule {
+    pub from: DateTime,
+    pub to: DateTime,
+    pub rescheduled_at: DateTime,
+    #[serde(default)]
+    pub requested_by: Option<String>,
+    #[serde(default)]
+    pub reason: Option<String>,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct IncomingReschedule {
+    pub time: String,
+    pub requested_by: Option<String>,
+    pub reason: Option<String>,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct PISNotification {
+    pub first_name: String,
+    pub last_name: String,
+    pub rushee_gtid: String,
+    pub message: String,
+    pub time: DateTime,
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq)]
+pub enum WaitlistStatus {
+    Waiting,
+    Offered,  // a seat is being held for the rushee until the hold expires
+    Accepted,
+    Expired,
+    Cancelled,
+}
+
+// a rushee waiting for a seat at a full PIS timeslot
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct PISWaitlistEntry {
+    #[serde(rename = "_id", default, skip_serializing_if = "Option::is_none")]
+    pub id: Option<ObjectId>,
+    pub gtid: String,
+    pub time: DateTime,
+    pub joined_at: DateTime,
+    pub status: WaitlistStatus,
+    #[serde(default)]
+    pub offered_at: Option<DateTime>,
+    #[serde(default)]
+    pub hold_expires_at: Option<DateTime>,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct IncomingWaitlist {
+    pub access_code: String,
+    pub times: Vec<String>,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct IncomingWaitlistOffer {
+    pub access_code: String,
+    pub time: String,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct WaitlistDepth {
+    pub time: DateTime,
+    pub num_available: i32,
+    pub waiting: u32,
+    pub held: u32,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct IncomingBlackout {
+    pub start: String,
+    pub end: String,
+}
+
+// generates every timeslot between start_date and end_date, from daily_start to daily_end each day
+#[derive(Debug, Deserialize, Serialize)]
+pub struct IncomingTimeslotGeneration {
+    pub start_date: String,  // "2025-09-08"
+    pub end_date: String,
+    pub daily_start: String, // "18:00", in the chapter's timezone
+    pub daily_end: String,
+    pub interval_minutes: u32,
+    pub capacity: i32,
+    #[serde(default)]
+    pub blackouts: Vec<IncomingBlackout>,
+    // without this, timeslots that already exist are left alone
+    #[serde(default)]
+    pub overwrite: bool,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct CalendarParams {
+    pub from: Option<String>,
+    pub to: Option<String>,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct PISCalendarBooking {
+    pub rushee_gtid: String,
+    pub rushee_name: String,
+    pub brothers: Vec<PISBrother>,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct PISCalendarSlot {
+    pub time: DateTime,
+    pub capacity: i32, // open seats, booked seats and seats held for the waitlist
+    pub num_available: i32,
+    pub booked: u32,
+    pub held: u32,
+    pub bookings: Vec<PISCalendarBooking>,
+    pub brothers: Vec<PISBrother>, // everyone interviewing in this timeslot
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq)]
+pub enum FlexStatus {
+    Queued,
+    Placed,
+    Withdrawn,
+}
+
+// a flex-window rushee waiting for an admin to give them a PIS time
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct PISFlexEntry {
+    pub gtid: String,
+    pub rushee_name: String,
+    pub joined_at: DateTime,
+    // times the rushee said they could do, empty if any time works
+    #[serde(default)]
+    pub available: Vec<DateTime>,
+    pub status: FlexStatus,
+    #[serde(default)]
+    pub time: Option<DateTime>,
+    #[serde(default)]
+    pub placed_at: Option<DateTime>,
+    #[serde(default)]
+    pub placed_by: Option<String>,
+    #[serde(default)]
+    pub confirmation: Option<String>, // shown to the rushee once they're placed
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct IncomingFlexPlacement {
+    pub time: String,
+    pub placed_by: String,
+}
+
+// a timeslot a flex-window rushee could be placed into
+#[derive(Debug, Deserialize, Serialize)]
+pub struct FlexPlacementOption {
+    pub time: DateTime,
+    pub num_available: i32,
+    pub brothers_free: Vec<String>, // brothers available at this time who aren't on another PIS then
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct FlexQueueEntry {
+    pub entry: PISFlexEntry,
+    pub options: Vec<FlexPlacementOption>,
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq)]
+pub enum Recommendation {
+    StrongYes,
+    Yes,
+    Neutral,
+    No,
+    StrongNo,
+}
+
+// a PIS interviewer's notes and rubric score for one question
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct ScorecardEntry {
+    pub question_id: String,
+    pub question: String, // the wording when the scorecard was written
+    pub version: u32,
+    #[serde(default)]
+    pub notes: String,
+    #[serde(default)]
+    pub score: Option<u32>,
+}
+
+// one per brother on a rushee's PIS panel, separate from rush night comment ratings
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct PISScorecard {
+    pub first_name: String,
+    pub last_name: String,
+    pub entries: Vec<ScorecardEntry>,
+    pub recommendation: Recommendation,
+    pub submitted_at: DateTime,
+    pub updated_at: DateTime,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct IncomingScorecardEntry {
+    pub question_id: String,
+    #[serde(default)]
+    pub notes: String,
+    pub score: Option<u32>,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct IncomingScorecard {
+    pub first_name: String,
+    pub last_name: String,
+    pub entries: Vec<IncomingScorecardEntry>,
+    pub recommendation: String, // strong-yes, yes, neutral, no or strong-no
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone, Default)]
+pub struct RecommendationCounts {
+    pub strong_yes: u32,
+    pub yes: u32,
+    pub neutral: u32,
+    pub no: u32,
+    pub strong_no: u32,
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct PISQuestionSummary {
+    pub question_id: String,
+    pub question: String,
+    pub average_score: Option<f64>,
+    pub num_scores: u32,
+}
+
+// the rushee's scorecards rolled up, recomputed whenever a scorecard is submitted
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct PISSummary {
+    pub num_scorecards: u32,
+    pub average_score: Option<f64>, // over every rubric score on every scorecard
+    pub questions: Vec<PISQuestionSummary>,
+    pub recommendations: RecommendationCounts,
+    pub missing: Vec<String>, // panel members who haven't submitted a scorecard yet
+    pub updated_at: DateTime,
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq)]
+pub enum LocationKind {
+    Room,
+    Virtual,
+}
+
+// a room or meeting link PIS can be held in
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct PISLocation {
+    #[serde(rename = "_id", default, skip_serializing_if = "Option::is_none")]
+    pub id: Option<ObjectId>,
+    pub name: String,
+    pub kind: LocationKind,
+    #[serde(default)]
+    pub link: String, // the meeting URL, or directions to the room
+    #[serde(default)]
+    pub meeting_id: String,
+    pub capacity: u32, // how many PIS it can host at once
+    #[serde(default)]
+    pub retired: bool,
+    // bumped on every booking so two bookings at once can't both take the last spot
+    #[serde(default)]
+    pub revision: u32,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct IncomingPISLocation {
+    pub name: String,
+    pub kind: String, // room or virtual
+    pub link: Option<String>,
+    pub meeting_id: Option<String>,
+    pub capacity: Option<u32>,
+}
+
+// where a rushee's PIS is being held
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct PISLocationAssignment {
+    pub location_id: String,
+    pub name: String,
+    pub kind: LocationKind,
+    pub link: String,
+    pub meeting_id: String,
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq)]
+pub enum PISOutcome {
+    Completed,
+    NoShow,
+    Cancelled,
+}
+
+// whether a scheduled PIS actually happened
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct PISStatusRecord {
+    pub status: PISOutcome,
+    pub time: DateTime, // the PIS this is about
+    pub marked_at: DateTime,
+    pub marked_by: String,
+    #[serde(default)]
+    pub note: Option<String>,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct IncomingPISStatus {
+    // the interviewer marking the PIS, admins can leave these out
+    #[serde(default)]
+    pub first_name: String,
+    #[serde(default)]
+    pub last_name: String,
+    pub status: String, // completed, no-show or cancelled
+    pub note: Option<String>,
+    // for no-shows, give the timeslot back and offer the rushee a new time
+    #[serde(default)]
+    pub release_slot: bool,
+}
@@ -0,0 +1,83 @@
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+use super::misc::{RsvpStatus, RushNight};
+
+#[derive(Debug, Deserialize, Serialize, Clone, Default)]
+pub struct BrotherParticipation {
+    pub brother_name: String,
+    pub total_comments: i32,
+    pub comments_per_night: BTreeMap<String, i32>,
+    pub rushees_met: i32,
+    pub average_rating: f32,
+    pub pis_assignments: i32,
+    pub votes_cast: i32,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ReportParams {
+    pub sort_by: Option<String>,
+    pub order: Option<String>, // asc or desc
+    pub format: Option<String>, // json or csv
+    pub brothers: Option<String>, // comma separated "First Last" names to include even if they haven't done anything
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CoverageParams {
+    pub min_comments: Option<usize>,
+    pub min_brothers: Option<usize>,
+    pub min_nights: Option<usize>,
+    pub max_suggestions: Option<usize>,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct RusheeCoverage {
+    pub gtid: String,
+    pub name: String,
+    pub comments: usize,
+    pub brothers: usize,
+    pub nights_attended: usize,
+    pub reasons: Vec<String>,
+    pub suggested_brothers: Vec<String>,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct NightAttendance {
+    pub night: RushNight,
+    pub total: usize,
+    pub first_time: usize,
+    pub returning: usize,
+    pub by_class: BTreeMap<String, usize>,
+    pub by_major: BTreeMap<String, usize>,
+    // how many of the previous night's attendees came back for this one
+    pub retained_from_previous: usize,
+    pub retention_rate: f32,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct InviteeAttendance {
+    pub gtid: String,
+    pub name: String,
+    pub rsvp: Option<RsvpStatus>, // None for rushees who came without an invitation
+    pub attended: bool,
+}
+
+#[derive(Debug, Deserialize, Serialize, Default)]
+pub struct RsvpCounts {
+    pub invited: usize,
+    pub attending: usize,
+    pub declined: usize,
+    pub pending: usize,
+    pub attending_and_came: usize,
+    pub attending_no_show: usize,
+    pub declined_but_came: usize,
+    pub pending_and_came: usize,
+    pub uninvited_and_came: usize,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct InvitationReconciliation {
+    pub night: RushNight,
+    pub counts: RsvpCounts,
+    pub rushees: Vec<InviteeAttendance>,
+}
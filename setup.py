@@ -125,14 +125,18 @@ with open("pis_questions.json", "r") as file:
 
     data = json.load(file)
 
-    for i in tqdm(range(len(data)), desc="Adding PIS Questions"):
-        response = requests.post(api_url + "/admin/add_pis_question", json=data[i])
-        
-        if response.status_code == 200:
-            if response.json().get("status") == "error":
-                errors.append(response.json().get("message"))
-        else:
-            errors.append(f"Some network error occurred while adding PIS Question {data[i]['question']}")
+    print("Importing PIS Questions...")
+    response = requests.post(
+        api_url + "/admin/import-pis-questions",
+        json=data,
+        headers={"x-rush-key": os.getenv("ADMIN_KEY", "")}
+    )
+
+    if response.status_code == 200:
+        if response.json().get("status") == "error":
+            errors.append(response.json().get("message"))
+    else:
+        errors.append("Some network error occurred while importing the PIS Questions")
 
 
 if len(errors) > 0: